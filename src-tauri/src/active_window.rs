@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+
+/// Title of the foreground window, used to auto-name recordings
+/// ClipForge's own windows are skipped, since it is usually in front when recording starts
+/// Returns None when no title is available (no focused window, missing permission, unsupported platform)
+pub fn active_window_title() -> Option<String> {
+    let title = platform::foreground_window_title()?;
+    let title = title.trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+/// An on-screen application window that can be recorded on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowInfo {
+    pub window_id: u32,
+    pub title: String,
+    pub owner_name: String, // Application that owns the window
+    pub x: f64, // Bounds in points, relative to the top-left of the main display
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Geometry of a display; avfoundation lists the main display as "Capture screen 0"
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayGeometry {
+    pub width: f64, // Points
+    pub height: f64,
+    pub scale: f64, // Captured pixels per point (2.0 on Retina displays)
+}
+
+/// Normal application windows currently on screen, front to back
+/// Empty on platforms without window enumeration
+pub fn list_windows() -> Vec<WindowInfo> {
+    platform::list_windows()
+}
+
+/// Look up a single on-screen window by its id
+pub fn find_window(window_id: u32) -> Option<WindowInfo> {
+    list_windows().into_iter().find(|window| window.window_id == window_id)
+}
+
+/// Size and pixel density of the main display
+pub fn main_display() -> Option<DisplayGeometry> {
+    platform::main_display()
+}
+
+/// Size and pixel density of the display avfoundation captures as "Capture screen N"
+/// Both number the active displays in CoreGraphics order, with the main display first
+pub fn display(screen_index: u32) -> Option<DisplayGeometry> {
+    platform::display(screen_index)
+}
+
+/// Refresh rate of "Capture screen N" in Hz; None when unknown (some built-in panels report 0)
+pub fn display_refresh_rate(screen_index: u32) -> Option<f64> {
+    platform::display_refresh_rate(screen_index)
+}
+
+/// Whether the app has been granted Screen Recording permission (checked without prompting)
+#[cfg(target_os = "macos")]
+pub fn screen_capture_allowed() -> bool {
+    platform::screen_capture_allowed()
+}
+
+/// Turn a window title into a safe file name stem
+/// Keeps letters, digits, spaces, '-' and '_', collapses everything else to a single '-'
+pub fn sanitize_filename(title: &str) -> String {
+    const MAX_LEN: usize = 80;
+
+    let mut name = String::new();
+    for c in title.chars() {
+        if c.is_alphanumeric() || c == ' ' || c == '_' || c == '-' {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+
+    let name: String = name.chars().take(MAX_LEN).collect();
+    name.trim_matches(|c: char| c == '-' || c == '_' || c.is_whitespace()).to_string()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::{c_char, c_void};
+
+    type CFTypeRef = *const c_void;
+    type CFIndex = isize;
+
+    use super::{DisplayGeometry, WindowInfo};
+
+    const K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY: u32 = 1;
+    const K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS: u32 = 1 << 4;
+    const K_CG_NULL_WINDOW_ID: u32 = 0;
+    const K_CF_NUMBER_SINT32_TYPE: CFIndex = 3;
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        static kCGWindowLayer: CFTypeRef;
+        static kCGWindowName: CFTypeRef;
+        static kCGWindowOwnerName: CFTypeRef;
+        static kCGWindowOwnerPID: CFTypeRef;
+        static kCGWindowNumber: CFTypeRef;
+        static kCGWindowBounds: CFTypeRef;
+        fn CGWindowListCopyWindowInfo(option: u32, relative_to_window: u32) -> CFTypeRef;
+        fn CGRectMakeWithDictionaryRepresentation(dict: CFTypeRef, rect: *mut CGRect) -> bool;
+        fn CGMainDisplayID() -> u32;
+        fn CGGetActiveDisplayList(max_displays: u32, displays: *mut u32, display_count: *mut u32) -> i32;
+        fn CGDisplayBounds(display: u32) -> CGRect;
+        fn CGDisplayCopyDisplayMode(display: u32) -> CFTypeRef;
+        fn CGDisplayModeGetWidth(mode: CFTypeRef) -> usize;
+        fn CGDisplayModeGetPixelWidth(mode: CFTypeRef) -> usize;
+        fn CGDisplayModeGetRefreshRate(mode: CFTypeRef) -> f64;
+        fn CGDisplayModeRelease(mode: CFTypeRef);
+        fn CGPreflightScreenCaptureAccess() -> bool;
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CGSize {
+        width: f64,
+        height: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CGRect {
+        origin: CGPoint,
+        size: CGSize,
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFArrayGetCount(array: CFTypeRef) -> CFIndex;
+        fn CFArrayGetValueAtIndex(array: CFTypeRef, index: CFIndex) -> CFTypeRef;
+        fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+        fn CFNumberGetValue(number: CFTypeRef, the_type: CFIndex, value_ptr: *mut c_void) -> bool;
+        fn CFStringGetLength(string: CFTypeRef) -> CFIndex;
+        fn CFStringGetMaximumSizeForEncoding(length: CFIndex, encoding: u32) -> CFIndex;
+        fn CFStringGetCString(string: CFTypeRef, buffer: *mut c_char, buffer_size: CFIndex, encoding: u32) -> bool;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    /// Walk the on-screen window list (front to back) and return the first normal window's title
+    /// that belongs to another app
+    /// Window names require Screen Recording permission; without it we fall back to the owning app's name
+    pub fn foreground_window_title() -> Option<String> {
+        unsafe {
+            let windows = CGWindowListCopyWindowInfo(
+                K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY | K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS,
+                K_CG_NULL_WINDOW_ID,
+            );
+            if windows.is_null() {
+                return None;
+            }
+
+            let mut title = None;
+            for i in 0..CFArrayGetCount(windows) {
+                let window = CFArrayGetValueAtIndex(windows, i);
+
+                // Layer 0 is regular app windows; skip the menu bar, dock, overlays, etc.
+                let layer_ref = CFDictionaryGetValue(window, kCGWindowLayer);
+                let mut layer: i32 = -1;
+                if layer_ref.is_null()
+                    || !CFNumberGetValue(layer_ref, K_CF_NUMBER_SINT32_TYPE, &mut layer as *mut i32 as *mut c_void)
+                    || layer != 0
+                {
+                    continue;
+                }
+                if cf_i32(CFDictionaryGetValue(window, kCGWindowOwnerPID)) == Some(std::process::id() as i32) {
+                    continue;
+                }
+
+                title = cf_string(CFDictionaryGetValue(window, kCGWindowName))
+                    .filter(|name| !name.trim().is_empty())
+                    .or_else(|| cf_string(CFDictionaryGetValue(window, kCGWindowOwnerName)));
+                break;
+            }
+
+            CFRelease(windows);
+            title
+        }
+    }
+
+    /// All layer-0 windows with their ids and bounds
+    /// Titles are empty without Screen Recording permission, so the owning app's name is kept alongside
+    pub fn list_windows() -> Vec<WindowInfo> {
+        unsafe {
+            let windows = CGWindowListCopyWindowInfo(
+                K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY | K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS,
+                K_CG_NULL_WINDOW_ID,
+            );
+            if windows.is_null() {
+                return Vec::new();
+            }
+
+            let mut result = Vec::new();
+            for i in 0..CFArrayGetCount(windows) {
+                let window = CFArrayGetValueAtIndex(windows, i);
+
+                let (Some(layer), Some(window_id)) = (
+                    cf_i32(CFDictionaryGetValue(window, kCGWindowLayer)),
+                    cf_i32(CFDictionaryGetValue(window, kCGWindowNumber)),
+                ) else {
+                    continue;
+                };
+                if layer != 0 {
+                    continue;
+                }
+
+                let bounds_ref = CFDictionaryGetValue(window, kCGWindowBounds);
+                let mut bounds = CGRect::default();
+                if bounds_ref.is_null() || !CGRectMakeWithDictionaryRepresentation(bounds_ref, &mut bounds) {
+                    continue;
+                }
+
+                result.push(WindowInfo {
+                    window_id: window_id as u32,
+                    title: cf_string(CFDictionaryGetValue(window, kCGWindowName)).unwrap_or_default(),
+                    owner_name: cf_string(CFDictionaryGetValue(window, kCGWindowOwnerName)).unwrap_or_default(),
+                    x: bounds.origin.x,
+                    y: bounds.origin.y,
+                    width: bounds.size.width,
+                    height: bounds.size.height,
+                });
+            }
+
+            CFRelease(windows);
+            result
+        }
+    }
+
+    // Upper bound on active displays looked up for a screen index
+    const MAX_DISPLAYS: usize = 16;
+
+    pub fn main_display() -> Option<DisplayGeometry> {
+        display_geometry(unsafe { CGMainDisplayID() })
+    }
+
+    pub fn display(screen_index: u32) -> Option<DisplayGeometry> {
+        display_geometry(active_display_id(screen_index)?)
+    }
+
+    /// CoreGraphics id of the Nth active display, the one avfoundation calls "Capture screen N"
+    fn active_display_id(screen_index: u32) -> Option<u32> {
+        let mut displays = [0u32; MAX_DISPLAYS];
+        let mut count = 0u32;
+        let error = unsafe { CGGetActiveDisplayList(MAX_DISPLAYS as u32, displays.as_mut_ptr(), &mut count) };
+        if error != 0 {
+            return None;
+        }
+        displays[..count as usize].get(screen_index as usize).copied()
+    }
+
+    fn display_geometry(display: u32) -> Option<DisplayGeometry> {
+        unsafe {
+            let bounds = CGDisplayBounds(display);
+            let mode = CGDisplayCopyDisplayMode(display);
+            if mode.is_null() {
+                return None;
+            }
+            let points = CGDisplayModeGetWidth(mode);
+            let pixels = CGDisplayModeGetPixelWidth(mode);
+            CGDisplayModeRelease(mode);
+
+            if points == 0 {
+                return None;
+            }
+            Some(DisplayGeometry {
+                width: bounds.size.width,
+                height: bounds.size.height,
+                scale: pixels as f64 / points as f64,
+            })
+        }
+    }
+
+    pub fn display_refresh_rate(screen_index: u32) -> Option<f64> {
+        let display = active_display_id(screen_index)?;
+        unsafe {
+            let mode = CGDisplayCopyDisplayMode(display);
+            if mode.is_null() {
+                return None;
+            }
+            let refresh_rate = CGDisplayModeGetRefreshRate(mode);
+            CGDisplayModeRelease(mode);
+
+            (refresh_rate > 0.0).then_some(refresh_rate)
+        }
+    }
+
+    /// Available since macOS 10.15
+    pub fn screen_capture_allowed() -> bool {
+        unsafe { CGPreflightScreenCaptureAccess() }
+    }
+
+    unsafe fn cf_i32(number: CFTypeRef) -> Option<i32> {
+        let mut value: i32 = 0;
+        if number.is_null() || !CFNumberGetValue(number, K_CF_NUMBER_SINT32_TYPE, &mut value as *mut i32 as *mut c_void) {
+            return None;
+        }
+        Some(value)
+    }
+
+    unsafe fn cf_string(string: CFTypeRef) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        // Sized for the worst case so long titles aren't dropped; the extra byte is the NUL terminator
+        // (kCFNotFound, i.e. negative, means the size would overflow)
+        let max_size = CFStringGetMaximumSizeForEncoding(CFStringGetLength(string), K_CF_STRING_ENCODING_UTF8);
+        let buffer_size = usize::try_from(max_size).ok()?.checked_add(1)?;
+        let mut buffer = vec![0 as c_char; buffer_size];
+        if !CFStringGetCString(string, buffer.as_mut_ptr(), buffer.len() as CFIndex, K_CF_STRING_ENCODING_UTF8) {
+            return None;
+        }
+        Some(std::ffi::CStr::from_ptr(buffer.as_ptr()).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+
+    use super::{DisplayGeometry, WindowInfo};
+
+    const GW_HWNDNEXT: u32 = 2;
+    // Stop walking the z-order after this many windows
+    const MAX_WINDOWS_CHECKED: usize = 256;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> *mut c_void;
+        fn GetWindow(hwnd: *mut c_void, cmd: u32) -> *mut c_void;
+        fn GetWindowTextW(hwnd: *mut c_void, text: *mut u16, max_count: i32) -> i32;
+        fn GetWindowThreadProcessId(hwnd: *mut c_void, process_id: *mut u32) -> u32;
+        fn IsWindowVisible(hwnd: *mut c_void) -> i32;
+    }
+
+    /// Start at the foreground window and walk down the z-order past ClipForge's own windows
+    pub fn foreground_window_title() -> Option<String> {
+        unsafe {
+            let mut hwnd = GetForegroundWindow();
+            for _ in 0..MAX_WINDOWS_CHECKED {
+                if hwnd.is_null() {
+                    return None;
+                }
+
+                let mut process_id = 0u32;
+                GetWindowThreadProcessId(hwnd, &mut process_id);
+                if process_id != std::process::id() && IsWindowVisible(hwnd) != 0 {
+                    let mut buffer = [0u16; 512];
+                    let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+                    if len > 0 {
+                        return Some(String::from_utf16_lossy(&buffer[..len as usize]));
+                    }
+                }
+
+                hwnd = GetWindow(hwnd, GW_HWNDNEXT);
+            }
+            None
+        }
+    }
+
+    /// Window capture relies on avfoundation, so only macOS enumerates windows
+    pub fn list_windows() -> Vec<WindowInfo> {
+        Vec::new()
+    }
+
+    pub fn main_display() -> Option<DisplayGeometry> {
+        None
+    }
+
+    pub fn display(_screen_index: u32) -> Option<DisplayGeometry> {
+        None
+    }
+
+    pub fn display_refresh_rate(_screen_index: u32) -> Option<f64> {
+        None
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::process::Command;
+
+    use super::{DisplayGeometry, WindowInfo};
+
+    /// X11 only, via xdotool; Wayland compositors don't expose the focused window
+    /// When ClipForge itself is focused, the topmost other window from the window manager's
+    /// stacking order (xprop) is used instead
+    pub fn foreground_window_title() -> Option<String> {
+        let active = xdotool(&["getactivewindow"])?;
+        if !is_own_window(&active) {
+            return xdotool(&["getwindowname", &active]);
+        }
+
+        // _NET_CLIENT_LIST_STACKING(WINDOW): window id # 0x1e00003, 0x2400007, ... (bottom to top)
+        let output = Command::new("xprop")
+            .arg("-root")
+            .arg("_NET_CLIENT_LIST_STACKING")
+            .output()
+            .ok()?;
+        let stacking = String::from_utf8_lossy(&output.stdout).into_owned();
+        let (_, ids) = stacking.split_once('#')?;
+        ids.split(',')
+            .rev()
+            .filter_map(|id| u64::from_str_radix(id.trim().trim_start_matches("0x"), 16).ok())
+            .map(|id| id.to_string())
+            .filter(|id| !is_own_window(id))
+            .find_map(|id| xdotool(&["getwindowname", &id]).filter(|name| !name.trim().is_empty()))
+    }
+
+    fn is_own_window(window: &str) -> bool {
+        xdotool(&["getwindowpid", window]).and_then(|pid| pid.trim().parse::<u32>().ok()) == Some(std::process::id())
+    }
+
+    fn xdotool(args: &[&str]) -> Option<String> {
+        let output = Command::new("xdotool").args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+    }
+
+    /// Window capture relies on avfoundation, so only macOS enumerates windows
+    pub fn list_windows() -> Vec<WindowInfo> {
+        Vec::new()
+    }
+
+    pub fn main_display() -> Option<DisplayGeometry> {
+        None
+    }
+
+    pub fn display(_screen_index: u32) -> Option<DisplayGeometry> {
+        None
+    }
+
+    pub fn display_refresh_rate(_screen_index: u32) -> Option<f64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_filename_collapses_punctuation() {
+        assert_eq!(sanitize_filename("main.rs — ClipForge"), "main-rs - ClipForge");
+        assert_eq!(sanitize_filename("a/b\\c::d"), "a-b-c-d");
+        assert_eq!(sanitize_filename("Café_notes 2"), "Café_notes 2");
+    }
+
+    #[test]
+    fn sanitize_filename_trims_and_caps_the_length() {
+        assert_eq!(sanitize_filename("  *Untitled*  "), "Untitled");
+        assert_eq!(sanitize_filename("???"), "");
+        assert_eq!(sanitize_filename(&"x".repeat(200)).len(), 80);
+    }
+}
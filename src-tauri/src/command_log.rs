@@ -0,0 +1,47 @@
+use std::io;
+use std::process::{Child, Command, Output};
+
+/// Program and arguments of a built command, e.g. for logging or showing a dry run to the user
+pub fn command_argv(cmd: &Command) -> Vec<String> {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect()
+}
+
+/// `output`/`spawn` that record the full argv (and, for `output`, the result) in the app log
+pub trait LoggedCommand {
+    fn logged_output(&mut self) -> io::Result<Output>;
+    fn logged_spawn(&mut self) -> io::Result<Child>;
+}
+
+impl LoggedCommand for Command {
+    fn logged_output(&mut self) -> io::Result<Output> {
+        let argv = command_argv(self).join(" ");
+        log::info!("Running: {}", argv);
+
+        let result = self.output();
+        match &result {
+            Ok(output) if output.status.success() => {
+                log::info!("Finished ({}): {}\n{}", output.status, argv, String::from_utf8_lossy(&output.stderr));
+            }
+            Ok(output) => {
+                log::warn!("Failed ({}): {}\n{}", output.status, argv, String::from_utf8_lossy(&output.stderr));
+            }
+            Err(e) => log::error!("Could not run {}: {}", argv, e),
+        }
+        result
+    }
+
+    fn logged_spawn(&mut self) -> io::Result<Child> {
+        let argv = command_argv(self).join(" ");
+        log::info!("Spawning: {}", argv);
+
+        let result = self.spawn();
+        match &result {
+            Ok(child) => log::info!("Spawned pid {}", child.id()),
+            Err(e) => log::error!("Could not spawn {}: {}", argv, e),
+        }
+        result
+    }
+}
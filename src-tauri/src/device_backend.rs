@@ -0,0 +1,307 @@
+use std::process::Command;
+
+use crate::command_log::LoggedCommand;
+use crate::ClipForgeError;
+
+/// A capture device as reported by the platform's FFmpeg input device
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureDevice {
+    pub index: u32, // avfoundation device number on macOS; position in the listing elsewhere
+    pub name: String,
+}
+
+/// Video and audio capture devices available right now
+#[derive(Debug, Clone, Default)]
+pub struct DeviceListing {
+    pub video: Vec<CaptureDevice>,
+    pub audio: Vec<CaptureDevice>,
+}
+
+/// Device enumeration for one FFmpeg input device family
+/// Each platform prints its device list in a different format, so the parsing lives with the backend
+pub trait DeviceBackend: Sync {
+    /// FFmpeg input device name(s), for logs and errors
+    fn name(&self) -> &'static str;
+
+    /// Ask FFmpeg for the connected devices
+    fn list_devices(&self) -> Result<DeviceListing, ClipForgeError>;
+}
+
+/// Backend for the OS this build targets
+pub fn current() -> &'static dyn DeviceBackend {
+    match std::env::consts::OS {
+        "macos" => &AvFoundation,
+        "windows" => &DirectShow,
+        _ => &AlsaV4l2,
+    }
+}
+
+/// macOS: avfoundation numbers cameras and "Capture screen N" entries, then audio devices,
+/// and the number is what -i takes
+pub struct AvFoundation;
+
+impl DeviceBackend for AvFoundation {
+    fn name(&self) -> &'static str {
+        "avfoundation"
+    }
+
+    fn list_devices(&self) -> Result<DeviceListing, ClipForgeError> {
+        // FFmpeg prints the listing to stderr and then fails on the empty input
+        let stderr = run_ffmpeg(&["-f", "avfoundation", "-list_devices", "true", "-i", ""])?;
+        Ok(parse_avfoundation_listing(&stderr))
+    }
+}
+
+/// Parse `-f avfoundation -list_devices true` output
+fn parse_avfoundation_listing(stderr: &str) -> DeviceListing {
+    // [AVFoundation indev @ ...] AVFoundation video devices:
+    // [AVFoundation indev @ ...] [0] FaceTime HD Camera
+    // [AVFoundation indev @ ...] [4] Capture screen 0
+    // [AVFoundation indev @ ...] AVFoundation audio devices:
+    // [AVFoundation indev @ ...] [0] MacBook Air Microphone
+    let mut listing = DeviceListing::default();
+    let mut section: Option<&mut Vec<CaptureDevice>> = None;
+
+    for line in stderr.lines() {
+        if line.contains("AVFoundation video devices:") {
+            section = Some(&mut listing.video);
+            continue;
+        }
+        if line.contains("AVFoundation audio devices:") {
+            section = Some(&mut listing.audio);
+            continue;
+        }
+        let Some(devices) = section.as_deref_mut() else {
+            continue;
+        };
+        if !line.contains("[AVFoundation indev") {
+            continue;
+        }
+
+        // The index is the bracket pair right after the prefix: [AVFoundation indev @ ...] [INDEX] NAME
+        // Names can contain brackets of their own, e.g. "USB Mic [Rode]"
+        let Some((index, name)) = line
+            .split_once("] ")
+            .and_then(|(_, message)| message.strip_prefix('['))
+            .and_then(|message| message.split_once(']'))
+            .and_then(|(index, name)| Some((index.parse::<u32>().ok()?, name.trim())))
+        else {
+            continue;
+        };
+        if !name.is_empty() {
+            devices.push(CaptureDevice { index, name: name.to_string() });
+        }
+    }
+
+    listing
+}
+
+/// Windows: dshow addresses devices by name ("video=Integrated Camera"), so indices are just
+/// positions in the listing
+pub struct DirectShow;
+
+impl DeviceBackend for DirectShow {
+    fn name(&self) -> &'static str {
+        "dshow"
+    }
+
+    fn list_devices(&self) -> Result<DeviceListing, ClipForgeError> {
+        let stderr = run_ffmpeg(&["-f", "dshow", "-list_devices", "true", "-i", "dummy"])?;
+        Ok(parse_dshow_listing(&stderr))
+    }
+}
+
+/// Parse `-f dshow -list_devices true` output, which comes in two shapes depending on the FFmpeg version
+fn parse_dshow_listing(stderr: &str) -> DeviceListing {
+    // FFmpeg 5+ gives the kind after each device:
+    // [dshow @ ...] "Integrated Camera" (video)
+    // Older versions give it as a section header:
+    // [dshow @ ...] DirectShow audio devices
+    // [dshow @ ...]  "Microphone (Realtek Audio)"
+    // Either way each device is followed by an "Alternative name" line, which is skipped
+    let mut listing = DeviceListing::default();
+    let mut section_is_audio: Option<bool> = None;
+
+    for line in stderr.lines() {
+        let Some(message) = line.split_once("] ").map(|(_, message)| message.trim()) else {
+            continue;
+        };
+        if message.starts_with("DirectShow video devices") {
+            section_is_audio = Some(false);
+            continue;
+        }
+        if message.starts_with("DirectShow audio devices") {
+            section_is_audio = Some(true);
+            continue;
+        }
+        if message.starts_with("Alternative name") {
+            continue;
+        }
+
+        let Some((name, kind)) = message.strip_prefix('"').and_then(|rest| rest.split_once('"')) else {
+            continue;
+        };
+        let is_audio = match kind.trim() {
+            "(video)" => false,
+            "(audio)" => true,
+            "" => match section_is_audio {
+                Some(is_audio) => is_audio,
+                None => continue,
+            },
+            // "(none)" marks devices FFmpeg can't capture from
+            _ => continue,
+        };
+
+        let devices = if is_audio { &mut listing.audio } else { &mut listing.video };
+        devices.push(CaptureDevice {
+            index: devices.len() as u32,
+            name: name.to_string(),
+        });
+    }
+
+    listing
+}
+
+/// Linux: cameras come from v4l2 and microphones from ALSA, each listed with `ffmpeg -sources`;
+/// the name is the device's description rather than its path ("/dev/video0", "hw:CARD=PCH,DEV=0")
+pub struct AlsaV4l2;
+
+impl DeviceBackend for AlsaV4l2 {
+    fn name(&self) -> &'static str {
+        "v4l2/alsa"
+    }
+
+    fn list_devices(&self) -> Result<DeviceListing, ClipForgeError> {
+        Ok(DeviceListing {
+            video: parse_sources_listing(&run_ffmpeg_stdout(&["-hide_banner", "-sources", "v4l2"])?),
+            audio: parse_sources_listing(&run_ffmpeg_stdout(&["-hide_banner", "-sources", "alsa"])?),
+        })
+    }
+}
+
+/// Parse `ffmpeg -sources DEVICE` output
+fn parse_sources_listing(stdout: &str) -> Vec<CaptureDevice> {
+    // Auto-detected sources for alsa:
+    // * default [Default ALSA Output (currently PulseAudio Sound Server)]
+    //   hw:CARD=PCH,DEV=0 [HDA Intel PCH, ALC3246 Analog]
+    // The leading "*" marks the default device
+    let mut devices = Vec::new();
+    for line in stdout.lines() {
+        let entry = line.trim_start().trim_start_matches('*').trim();
+        let Some((path, description)) = entry.split_once(" [") else {
+            continue;
+        };
+        let name = description.strip_suffix(']').unwrap_or(description).trim();
+        devices.push(CaptureDevice {
+            index: devices.len() as u32,
+            name: if name.is_empty() { path.trim() } else { name }.to_string(),
+        });
+    }
+    devices
+}
+
+/// Run FFmpeg for a device listing and return its stderr; the exit status is ignored since
+/// listing commands end by failing to open their dummy input
+fn run_ffmpeg(args: &[&str]) -> Result<String, ClipForgeError> {
+    let output = Command::new(crate::ffmpeg_binary())
+        .args(args)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+    Ok(String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+/// Like run_ffmpeg, but `-sources` writes its listing to stdout
+fn run_ffmpeg_stdout(args: &[&str]) -> Result<String, ClipForgeError> {
+    let output = Command::new(crate::ffmpeg_binary())
+        .args(args)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(index: u32, name: &str) -> CaptureDevice {
+        CaptureDevice { index, name: name.to_string() }
+    }
+
+    #[test]
+    fn avfoundation_listing_splits_video_and_audio_devices() {
+        let stderr = "\
+[AVFoundation indev @ 0x7fc8f4c04b40] AVFoundation video devices:
+[AVFoundation indev @ 0x7fc8f4c04b40] [0] FaceTime HD Camera
+[AVFoundation indev @ 0x7fc8f4c04b40] [1] Capture screen 0
+[AVFoundation indev @ 0x7fc8f4c04b40] [2] Capture screen 1
+[AVFoundation indev @ 0x7fc8f4c04b40] AVFoundation audio devices:
+[AVFoundation indev @ 0x7fc8f4c04b40] [0] MacBook Pro Microphone
+[AVFoundation indev @ 0x7fc8f4c04b40] [1] USB Mic [Rode]
+: Input/output error
+";
+        let listing = parse_avfoundation_listing(stderr);
+        assert_eq!(
+            listing.video,
+            vec![device(0, "FaceTime HD Camera"), device(1, "Capture screen 0"), device(2, "Capture screen 1")]
+        );
+        assert_eq!(listing.audio, vec![device(0, "MacBook Pro Microphone"), device(1, "USB Mic [Rode]")]);
+    }
+
+    #[test]
+    fn dshow_listing_with_the_kind_after_each_device() {
+        // FFmpeg 5+
+        let stderr = r#"[dshow @ 000001d5d8e4e940] "Integrated Camera" (video)
+[dshow @ 000001d5d8e4e940]   Alternative name "@device_pnp_\\?\usb#vid_04f2&pid_b6dd&mi_00#6&1a2b3c4d&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global"
+[dshow @ 000001d5d8e4e940] "Virtual Camera" (none)
+[dshow @ 000001d5d8e4e940]   Alternative name "@device_sw_{860BB310-5D01-11D0-BD3B-00A0C911CE86}\{A3FCE0F5-3493-419F-958A-ABA1250EC20B}"
+[dshow @ 000001d5d8e4e940] "Microphone Array (Realtek(R) Audio)" (audio)
+[dshow @ 000001d5d8e4e940]   Alternative name "@device_cm_{33D9A762-90C8-11D0-BD43-00A0C911CE86}\wave_{8F1C3E4B-0D4A-4C8E-9D7A-2B5E6F7A8B9C}"
+dummy: Immediate exit requested
+"#;
+        let listing = parse_dshow_listing(stderr);
+        assert_eq!(listing.video, vec![device(0, "Integrated Camera")]);
+        assert_eq!(listing.audio, vec![device(0, "Microphone Array (Realtek(R) Audio)")]);
+    }
+
+    #[test]
+    fn dshow_listing_with_section_headers() {
+        // FFmpeg 4 and older
+        let stderr = r#"[dshow @ 0000020b3f0ff4c0] DirectShow video devices (some may be both video and audio devices)
+[dshow @ 0000020b3f0ff4c0]  "Integrated Camera"
+[dshow @ 0000020b3f0ff4c0]     Alternative name "@device_pnp_\\?\usb#vid_04f2&pid_b6dd&mi_00#6&1a2b3c4d&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global"
+[dshow @ 0000020b3f0ff4c0]  "USB Video Device"
+[dshow @ 0000020b3f0ff4c0]     Alternative name "@device_pnp_\\?\usb#vid_046d&pid_0825&mi_00#7&2c3d4e5f&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global"
+[dshow @ 0000020b3f0ff4c0] DirectShow audio devices
+[dshow @ 0000020b3f0ff4c0]  "Microphone (Realtek Audio)"
+[dshow @ 0000020b3f0ff4c0]     Alternative name "@device_cm_{33D9A762-90C8-11D0-BD43-00A0C911CE86}\wave_{5A6B7C8D-9E0F-4A1B-8C2D-3E4F5A6B7C8D}"
+dummy: Immediate exit requested
+"#;
+        let listing = parse_dshow_listing(stderr);
+        assert_eq!(listing.video, vec![device(0, "Integrated Camera"), device(1, "USB Video Device")]);
+        assert_eq!(listing.audio, vec![device(0, "Microphone (Realtek Audio)")]);
+    }
+
+    #[test]
+    fn sources_listing_uses_descriptions_and_skips_the_header() {
+        let alsa = "\
+Auto-detected sources for alsa:
+* default [Default ALSA Output (currently PulseAudio Sound Server)]
+  hw:CARD=PCH,DEV=0 [HDA Intel PCH, ALC3246 Analog]
+  hw:CARD=Mic,DEV=0 []
+";
+        assert_eq!(
+            parse_sources_listing(alsa),
+            vec![
+                device(0, "Default ALSA Output (currently PulseAudio Sound Server)"),
+                device(1, "HDA Intel PCH, ALC3246 Analog"),
+                device(2, "hw:CARD=Mic,DEV=0"),
+            ]
+        );
+
+        let v4l2 = "\
+Auto-detected sources for video4linux2,v4l2:
+* /dev/video0 [Integrated Camera: Integrated C]
+";
+        assert_eq!(parse_sources_listing(v4l2), vec![device(0, "Integrated Camera: Integrated C")]);
+    }
+}
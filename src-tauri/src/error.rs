@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::CaptureMode;
+
+/// Error returned by every ClipForge command
+/// Serialized with a `kind` tag, e.g. `{ "kind": "ffmpeg_not_found", "message": "..." }`,
+/// so the frontend can branch on the failure type instead of matching message text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClipForgeError {
+    /// FFmpeg (or ffprobe) is not installed or could not be executed
+    FfmpegNotFound { message: String },
+    /// The OS denied access to a capture device or file
+    PermissionDenied { message: String },
+    /// FFmpeg could not be started
+    ProcessSpawnFailed { message: String },
+    /// FFmpeg started but exited with an error
+    ProcessFailed { message: String },
+    /// A recording or export finished without producing a usable file
+    OutputFileMissing { message: String },
+    /// A parameter was malformed or out of range
+    InvalidArgument { message: String },
+    /// A referenced file, recording, or device does not exist
+    NotFound { message: String },
+    /// The capture device rejected the requested framerate or size; lists the valid modes
+    UnsupportedCaptureMode {
+        message: String,
+        supported_modes: Vec<CaptureMode>,
+    },
+    /// Reading or writing a file failed (e.g. disk full)
+    Io { message: String },
+    /// The user cancelled the operation before it started
+    Cancelled { message: String },
+    /// Unexpected internal failure such as a poisoned lock
+    Internal { message: String },
+}
+
+impl ClipForgeError {
+    pub fn ffmpeg_not_found(message: impl Into<String>) -> Self {
+        ClipForgeError::FfmpegNotFound { message: message.into() }
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        ClipForgeError::PermissionDenied { message: message.into() }
+    }
+
+    pub fn process_failed(message: impl Into<String>) -> Self {
+        ClipForgeError::ProcessFailed { message: message.into() }
+    }
+
+    pub fn output_file_missing(message: impl Into<String>) -> Self {
+        ClipForgeError::OutputFileMissing { message: message.into() }
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        ClipForgeError::InvalidArgument { message: message.into() }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ClipForgeError::NotFound { message: message.into() }
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        ClipForgeError::Cancelled { message: message.into() }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ClipForgeError::Internal { message: message.into() }
+    }
+
+    /// Classify a failure to launch FFmpeg/ffprobe: a missing binary is reported as
+    /// `FfmpegNotFound`, anything else as `ProcessSpawnFailed`
+    pub fn spawn(message: impl Into<String>, error: &std::io::Error) -> Self {
+        let message = message.into();
+        match error.kind() {
+            std::io::ErrorKind::NotFound => ClipForgeError::FfmpegNotFound { message },
+            std::io::ErrorKind::PermissionDenied => ClipForgeError::PermissionDenied { message },
+            _ => ClipForgeError::ProcessSpawnFailed { message },
+        }
+    }
+
+    /// Classify a filesystem error by its kind
+    pub fn io(message: impl Into<String>, error: &std::io::Error) -> Self {
+        let message = message.into();
+        match error.kind() {
+            std::io::ErrorKind::NotFound => ClipForgeError::NotFound { message },
+            std::io::ErrorKind::PermissionDenied => ClipForgeError::PermissionDenied { message },
+            _ => ClipForgeError::Io { message },
+        }
+    }
+
+    /// Human-readable description of the error
+    pub fn message(&self) -> &str {
+        match self {
+            ClipForgeError::FfmpegNotFound { message }
+            | ClipForgeError::PermissionDenied { message }
+            | ClipForgeError::ProcessSpawnFailed { message }
+            | ClipForgeError::ProcessFailed { message }
+            | ClipForgeError::OutputFileMissing { message }
+            | ClipForgeError::InvalidArgument { message }
+            | ClipForgeError::NotFound { message }
+            | ClipForgeError::UnsupportedCaptureMode { message, .. }
+            | ClipForgeError::Io { message }
+            | ClipForgeError::Cancelled { message }
+            | ClipForgeError::Internal { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for ClipForgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for ClipForgeError {}
@@ -0,0 +1,608 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Mutex, MutexGuard};
+use tauri::Emitter;
+
+use crate::{ClipForgeError, ExportResult};
+
+// Jobs run at once unless changed with set_export_concurrency; FFmpeg already uses every core,
+// so running more than one mostly helps with many short copy/remux jobs
+const DEFAULT_EXPORT_CONCURRENCY: usize = 1;
+
+// Upper bound for set_export_concurrency
+const MAX_EXPORT_CONCURRENCY: usize = 8;
+
+// Finished jobs kept for list_export_jobs; the oldest are dropped first
+const MAX_FINISHED_EXPORT_JOBS: usize = 100;
+
+/// Export work queued with queue_export, or by the export commands themselves, which wait for their job;
+/// fields mirror the matching command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExportJobRequest {
+    Export {
+        source_path: String,
+        destination_path: String,
+    },
+    Transcode {
+        source_path: String,
+        output_path: String,
+        quality: Option<String>,
+        crf: Option<u32>,
+        normalize_audio: Option<bool>,
+        two_pass_loudnorm: Option<bool>,
+        fade_in_seconds: Option<f64>,
+        fade_out_seconds: Option<f64>,
+        scale_to: Option<String>,
+        video_codec: Option<String>,
+        pixel_format: Option<String>,
+        color_range: Option<String>,
+        audio_offset_ms: Option<i64>,
+        extra_args: Option<Vec<String>>,
+    },
+    ExtractAudio {
+        source_path: String,
+        output_path: String,
+        format: Option<String>,
+        extra_args: Option<Vec<String>>,
+    },
+    Remux {
+        source_path: String,
+        output_path: String,
+        container: String,
+        extra_args: Option<Vec<String>>,
+    },
+    ExportForWeb {
+        source_path: String,
+        output_path: String,
+        target_width: Option<u32>,
+        extra_args: Option<Vec<String>>,
+    },
+    Rotate {
+        source_path: String,
+        output_path: String,
+        degrees: u32,
+        metadata_only: Option<bool>,
+        extra_args: Option<Vec<String>>,
+    },
+    BurnSubtitles {
+        source_path: String,
+        subtitle_path: String,
+        output_path: String,
+        style: Option<String>,
+        extra_args: Option<Vec<String>>,
+    },
+    MobileSplit {
+        screen_path: String,
+        webcam_path: String,
+        output_path: String,
+        background_color: Option<String>,
+        audio_source: Option<String>,
+        extra_args: Option<Vec<String>>,
+    },
+    ExportGif {
+        source_path: String,
+        output_path: String,
+        fps: Option<u32>,
+        width: Option<u32>,
+        extra_args: Option<Vec<String>>,
+    },
+    StripMetadata {
+        source_path: String,
+        output_path: String,
+        keep: Vec<String>,
+        extra_args: Option<Vec<String>>,
+    },
+    ChapterThumbnails {
+        source_path: String,
+        chapters: Vec<f64>,
+        output_dir: String,
+        width: Option<u32>,
+        extra_args: Option<Vec<String>>,
+    },
+    Segment {
+        source_path: String,
+        segment_seconds: u64,
+        output_dir: String,
+        extra_args: Option<Vec<String>>,
+    },
+    WrapWithIntroOutro {
+        main_path: String,
+        intro_path: Option<String>,
+        outro_path: Option<String>,
+        output_path: String,
+        extra_args: Option<Vec<String>>,
+    },
+}
+
+type JobOutcome = Result<ExportResult, ClipForgeError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportJobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportJob {
+    pub id: u64,
+    pub request: ExportJobRequest,
+    pub status: ExportJobStatus,
+    pub result: Option<ExportResult>, // Set once the job completes
+    pub error: Option<ClipForgeError>, // Set if the job failed
+    pub queued_at: u64, // Unix seconds
+}
+
+/// Payload of the `export-progress` event, emitted when a job changes status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub job_id: u64,
+    pub status: ExportJobStatus,
+    pub queued_ahead: usize, // Jobs still waiting in front of this one; 0 once it runs
+}
+
+struct ExportQueue {
+    jobs: VecDeque<ExportJob>, // In submission order
+    waiters: HashMap<u64, Sender<JobOutcome>>, // Commands blocked on their job, by job ID
+    running: usize,
+    concurrency: usize,
+}
+
+impl ExportQueue {
+    fn new() -> Self {
+        ExportQueue {
+            jobs: VecDeque::new(),
+            waiters: HashMap::new(),
+            running: 0,
+            concurrency: DEFAULT_EXPORT_CONCURRENCY,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref EXPORT_QUEUE: Mutex<ExportQueue> = Mutex::new(ExportQueue::new());
+}
+
+static NEXT_EXPORT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What the queue bookkeeping needs from outside: where to report job changes and how to run a job
+/// The app emits Tauri events and runs FFmpeg; tests record the events and decide when jobs finish
+trait QueueHost: Clone + Send + 'static {
+    /// Called with the queue locked, so it must not touch the queue itself
+    fn progress(&self, progress: ExportProgress);
+    fn completed(&self, job: ExportJob);
+    fn run(&self, request: ExportJobRequest) -> JobOutcome;
+}
+
+impl QueueHost for tauri::AppHandle {
+    fn progress(&self, progress: ExportProgress) {
+        if let Err(e) = self.emit("export-progress", progress) {
+            log::error!("Failed to emit export-progress event: {}", e);
+        }
+    }
+
+    fn completed(&self, job: ExportJob) {
+        if let Err(e) = self.emit("export-complete", job) {
+            log::error!("Failed to emit export-complete event: {}", e);
+        }
+    }
+
+    fn run(&self, request: ExportJobRequest) -> JobOutcome {
+        run_request(request)
+    }
+}
+
+/// Lock a queue, recovering it if a panic poisoned the mutex
+fn lock(queue: &Mutex<ExportQueue>) -> MutexGuard<'_, ExportQueue> {
+    queue.lock().unwrap_or_else(|poisoned| {
+        log::warn!("Recovering export queue after a panic while it was locked");
+        queue.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Add a job to the queue and start it if a worker slot is free; returns the job ID
+pub fn enqueue(app: &tauri::AppHandle, request: ExportJobRequest) -> u64 {
+    push(&EXPORT_QUEUE, app, request, None)
+}
+
+/// Queue a job and return a receiver for its outcome, so a command can wait its turn
+/// Cancelling the job while it is queued delivers a Cancelled error
+pub fn submit(app: &tauri::AppHandle, request: ExportJobRequest) -> Receiver<JobOutcome> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    push(&EXPORT_QUEUE, app, request, Some(sender));
+    receiver
+}
+
+/// Queue a job and block until it has run
+pub fn run(app: &tauri::AppHandle, request: ExportJobRequest) -> JobOutcome {
+    wait(submit(app, request))
+}
+
+/// Block on a receiver from submit
+pub fn wait(receiver: Receiver<JobOutcome>) -> JobOutcome {
+    receiver
+        .recv()
+        .unwrap_or_else(|_| Err(ClipForgeError::internal("Export job ended without reporting a result")))
+}
+
+fn push<H: QueueHost>(
+    queue: &'static Mutex<ExportQueue>,
+    host: &H,
+    request: ExportJobRequest,
+    waiter: Option<Sender<JobOutcome>>,
+) -> u64 {
+    let id = NEXT_EXPORT_JOB_ID.fetch_add(1, Ordering::SeqCst);
+    let queued_ahead = {
+        let mut queue = lock(queue);
+        if let Some(waiter) = waiter {
+            queue.waiters.insert(id, waiter);
+        }
+        queue.jobs.push_back(ExportJob {
+            id,
+            request,
+            status: ExportJobStatus::Queued,
+            result: None,
+            error: None,
+            queued_at: crate::unix_timestamp(),
+        });
+        queue.jobs.iter().filter(|job| job.status == ExportJobStatus::Queued).count() - 1
+    };
+    log::info!("Queued export job {} ({} ahead)", id, queued_ahead);
+    host.progress(ExportProgress { job_id: id, status: ExportJobStatus::Queued, queued_ahead });
+
+    start_queued_jobs(queue, host);
+    id
+}
+
+/// Snapshot of every queued, running and recently finished job, oldest first
+pub fn list() -> Vec<ExportJob> {
+    lock(&EXPORT_QUEUE).jobs.iter().cloned().collect()
+}
+
+/// Remove a job that hasn't started yet
+/// A running job can't be cancelled since its FFmpeg process is owned by the export itself
+pub fn cancel(app: &tauri::AppHandle, job_id: u64) -> Result<(), ClipForgeError> {
+    cancel_job(&EXPORT_QUEUE, app, job_id)
+}
+
+fn cancel_job<H: QueueHost>(queue: &Mutex<ExportQueue>, host: &H, job_id: u64) -> Result<(), ClipForgeError> {
+    let waiter = {
+        let mut queue = lock(queue);
+        let job = queue.jobs.iter_mut().find(|job| job.id == job_id)
+            .ok_or_else(|| ClipForgeError::not_found(format!("Export job {} not found", job_id)))?;
+        match job.status {
+            ExportJobStatus::Queued => job.status = ExportJobStatus::Cancelled,
+            ExportJobStatus::Running => {
+                return Err(ClipForgeError::invalid_argument(format!("Export job {} is already running", job_id)));
+            }
+            _ => {
+                return Err(ClipForgeError::invalid_argument(format!("Export job {} has already finished", job_id)));
+            }
+        }
+        queue.waiters.remove(&job_id)
+    };
+    if let Some(waiter) = waiter {
+        let _ = waiter.send(Err(ClipForgeError::cancelled(format!("Export job {} was cancelled", job_id))));
+    }
+    log::info!("Cancelled export job {}", job_id);
+    host.progress(ExportProgress { job_id, status: ExportJobStatus::Cancelled, queued_ahead: 0 });
+    Ok(())
+}
+
+/// Change how many jobs run at once; extra slots are filled from the queue immediately,
+/// lowering it lets running jobs finish
+pub fn set_concurrency(app: &tauri::AppHandle, concurrency: usize) -> Result<(), ClipForgeError> {
+    set_queue_concurrency(&EXPORT_QUEUE, app, concurrency)
+}
+
+fn set_queue_concurrency<H: QueueHost>(
+    queue: &'static Mutex<ExportQueue>,
+    host: &H,
+    concurrency: usize,
+) -> Result<(), ClipForgeError> {
+    if concurrency == 0 || concurrency > MAX_EXPORT_CONCURRENCY {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Export concurrency must be between 1 and {} (got {})",
+            MAX_EXPORT_CONCURRENCY, concurrency
+        )));
+    }
+    lock(queue).concurrency = concurrency;
+    start_queued_jobs(queue, host);
+    Ok(())
+}
+
+/// Start queued jobs, oldest first, until every worker slot is busy
+fn start_queued_jobs<H: QueueHost>(queue: &'static Mutex<ExportQueue>, host: &H) {
+    let mut guard = lock(queue);
+    while guard.running < guard.concurrency {
+        let Some(job) = guard.jobs.iter_mut().find(|job| job.status == ExportJobStatus::Queued) else {
+            break;
+        };
+        job.status = ExportJobStatus::Running;
+        let (id, request) = (job.id, job.request.clone());
+        guard.running += 1;
+
+        log::info!("Starting export job {}", id);
+        host.progress(ExportProgress { job_id: id, status: ExportJobStatus::Running, queued_ahead: 0 });
+
+        let host = host.clone();
+        std::thread::spawn(move || {
+            let result = host.run(request);
+            finish_job(queue, &host, id, result);
+            start_queued_jobs(queue, &host);
+        });
+    }
+}
+
+fn run_request(request: ExportJobRequest) -> JobOutcome {
+    match request {
+        ExportJobRequest::Export { source_path, destination_path } => crate::run_export_video(source_path, destination_path),
+        ExportJobRequest::Transcode {
+            source_path,
+            output_path,
+            quality,
+            crf,
+            normalize_audio,
+            two_pass_loudnorm,
+            fade_in_seconds,
+            fade_out_seconds,
+            scale_to,
+            video_codec,
+            pixel_format,
+            color_range,
+            audio_offset_ms,
+            extra_args,
+        } => crate::run_transcode_video(
+            source_path,
+            output_path,
+            quality,
+            crf,
+            normalize_audio,
+            two_pass_loudnorm,
+            fade_in_seconds,
+            fade_out_seconds,
+            scale_to,
+            video_codec,
+            pixel_format,
+            color_range,
+            audio_offset_ms,
+            extra_args,
+        ),
+        ExportJobRequest::ExtractAudio { source_path, output_path, format, extra_args } => {
+            crate::run_extract_audio(source_path, output_path, format, extra_args)
+        }
+        ExportJobRequest::Remux { source_path, output_path, container, extra_args } => {
+            crate::run_remux_video(source_path, output_path, container, extra_args)
+        }
+        ExportJobRequest::ExportForWeb { source_path, output_path, target_width, extra_args } => {
+            crate::run_export_for_web(source_path, output_path, target_width, extra_args)
+        }
+        ExportJobRequest::Rotate { source_path, output_path, degrees, metadata_only, extra_args } => {
+            crate::run_rotate_video(source_path, output_path, degrees, metadata_only, extra_args)
+        }
+        ExportJobRequest::BurnSubtitles { source_path, subtitle_path, output_path, style, extra_args } => {
+            crate::run_burn_subtitles(source_path, subtitle_path, output_path, style, extra_args)
+        }
+        ExportJobRequest::MobileSplit { screen_path, webcam_path, output_path, background_color, audio_source, extra_args } => {
+            crate::run_mobile_split(screen_path, webcam_path, output_path, background_color, audio_source, extra_args)
+        }
+        ExportJobRequest::ExportGif { source_path, output_path, fps, width, extra_args } => {
+            crate::run_export_gif(source_path, output_path, fps, width, extra_args)
+        }
+        ExportJobRequest::StripMetadata { source_path, output_path, keep, extra_args } => {
+            crate::run_strip_metadata(source_path, output_path, keep, extra_args)
+        }
+        ExportJobRequest::ChapterThumbnails { source_path, chapters, output_dir, width, extra_args } => {
+            crate::run_generate_chapter_thumbnails(source_path, chapters, output_dir, width, extra_args)
+        }
+        ExportJobRequest::Segment { source_path, segment_seconds, output_dir, extra_args } => {
+            crate::run_segment_video(source_path, segment_seconds, output_dir, extra_args)
+        }
+        ExportJobRequest::WrapWithIntroOutro { main_path, intro_path, outro_path, output_path, extra_args } => {
+            crate::run_wrap_with_intro_outro(main_path, intro_path, outro_path, output_path, extra_args)
+        }
+    }
+}
+
+/// Record a job's outcome, hand it to a waiting command, emit `export-complete` and drop the
+/// oldest finished jobs past the limit
+fn finish_job<H: QueueHost>(queue: &Mutex<ExportQueue>, host: &H, job_id: u64, result: JobOutcome) {
+    let finished = {
+        let mut queue = lock(queue);
+        queue.running -= 1;
+        if let Some(waiter) = queue.waiters.remove(&job_id) {
+            let _ = waiter.send(result.clone());
+        }
+        let finished = queue.jobs.iter_mut().find(|job| job.id == job_id).map(|job| {
+            match result {
+                Ok(result) => {
+                    job.status = ExportJobStatus::Completed;
+                    job.result = Some(result);
+                }
+                Err(error) => {
+                    job.status = ExportJobStatus::Failed;
+                    job.error = Some(error);
+                }
+            }
+            job.clone()
+        });
+
+        let finished_count = queue.jobs.iter().filter(|job| is_finished(job.status)).count();
+        let mut excess = finished_count.saturating_sub(MAX_FINISHED_EXPORT_JOBS);
+        queue.jobs.retain(|job| {
+            if excess > 0 && is_finished(job.status) {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+        finished
+    };
+
+    let Some(job) = finished else {
+        return;
+    };
+    match &job.error {
+        Some(error) => log::warn!("Export job {} failed: {}", job_id, error),
+        None => log::info!("Export job {} completed", job_id),
+    }
+    host.completed(job);
+}
+
+fn is_finished(status: ExportJobStatus) -> bool {
+    matches!(status, ExportJobStatus::Completed | ExportJobStatus::Failed | ExportJobStatus::Cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    // Stands in for the app: records progress events and runs each job until the test releases it
+    #[derive(Clone)]
+    struct TestHost {
+        release: Arc<Mutex<Receiver<()>>>,
+        active: Arc<AtomicUsize>,
+        max_active: Arc<AtomicUsize>,
+        runs: Arc<AtomicUsize>,
+        progress: Arc<Mutex<Vec<ExportProgress>>>,
+    }
+
+    impl TestHost {
+        /// Jobs block until a message arrives on the returned sender, or run straight through once it's dropped
+        fn new() -> (Self, Sender<()>) {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let host = TestHost {
+                release: Arc::new(Mutex::new(receiver)),
+                active: Arc::new(AtomicUsize::new(0)),
+                max_active: Arc::new(AtomicUsize::new(0)),
+                runs: Arc::new(AtomicUsize::new(0)),
+                progress: Arc::new(Mutex::new(Vec::new())),
+            };
+            (host, sender)
+        }
+    }
+
+    impl QueueHost for TestHost {
+        fn progress(&self, progress: ExportProgress) {
+            self.progress.lock().unwrap().push(progress);
+        }
+
+        fn completed(&self, _job: ExportJob) {}
+
+        fn run(&self, _request: ExportJobRequest) -> JobOutcome {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let _ = self.release.lock().unwrap().recv();
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(ExportResult { success: true, ..Default::default() })
+        }
+    }
+
+    // Each test gets its own queue, so they can run in parallel with each other and the app's
+    fn new_queue() -> &'static Mutex<ExportQueue> {
+        Box::leak(Box::new(Mutex::new(ExportQueue::new())))
+    }
+
+    fn submit_to(queue: &'static Mutex<ExportQueue>, host: &TestHost) -> (u64, Receiver<JobOutcome>) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let request = ExportJobRequest::Export { source_path: "in.mp4".to_string(), destination_path: "out.mp4".to_string() };
+        (push(queue, host, request, Some(sender)), receiver)
+    }
+
+    fn status(queue: &Mutex<ExportQueue>, job_id: u64) -> ExportJobStatus {
+        lock(queue).jobs.iter().find(|job| job.id == job_id).expect("job is listed").status
+    }
+
+    #[test]
+    fn cancelling_a_queued_job_tells_its_waiter_and_never_runs_it() {
+        let queue = new_queue();
+        let (host, release) = TestHost::new();
+        let (running, running_outcome) = submit_to(queue, &host);
+        let (queued, queued_outcome) = submit_to(queue, &host);
+        assert_eq!(status(queue, queued), ExportJobStatus::Queued);
+
+        cancel_job(queue, &host, queued).unwrap();
+        assert!(matches!(wait(queued_outcome), Err(ClipForgeError::Cancelled { .. })));
+        assert_eq!(status(queue, queued), ExportJobStatus::Cancelled);
+        assert!(host.progress.lock().unwrap().iter()
+            .any(|progress| progress.job_id == queued && progress.status == ExportJobStatus::Cancelled));
+
+        // Jobs start in order, so the cancelled one would have run before this later one
+        drop(release);
+        assert!(wait(running_outcome).is_ok());
+        let (later, later_outcome) = submit_to(queue, &host);
+        assert!(wait(later_outcome).is_ok());
+        assert_eq!(status(queue, later), ExportJobStatus::Completed);
+        assert_eq!(status(queue, running), ExportJobStatus::Completed);
+        assert_eq!(host.runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn cancelling_a_running_job_is_refused() {
+        let queue = new_queue();
+        let (host, release) = TestHost::new();
+        let (running, outcome) = submit_to(queue, &host);
+        assert_eq!(status(queue, running), ExportJobStatus::Running);
+
+        let error = cancel_job(queue, &host, running).unwrap_err();
+        assert!(matches!(error, ClipForgeError::InvalidArgument { .. }));
+        assert_eq!(status(queue, running), ExportJobStatus::Running);
+
+        release.send(()).unwrap();
+        assert!(wait(outcome).is_ok());
+        assert_eq!(status(queue, running), ExportJobStatus::Completed);
+    }
+
+    #[test]
+    fn only_the_newest_finished_jobs_are_kept() {
+        let queue = new_queue();
+        let (host, release) = TestHost::new();
+        drop(release);
+
+        let ids: Vec<u64> = (0..MAX_FINISHED_EXPORT_JOBS + 5)
+            .map(|_| {
+                let (id, outcome) = submit_to(queue, &host);
+                assert!(wait(outcome).is_ok());
+                id
+            })
+            .collect();
+
+        let kept: Vec<u64> = lock(queue).jobs.iter().map(|job| job.id).collect();
+        assert_eq!(kept, ids[5..]);
+    }
+
+    #[test]
+    fn running_jobs_never_exceed_the_concurrency() {
+        let queue = new_queue();
+        let (host, release) = TestHost::new();
+        set_queue_concurrency(queue, &host, 2).unwrap();
+
+        let outcomes: Vec<_> = (0..6).map(|_| submit_to(queue, &host).1).collect();
+        {
+            let queue = lock(queue);
+            assert_eq!(queue.running, 2);
+            assert_eq!(queue.jobs.iter().filter(|job| job.status == ExportJobStatus::Running).count(), 2);
+        }
+
+        for _ in 0..outcomes.len() {
+            release.send(()).unwrap();
+            assert!(lock(queue).running <= 2);
+        }
+        for outcome in outcomes {
+            assert!(wait(outcome).is_ok());
+        }
+        assert_eq!(host.runs.load(Ordering::SeqCst), 6);
+        assert!(host.max_active.load(Ordering::SeqCst) <= 2);
+        assert_eq!(lock(queue).running, 0);
+    }
+}
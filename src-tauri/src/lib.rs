@@ -1,9 +1,27 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
+use command_log::{command_argv, LoggedCommand};
+use recording_child::RecordingChild;
+use stderr_log::StderrLog;
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+mod active_window;
+mod command_log;
+mod device_backend;
+mod error;
+mod export_queue;
+mod recording_child;
+mod stderr_log;
+pub use active_window::WindowInfo;
+pub use error::ClipForgeError;
+pub use export_queue::{ExportJob, ExportJobRequest, ExportJobStatus, ExportProgress};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VideoFile {
@@ -12,17 +30,69 @@ pub struct VideoFile {
     pub size: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExportResult {
     pub success: bool,
     pub message: String,
     pub output_path: Option<String>,
+    // Extra details recorded for export manifests; optional so older callers can omit them
+    #[serde(default)]
+    pub source_path: Option<String>,
+    #[serde(default)]
+    pub settings: HashMap<String, String>,
+    #[serde(default)]
+    pub duration_seconds: Option<f64>,
+    #[serde(default)]
+    pub checksum: Option<String>, // SHA-256 of the output file, hex encoded
+    #[serde(default)]
+    pub timestamp: Option<u64>, // Unix seconds when the export finished
+    #[serde(default)]
+    pub error: Option<ClipForgeError>, // Why a batch export failed, with its kind; only set when success is false
+    #[serde(default)]
+    pub output_paths: Vec<String>, // Every file written, for exports that produce several (segments, thumbnails)
 }
 
+/// JSON manifest describing a batch of exports, written by `write_export_manifest`
 #[derive(Debug, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub created_at: u64,
+    pub export_count: usize,
+    pub exports: Vec<ExportResult>,
+}
+
+/// One file in an export_videos batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchExportJob {
+    pub source_path: String,
+    pub destination_path: String,
+    pub format: Option<String>, // Transcode to this container ("mp4", "mkv", "webm"); None copies the file as-is
+}
+
+/// Last-used recording choices, persisted by `save_settings` and restored by `load_settings`
+/// Every field is optional so files written by older versions still load
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingSettings {
+    pub audio_device_index: Option<u32>,
+    pub webcam_device_index: Option<u32>,
+    pub screen_index: Option<u32>, // 0 = first screen
+    pub quality: Option<String>, // "low", "medium", "high", "lossless"
+    pub crf: Option<u32>,
+    pub bitrate: Option<String>,
+    pub framerate: Option<u32>,
+    pub video_size: Option<String>, // Webcam capture size such as "1280x720"
+    pub container: Option<String>, // "mp4", "webm", or "mkv"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingResult {
-    pub process_id: u32,
+    pub process_id: u64, // Recording ID assigned by ClipForge (not the OS PID); pass it to stop_screen_recording
     pub output_path: String,
+    pub screen_device_index: Option<u32>, // avfoundation device the screen was captured from; None for webcam-only
+    pub command: Option<Vec<String>>, // Full FFmpeg argv; only set for dry runs, which use process_id 0
+    pub framerate: u32, // Output framerate after defaults were applied
+    pub resolution: Option<String>, // Output size as "WIDTHxHEIGHT"; None when it isn't known before FFmpeg runs
+    pub audio_enabled: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,12 +100,566 @@ pub struct StopRecordingResult {
     pub success: bool,
     pub file_path: String,
     pub message: String,
+    pub clean_shutdown: bool, // FFmpeg exited on the graceful stop request and finalized the file; false means it was killed and may be corrupt
+    pub size_limit_reached: bool, // FFmpeg had already stopped itself at max_size_bytes
+}
+
+/// Contents of the JSON sidecar written next to a recording when stopped with write_metadata
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingMetadata {
+    pub capture_type: String, // "screen", "webcam", or "screen_webcam"
+    pub file_path: String,
+    pub framerate: u32,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub audio_device_index: Option<u32>,
+    pub audio_device_name: Option<String>,
+    pub started_at: u64, // Unix seconds
+    pub stopped_at: u64, // Unix seconds
+    pub duration_seconds: Option<f64>,
+}
+
+/// Outcome of recover_segments
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoverSegmentsResult {
+    pub success: bool,
+    pub output_path: String,
+    pub recovered_seconds: f64,
+    pub segment_count: usize, // Segments included in the recovered file
+    pub missing_segments: Vec<u32>, // Segment numbers absent from the sequence
+    pub corrupt_segments: Vec<String>, // Segment files ffprobe could not read; skipped
+    pub message: String,
+}
+
+/// A recording that is currently running, as reported by list_active_recordings
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActiveRecording {
+    pub process_id: u64,
+    pub output_path: String,
+    pub elapsed_seconds: f64,
+    pub size_limit_reached: bool, // FFmpeg stopped itself at max_size_bytes; stop the recording to collect the file
+}
+
+// How a recording was started; kept for the metadata sidecar
+#[derive(Debug, Clone)]
+struct RecordingInfo {
+    capture_type: &'static str, // "screen", "webcam", or "screen_webcam"
+    framerate: u32,
+    audio_device_index: Option<u32>,
+    started_at: u64, // Unix seconds
+    max_size_bytes: Option<u64>, // FFmpeg's -fs limit, to tell a size-capped exit from a crash
+    preview_path: Option<std::path::PathBuf>, // JPEG FFmpeg keeps overwriting with the latest frame, when started with preview
+    text_file: Option<std::path::PathBuf>, // Watermark text read by drawtext, when started with watermark_text
+    internal: bool, // Started by ClipForge itself (test_recording); hidden from list_active_recordings and the hotkey
+}
+
+impl RecordingInfo {
+    fn new(capture_type: &'static str, framerate: u32, audio_device_index: Option<u32>, max_size_bytes: Option<u64>) -> Self {
+        RecordingInfo {
+            capture_type,
+            framerate,
+            audio_device_index,
+            started_at: unix_timestamp(),
+            max_size_bytes,
+            preview_path: None,
+            text_file: None,
+            internal: false,
+        }
+    }
+
+    fn with_preview(mut self, preview_path: Option<std::path::PathBuf>) -> Self {
+        self.preview_path = preview_path;
+        self
+    }
+
+    fn with_text_file(mut self, text_file: Option<TempPathGuard>) -> Self {
+        self.text_file = text_file.map(TempPathGuard::keep);
+        self
+    }
+
+    fn with_internal(mut self, internal: bool) -> Self {
+        self.internal = internal;
+        self
+    }
+
+    /// Delete the scratch files that only live as long as the recording
+    fn remove_temp_files(&self) {
+        for path in self.preview_path.iter().chain(&self.text_file) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// A running FFmpeg recording tracked in RECORDING_PROCESSES
+struct RecordingProcess {
+    child: RecordingChild,
+    output_path: String,
+    started_at: std::time::Instant,
+    info: RecordingInfo,
+    stderr_log: StderrLog,
+    detached: bool, // Left running when the app quits and listed in the recovery file
+    size_limit_reached: bool, // Exited on its own at max_size_bytes; kept until stopped so the stop result reports it
+}
+
+impl RecordingProcess {
+    fn new(child: std::process::Child, output_path: String, info: RecordingInfo) -> Self {
+        Self::with_stderr_forwarding(child, output_path, info, None)
+    }
+
+    /// Like `new`, additionally sending each stderr line to `forward` (e.g. for inactivity detection)
+    fn with_stderr_forwarding(
+        mut child: std::process::Child,
+        output_path: String,
+        info: RecordingInfo,
+        forward: Option<std::sync::mpsc::Sender<String>>,
+    ) -> Self {
+        let stderr_log = StderrLog::start(child.stderr.take(), forward);
+        RecordingProcess {
+            child: RecordingChild::Spawned(child),
+            output_path,
+            started_at: std::time::Instant::now(),
+            info,
+            stderr_log,
+            detached: false,
+            size_limit_reached: false,
+        }
+    }
+
+    /// A detached recording from a previous app session; its stderr went with that session
+    fn reattached(pid: u32, output_path: String, info: RecordingInfo) -> Self {
+        RecordingProcess {
+            child: RecordingChild::Reattached(pid),
+            output_path,
+            started_at: std::time::Instant::now(),
+            info,
+            stderr_log: StderrLog::start(None, None),
+            detached: true,
+            size_limit_reached: false,
+        }
+    }
+}
+
+// Entry in the detached-recordings recovery file, enough to track the recording again after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DetachedRecording {
+    process_id: u64,
+    pid: u32,
+    output_path: String,
+    capture_type: String,
+    framerate: u32,
+    audio_device_index: Option<u32>,
+    started_at: u64, // Unix seconds
+    max_size_bytes: Option<u64>,
+    #[serde(default)]
+    preview_path: Option<std::path::PathBuf>, // So preview frames keep working after a reattach
+    #[serde(default)]
+    text_file: Option<std::path::PathBuf>, // So the watermark text file is still removed when it stops
+}
+
+// Serializes read-modify-write cycles of the recovery file
+lazy_static::lazy_static! {
+    static ref DETACHED_RECORDINGS_LOCK: Mutex<()> = Mutex::new(());
 }
 
 // Global storage for active recording processes
-// Maps process ID to process handle, output path, and stderr handle
+// Maps recording ID to process handle and output path
+lazy_static::lazy_static! {
+    static ref RECORDING_PROCESSES: Mutex<HashMap<u64, RecordingProcess>> = Mutex::new(HashMap::new());
+    // Output path of every recording started this session, so delete_recording also accepts
+    // recordings that were named from a template or the active window
+    static ref STARTED_RECORDINGS: Mutex<Vec<std::path::PathBuf>> = Mutex::new(Vec::new());
+}
+
+/// Lock the recording map, recovering it if a panic poisoned the mutex
+/// Entries are only ever inserted or removed whole, so the map is still consistent after a panic,
+/// and refusing every later recording command until the app restarts would be worse
+fn lock_recording_processes() -> MutexGuard<'static, HashMap<u64, RecordingProcess>> {
+    RECORDING_PROCESSES.lock().unwrap_or_else(|poisoned| {
+        log::warn!("Recovering recording processes after a panic while they were locked");
+        RECORDING_PROCESSES.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+// Monotonic ID handed to the frontend for each recording; starts at 1
+static NEXT_RECORDING_ID: AtomicU64 = AtomicU64::new(1);
+
+// Numbers preview JPEGs; the recording ID is only assigned once FFmpeg is running
+static NEXT_PREVIEW_ID: AtomicU64 = AtomicU64::new(1);
+
+// Numbers scratch files and directories so concurrent commands never share one
+static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Scratch path in the temp dir that no other command or ClipForge instance will pick
+fn unique_temp_path(kind: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!(
+        "clipforge-{}-{}-{}",
+        kind,
+        std::process::id(),
+        NEXT_TEMP_ID.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+/// Scratch file or directory removed when dropped, so early returns don't leave it behind
+/// `keep` hands the path off to whoever cleans it up later
+struct TempPathGuard(Option<std::path::PathBuf>);
+
+impl TempPathGuard {
+    fn create_dir(path: std::path::PathBuf) -> Result<Self, ClipForgeError> {
+        std::fs::create_dir_all(&path)
+            .map_err(|e| ClipForgeError::io(format!("Failed to create '{}': {}", path.display(), e), &e))?;
+        Ok(TempPathGuard(Some(path)))
+    }
+
+    fn write_file(path: std::path::PathBuf, contents: &str) -> Result<Self, ClipForgeError> {
+        std::fs::write(&path, contents)
+            .map_err(|e| ClipForgeError::io(format!("Failed to write '{}': {}", path.display(), e), &e))?;
+        Ok(TempPathGuard(Some(path)))
+    }
+
+    fn path(&self) -> &std::path::Path {
+        self.0.as_deref().expect("TempPathGuard path is only taken by keep")
+    }
+
+    fn keep(mut self) -> std::path::PathBuf {
+        self.0.take().expect("TempPathGuard path is only taken by keep")
+    }
+}
+
+impl Drop for TempPathGuard {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            if path.is_dir() {
+                let _ = std::fs::remove_dir_all(path);
+            } else {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+// Rolling background capture used to prepend the moments before recording started
+struct PrerollBuffer {
+    child: RecordingChild,
+    stderr_log: StderrLog,
+    segment_dir: std::path::PathBuf,
+    preroll_seconds: u64,
+    audio_device_index: Option<u32>,
+    screen_index: u32,
+    show_cursor: bool,
+    framerate: u32,
+    video_quality: VideoQuality,
+}
+
+// Buffered segments claimed from a stopped pre-roll capture; the directory goes when this is dropped
+struct PrerollSegments {
+    files: Vec<std::path::PathBuf>,
+    _dir: TempPathGuard,
+}
+
+lazy_static::lazy_static! {
+    static ref PREROLL_BUFFER: Mutex<Option<PrerollBuffer>> = Mutex::new(None);
+    // Pre-roll segments claimed by a recording, keyed by process ID and prepended on stop
+    static ref PREROLL_SEGMENTS: Mutex<HashMap<u64, PrerollSegments>> = Mutex::new(HashMap::new());
+    // Existing files a recording should be appended to, keyed by process ID and joined on stop
+    static ref APPEND_TARGETS: Mutex<HashMap<u64, String>> = Mutex::new(HashMap::new());
+}
+
+// Length of each rolling pre-roll segment; also the granularity of the pre-roll duration
+const PREROLL_SEGMENT_SECONDS: u64 = 1;
+
+// Upper bound for the pre-roll buffer so the rolling capture stays small
+const MAX_PREROLL_SECONDS: u64 = 60;
+
+// File extensions recover_segments treats as recording segments
+const SEGMENT_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v", "mkv", "ts"];
+
+// User-configured FFmpeg binary path; None means use "ffmpeg" from PATH
+// Bundled FFmpeg found in the app's resource directory at startup, if any
+lazy_static::lazy_static! {
+    static ref FFMPEG_PATH: Mutex<Option<String>> = Mutex::new(None);
+    static ref BUNDLED_FFMPEG_PATH: Mutex<Option<String>> = Mutex::new(None);
+}
+
+// Folder chosen with set_default_output_dir for default-named recordings; None means the OS temp dir
+lazy_static::lazy_static! {
+    static ref DEFAULT_OUTPUT_DIR: Mutex<Option<std::path::PathBuf>> = Mutex::new(None);
+}
+
+// Set by cancel_recording_countdown to abort a delayed recording start
+static COUNTDOWN_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+// Re-runs the last successful start command with the same settings; used by the recording hotkey
+type RecordingStarter = std::sync::Arc<dyn Fn(tauri::AppHandle) -> Result<RecordingResult, ClipForgeError> + Send + Sync>;
+
+// Accelerator currently bound to toggling recording, and how to start a recording when it fires
 lazy_static::lazy_static! {
-    static ref RECORDING_PROCESSES: Mutex<HashMap<u32, (std::process::Child, String)>> = Mutex::new(HashMap::new());
+    static ref RECORDING_HOTKEY: Mutex<Option<String>> = Mutex::new(None);
+    static ref LAST_RECORDING_STARTER: Mutex<Option<RecordingStarter>> = Mutex::new(None);
+}
+
+// Set while a hotkey press is starting or stopping a recording so repeated presses are ignored
+static HOTKEY_TOGGLE_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+// Allowed range for audio_gain_db on recordings
+const MIN_AUDIO_GAIN_DB: f32 = -30.0;
+const MAX_AUDIO_GAIN_DB: f32 = 30.0;
+
+// Allowed range for audio_highpass_hz and audio_lowpass_hz; the range of human hearing
+const MIN_AUDIO_FILTER_HZ: u32 = 20;
+const MAX_AUDIO_FILTER_HZ: u32 = 20_000;
+
+// How long FFmpeg gets to finalize recordings when the app quits before being killed
+const EXIT_FLUSH_TIMEOUT_MS: u64 = 3000;
+
+// How long stop_screen_recording waits for FFmpeg to exit after the stop request, by default and at most
+const DEFAULT_FLUSH_TIMEOUT_MS: u64 = 5000;
+const MAX_FLUSH_TIMEOUT_MS: u64 = 120_000;
+
+// How often a stopping recording is checked for exit
+const STOP_POLL_MS: u64 = 50;
+
+// After FFmpeg exits, how long and how often to check for the output file on slow disks
+const OUTPUT_FILE_WAIT_MS: u64 = 5000;
+const OUTPUT_FILE_POLL_MS: u64 = 250;
+
+// How often the countdown checks for cancellation within each second
+const COUNTDOWN_POLL_MS: u64 = 100;
+
+/// Where the FFmpeg binary used by ClipForge comes from
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FfmpegSource {
+    pub source: String, // "configured", "bundled", or "system"
+    pub path: String,
+}
+
+/// Installed FFmpeg build details, as reported by validate_ffmpeg
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FfmpegInfo {
+    pub path: String,
+    pub version: String, // e.g. "6.1.1"
+    pub encoders: Vec<String>, // Encoders ClipForge uses that this build provides
+}
+
+// Encoders ClipForge can use; validate_ffmpeg reports which of these the installed build has
+const RELEVANT_ENCODERS: &[&str] = &[
+    "libx264",
+    "libx265",
+    "h264_videotoolbox",
+    "hevc_videotoolbox",
+    "libvpx-vp9",
+    "aac",
+    "libopus",
+    "gif",
+    "png",
+    "mjpeg",
+];
+
+// avfoundation lists screen devices after cameras; "Capture screen 0" is device 4
+const SCREEN_CAPTURE_DEVICE_OFFSET: u32 = 4;
+
+// Screen device indices start_screen_recording tries in order when the device can't be opened
+const SCREEN_DEVICE_CANDIDATES: &[u32] = &[4, 3, 5];
+
+// Framerate no device supports, used to make avfoundation print its supported modes
+const PROBE_INVALID_FRAMERATE: &str = "10000";
+
+// Video size no camera supports, used the same way by list_camera_capabilities
+const PROBE_INVALID_VIDEO_SIZE: &str = "1x1";
+
+// Framerates offered when a device doesn't report its own list
+const COMMON_FRAMERATES: [f64; 7] = [15.0, 24.0, 25.0, 30.0, 48.0, 50.0, 60.0];
+
+// Recording framerate bounds; omitted framerates keep the historical 30 fps
+const DEFAULT_FRAMERATE: u32 = 30;
+const MIN_FRAMERATE: u32 = 1;
+const MAX_FRAMERATE: u32 = 120;
+
+// Highest framerate auto_framerate picks unless an explicit framerate raises the cap
+const AUTO_FRAMERATE_CAP: u32 = 60;
+
+// Time-lapse bounds for start_screen_recording's speed_factor; avfoundation devices report
+// 1 fps as their lowest mode, so larger factors capture at 1 fps and drop frames on output
+const MAX_SPEED_FACTOR: f64 = 600.0;
+const MIN_TIMELAPSE_CAPTURE_FRAMERATE: f64 = 1.0;
+
+// EBU R128 loudness target used by transcode_video's normalize_audio (-16 LUFS, -1.5 dBTP)
+const LOUDNORM_TARGET: &str = "loudnorm=I=-16:TP=-1.5:LRA=11";
+
+// System fonts tried for drawtext overlays before falling back to the bundled font
+const DRAWTEXT_FONT_CANDIDATES: &[&str] = &[
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/System/Library/Fonts/Helvetica.ttc",
+    "/Library/Fonts/Arial.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+// Webcam capture size when start_webcam_recording isn't given one
+const DEFAULT_WEBCAM_VIDEO_SIZE: &str = "1280x720";
+
+// Sample rates accepted for recorded audio; Opus (webm) only supports the OPUS_ subset
+const SUPPORTED_AUDIO_SAMPLE_RATES: &[u32] = &[8000, 16000, 22050, 24000, 32000, 44100, 48000];
+const OPUS_AUDIO_SAMPLE_RATES: &[u32] = &[8000, 16000, 24000, 48000];
+
+// Lowercase name fragments of virtual audio drivers, matched by is_virtual_audio_device
+const VIRTUAL_AUDIO_DEVICE_PATTERNS: &[&str] = &["blackhole", "loopback", "soundflower", "aggregate"];
+
+// File in the app config directory holding the persisted RecordingSettings
+const SETTINGS_FILE_NAME: &str = "recording-settings.json";
+
+// Log file in the app log directory ("<name>.log"); rotated once it reaches the size limit
+const LOG_FILE_NAME: &str = "clipforge";
+const LOG_MAX_FILE_BYTES: u128 = 5 * 1024 * 1024;
+const LOG_ROTATED_FILES_KEPT: usize = 5;
+
+// Name prefix and extensions of default-named recordings, used to find leftovers after a crash
+const DEFAULT_RECORDING_PREFIX: &str = "clipforge-";
+const RECORDING_FILE_EXTENSIONS: &[&str] = &["mp4", "webm", "mkv"];
+
+// Height both screens are scaled to in a dual-screen recording unless overridden
+const DEFAULT_DUAL_SCREEN_HEIGHT: u32 = 1080;
+
+// Start of each recording dropped to hide avfoundation's garbled first frames; trim_start_ms overrides it
+// Pre-roll recordings and time-lapses default to no trim: the pre-roll already covers the start, and a
+// time-lapse would lose 200 ms of output, which is several seconds of real time
+// The frames are decoded and discarded (-ss as an output option), so the cut is frame-accurate
+const DEFAULT_TRIM_START_MS: u64 = 200;
+const MAX_TRIM_START_MS: u64 = 10_000;
+
+// Highest {counter} value (or "-N" suffix without one) tried when expanding a filename_template
+const MAX_FILENAME_COUNTER: u32 = 9999;
+
+// Recovery file in the temp dir listing detached recordings; like the processes, it doesn't outlive a reboot
+const DETACHED_RECORDINGS_FILE_NAME: &str = "clipforge-detached-recordings.json";
+
+// Niceness for recordings started with low_priority
+#[cfg(unix)]
+const LOW_PRIORITY_NICENESS: libc::c_int = 10;
+
+// Range for keyframe_interval_seconds; very short intervals bloat the file for little trim precision
+const MIN_KEYFRAME_INTERVAL_SECONDS: f64 = 0.1;
+const MAX_KEYFRAME_INTERVAL_SECONDS: f64 = 60.0;
+
+// Sample length bounds for measure_audio_level
+const MIN_AUDIO_LEVEL_SAMPLE_MS: u64 = 100;
+const MAX_AUDIO_LEVEL_SAMPLE_MS: u64 = 10_000;
+
+// Peak level below which measure_audio_level reports the device as silent
+const SILENCE_PEAK_DB: f64 = -60.0;
+
+// Upper bound for audio_device_index plus audio_device_indices in one recording
+const MAX_MICROPHONES: usize = 8;
+
+// Image formats accepted for overlay_image_path; PNG keeps the logo's transparency
+const OVERLAY_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "webp", "tif", "tiff"];
+
+// Length of the sample recorded by test_recording
+const TEST_RECORDING_SECONDS: u64 = 3;
+
+// export_for_web settings: VP9 CRF (higher is smaller) and the width videos are scaled down to by default
+const WEB_EXPORT_CRF: u32 = 32;
+const DEFAULT_WEB_EXPORT_WIDTH: u32 = 1280;
+const MIN_WEB_EXPORT_WIDTH: u32 = 16;
+const MAX_WEB_EXPORT_WIDTH: u32 = 7680;
+
+// Largest audio_offset_ms either way; real drift is well under a second
+const MAX_AUDIO_OFFSET_MS: i64 = 10_000;
+
+// Subtitle formats burn_subtitles accepts; both are rendered by libass
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa"];
+
+// Smallest max_size_bytes; below this the container header and first keyframe alone can hit the limit
+const MIN_MAX_SIZE_BYTES: u64 = 1024 * 1024;
+
+// Fraction of max_size_bytes an exited recording's file must reach to count as stopped by the limit;
+// FFmpeg checks -fs against the bytes it has handed to the muxer, so with buffered output the file on
+// disk can be well short of it. A failed exit status rules out the limit regardless of size
+const SIZE_LIMIT_REACHED_FRACTION: f64 = 0.5;
+
+// Live preview JPEG written alongside recordings started with preview: refresh rate and width
+const PREVIEW_FRAMERATE: u32 = 1;
+const PREVIEW_WIDTH: u32 = 320;
+
+// get_preview_frame rereads a preview caught mid-write this many times, this far apart
+const PREVIEW_READ_ATTEMPTS: u32 = 3;
+const PREVIEW_RETRY_MS: u64 = 50;
+
+// Shown when macOS blocks screen capture; the grant only takes effect after a restart
+const SCREEN_RECORDING_PERMISSION_MESSAGE: &str =
+    "Screen recording permission not granted. Enable ClipForge in System Settings > Privacy & Security > Screen Recording, then restart the app.";
+
+// Upper bound for extra_args, well beyond any real set of flags
+const MAX_EXTRA_ARGS: usize = 64;
+
+// Pixel formats hevc_videotoolbox accepts (8-bit and 10-bit 4:2:0)
+const HEVC_VIDEOTOOLBOX_PIXEL_FORMATS: &[&str] = &["yuv420p", "nv12", "p010le"];
+
+// Output extensions wrap_with_intro_outro can write; webm gets VP9/Opus, the others H.264/AAC
+const WRAP_OUTPUT_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v", "mkv", "webm"];
+
+// Vertical canvas used by mobile_split (9:16)
+const MOBILE_WIDTH: u32 = 1080;
+const MOBILE_HEIGHT: u32 = 1920;
+
+// Default freezedetect noise tolerance used for inactivity auto-stop
+// Small enough that slow scrolling or typing still counts as activity
+const DEFAULT_INACTIVITY_MOTION_THRESHOLD: f64 = 0.001;
+
+// How often the background watcher checks tracked recordings for unexpected exits
+const RECORDING_WATCH_INTERVAL_MS: u64 = 1000;
+
+/// Payload of the `recording-crashed` event, emitted when a tracked FFmpeg
+/// process exits on its own instead of being stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingCrashedEvent {
+    pub process_id: u64,
+    pub output_path: String,
+    pub exit_status: String,
+    pub stderr: String,
+}
+
+/// Payload of the `recording-size-limit-reached` event, emitted when a recording started with
+/// max_size_bytes stops itself; stop it as usual to collect the file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSizeLimitEvent {
+    pub process_id: u64,
+    pub output_path: String,
+    pub max_size_bytes: u64,
+}
+
+/// A resolution/framerate combination supported by an avfoundation capture device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureMode {
+    pub width: u32,
+    pub height: u32,
+    pub min_framerate: f64,
+    pub max_framerate: f64,
+}
+
+/// A resolution a camera can capture at, with the highest framerate it supports at that size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraMode {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+}
+
+/// Input level of an audio device over a short sample, from FFmpeg's astats filter
+/// Levels are in dBFS (0 is full scale); None means digital silence (-inf)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioLevel {
+    pub device_index: u32,
+    pub rms_db: Option<f64>,
+    pub peak_db: Option<f64>,
+    pub silent: bool, // Peak below SILENCE_PEAK_DB, e.g. a muted or disconnected microphone
+}
+
+/// Capture framerates available for a display
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisplayFramerates {
+    pub screen_index: u32,
+    pub framerates: Vec<f64>,
+    pub reported_by_device: bool, // false when the device accepted any rate and common values are returned
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -44,10 +668,54 @@ pub struct PermissionStatus {
     pub message: String,
 }
 
+/// Outcome of one run_healthcheck check
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Warning, // Recording may still work, e.g. a permission that can't be verified from the backend
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String, // "ffmpeg", "devices", "screen_permission", "microphone_permission", "output_dir"
+    pub status: HealthStatus,
+    pub message: String,
+}
+
+/// Outcome of `test_recording`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestResult {
+    pub passed: bool,
+    pub message: String, // What failed, or a summary of the recorded streams
+    pub duration_seconds: Option<f64>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>, // None when the settings record no audio
+}
+
+/// Readiness of the recording pipeline, returned by `run_healthcheck`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub ready: bool, // No check failed
+    pub checks: Vec<HealthCheck>,
+}
+
+/// Encoder performance of a running recording, read from FFmpeg's latest progress line
+/// Fields are None until FFmpeg has reported them
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncodingStats {
+    pub fps: Option<f64>, // Frames encoded per second
+    pub speed: Option<f64>, // Encoding speed relative to real time; below 1.0 means the encoder is falling behind
+    pub dropped_frames: Option<u64>,
+    pub frame: Option<u64>, // Frames encoded so far
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AudioDevice {
     pub index: u32,
     pub name: String,
+    pub is_virtual: bool, // Loopback/virtual driver (e.g. BlackHole) rather than a physical mic
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -61,405 +729,5972 @@ fn greet(name: &str) -> String {
 }
 
 /// Export a video file to the specified destination
-/// This is a prototype implementation that simply copies the file
-#[tauri::command]
-fn export_video(source_path: String, destination_path: String) -> Result<ExportResult, String> {
-    use std::fs;
-    use std::io::Write;
-    
-    // Read the source file
-    let source_data = fs::read(&source_path)
-        .map_err(|e| format!("Failed to read source file: {}", e))?;
-    
-    // Write to destination
-    let mut dest_file = fs::File::create(&destination_path)
-        .map_err(|e| format!("Failed to create destination file: {}", e))?;
-    
-    dest_file.write_all(&source_data)
-        .map_err(|e| format!("Failed to write to destination: {}", e))?;
-    
-    // Ensure data is written to disk
-    dest_file.sync_all()
-        .map_err(|e| format!("Failed to sync file: {}", e))?;
+/// This is a prototype implementation that simply copies the file; FFmpeg isn't involved, so unlike
+/// the other export commands it takes no extra_args (use transcode_video or remux_video for that)
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn export_video(app: tauri::AppHandle, source_path: String, destination_path: String) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::Export { source_path, destination_path })
+}
+
+/// export_video itself, run on an export queue worker
+fn run_export_video(source_path: String, destination_path: String) -> Result<ExportResult, ClipForgeError> {
+    let checksum = copy_file_streaming(&source_path, &destination_path)?;
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "copy".to_string());
     
     Ok(ExportResult {
         success: true,
         message: "Export completed successfully".to_string(),
+        duration_seconds: probe_duration_seconds(&destination_path),
         output_path: Some(destination_path),
+        source_path: Some(source_path),
+        settings,
+        checksum: Some(checksum),
+        timestamp: Some(unix_timestamp()),
+        error: None,
+        output_paths: Vec::new(),
     })
 }
 
-/// Start screen recording using FFmpeg
-/// Returns a process ID that can be used to stop the recording
-#[tauri::command]
-fn start_screen_recording(output_path: Option<String>, audio_device_index: Option<u32>) -> Result<RecordingResult, String> {
-    // Generate output path if not provided
-    let output = if let Some(path) = output_path {
-        path
-    } else {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let temp_dir = std::env::temp_dir();
-        temp_dir
-            .join(format!("clipforge-recording-{}.mp4", timestamp))
-            .to_str()
-            .ok_or("Failed to create temp file path")?
-            .to_string()
+/// Export several files in one call through export_video or transcode_video
+/// Every file is queued up front and runs in order on the export queue; a failed file doesn't stop the batch:
+/// its result has `success: false`, its intended output_path and the error (also as its message), so results
+/// line up one-to-one with `jobs`. Jobs whose destination extension contradicts their format, or whose outputs
+/// would land on the same path, fail without running
+#[tauri::command(async)]
+fn export_videos(app: tauri::AppHandle, jobs: Vec<BatchExportJob>) -> Vec<ExportResult> {
+    let total = jobs.len();
+    let planned: Vec<_> = jobs
+        .into_iter()
+        .map(|job| {
+            let source_path = job.source_path.clone();
+            let mut output_path = job.destination_path.clone();
+            let request = match job.format {
+                None => Ok(ExportJobRequest::Export { source_path: job.source_path, destination_path: job.destination_path }),
+                Some(format) => {
+                    // transcode_video picks codecs from the output extension, so a destination without one
+                    // gets the format's; one that names a different format is refused rather than rewritten
+                    let format = format.trim_start_matches('.').to_ascii_lowercase();
+                    let destination = std::path::Path::new(&job.destination_path);
+                    match destination.extension().map(|extension| extension.to_string_lossy().to_ascii_lowercase()) {
+                        Some(extension) if extension != format => Err(ClipForgeError::invalid_argument(format!(
+                            "Destination {} doesn't match format {}",
+                            job.destination_path, format
+                        ))),
+                        _ => {
+                            output_path = destination.with_extension(&format).to_string_lossy().to_string();
+                            Ok(ExportJobRequest::Transcode {
+                                source_path: job.source_path,
+                                output_path: output_path.clone(),
+                                quality: None,
+                                crf: None,
+                                normalize_audio: None,
+                                two_pass_loudnorm: None,
+                                fade_in_seconds: None,
+                                fade_out_seconds: None,
+                                scale_to: None,
+                                video_codec: None,
+                                pixel_format: None,
+                                color_range: None,
+                                audio_offset_ms: None,
+                                extra_args: None,
+                            })
+                        }
+                    }
+                }
+            };
+            (source_path, output_path, request)
+        })
+        .collect();
+
+    // Jobs writing the same file would overwrite each other, so none of them runs
+    let output_paths: Vec<String> = planned.iter().map(|(_, output_path, _)| output_path.clone()).collect();
+    let queued: Vec<_> = planned
+        .into_iter()
+        .map(|(source_path, output_path, request)| {
+            let shared = output_paths
+                .iter()
+                .filter(|other| std::path::Path::new(other) == std::path::Path::new(&output_path))
+                .count()
+                > 1;
+            let pending = request.and_then(|request| {
+                if shared {
+                    return Err(ClipForgeError::invalid_argument(format!(
+                        "More than one job in the batch writes to {}",
+                        output_path
+                    )));
+                }
+                Ok(export_queue::submit(&app, request))
+            });
+            (source_path, output_path, pending)
+        })
+        .collect();
+
+    let results: Vec<ExportResult> = queued
+        .into_iter()
+        .map(|(source_path, output_path, pending)| {
+            pending.and_then(export_queue::wait).unwrap_or_else(|e| {
+                log::warn!("Batch export of {} failed: {}", source_path, e);
+                ExportResult {
+                    success: false,
+                    message: e.message().to_string(),
+                    output_path: Some(output_path),
+                    source_path: Some(source_path),
+                    timestamp: Some(unix_timestamp()),
+                    error: Some(e),
+                    ..Default::default()
+                }
+            })
+        })
+        .collect();
+
+    let failed = results.iter().filter(|result| !result.success).count();
+    log::info!("Batch export finished: {} of {} succeeded", total - failed, total);
+    results
+}
+
+/// Reader adapter that feeds everything read through it into a SHA-256 hash
+struct HashingReader<R> {
+    inner: R,
+    hasher: sha2::Sha256,
+}
+
+impl<R: std::io::Read> std::io::Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use sha2::Digest;
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Copy a file in buffered chunks, syncing it to disk, and return the SHA-256 of its contents
+/// Large recordings are never held in memory. Copying a file onto itself is refused, since creating
+/// the destination would truncate the source before it's read
+fn copy_file_streaming(source_path: &str, destination_path: &str) -> Result<String, ClipForgeError> {
+    use sha2::{Digest, Sha256};
+    use std::fs;
+    use std::io::{BufReader, BufWriter};
+
+    // A destination that doesn't exist yet can't be the source
+    if let (Ok(source), Ok(destination)) = (fs::canonicalize(source_path), fs::canonicalize(destination_path)) {
+        if source == destination {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Destination is the source file itself: {}",
+                destination_path
+            )));
+        }
+    }
+
+    let source_file = fs::File::open(source_path)
+        .map_err(|e| ClipForgeError::io(format!("Failed to read source file: {}", e), &e))?;
+    let mut reader = HashingReader {
+        inner: BufReader::new(source_file),
+        hasher: Sha256::new(),
     };
 
-    // Check if FFmpeg is available
-    let ffmpeg_check = Command::new("ffmpeg")
-        .arg("-version")
-        .output();
-    
-    match ffmpeg_check {
-        Ok(_) => {},
-        Err(_) => return Err("FFmpeg is not installed or not found in PATH. Please install FFmpeg to use screen recording.".to_string()),
+    let dest_file = fs::File::create(destination_path)
+        .map_err(|e| ClipForgeError::io(format!("Failed to create destination file: {}", e), &e))?;
+    let mut writer = BufWriter::new(dest_file);
+
+    std::io::copy(&mut reader, &mut writer)
+        .map_err(|e| ClipForgeError::io(format!("Failed to write to destination: {}", e), &e))?;
+
+    let dest_file = writer.into_inner()
+        .map_err(|e| ClipForgeError::io(format!("Failed to write to destination: {}", e.error()), e.error()))?;
+
+    // Ensure data is written to disk
+    dest_file.sync_all()
+        .map_err(|e| ClipForgeError::io(format!("Failed to sync file: {}", e), &e))?;
+
+    Ok(format!("{:x}", reader.hasher.finalize()))
+}
+
+/// Move a finished recording to its final location instead of copying it
+/// Uses an atomic rename when possible and falls back to copy + delete across filesystems
+/// (e.g. from the temp dir to an external drive)
+#[tauri::command]
+fn move_recording(source_path: String, destination_path: String) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
     }
 
-    // Construct FFmpeg command for macOS using avfoundation
-    // Screen capture devices start at index 4 (Capture screen 0), 5 (Capture screen 1), etc.
-    // Format: ffmpeg -f avfoundation -i "4:0" -r 30 -c:v libx264 -preset fast -crf 23 -pix_fmt yuv420p -c:a aac -b:a 192k -ar 48000 output.mp4
-    // "4:0" means screen capture device 4 (first screen), audio device 0 (first microphone)
-    // "4:" means screen capture device 4, no audio device
-    
-    // Build input device string: "video_device:audio_device" or "video_device:" if no audio
-    let input_device = if let Some(audio_idx) = audio_device_index {
-        format!("4:{}", audio_idx)
-    } else {
-        "4:".to_string()
+    let mut settings = HashMap::new();
+    let checksum = match std::fs::rename(&source_path, &destination_path) {
+        Ok(()) => {
+            settings.insert("mode".to_string(), "rename".to_string());
+            None
+        }
+        Err(_) => {
+            let checksum = copy_file_streaming(&source_path, &destination_path)?;
+            std::fs::remove_file(&source_path)
+                .map_err(|e| ClipForgeError::io(format!("Copied to destination but failed to remove source file: {}", e), &e))?;
+            settings.insert("mode".to_string(), "copy".to_string());
+            Some(checksum)
+        }
     };
-    
-    let mut cmd = Command::new("ffmpeg");
-    cmd.arg("-f")
-        .arg("avfoundation")
-        .arg("-capture_cursor")
-        .arg("1")  // Capture cursor
-        .arg("-framerate")
-        .arg("30")  // Input framerate
+
+    Ok(ExportResult {
+        success: true,
+        message: "Recording moved successfully".to_string(),
+        duration_seconds: probe_duration_seconds(&destination_path),
+        output_path: Some(destination_path),
+        source_path: Some(source_path),
+        settings,
+        checksum,
+        timestamp: Some(unix_timestamp()),
+        error: None,
+        output_paths: Vec::new(),
+    })
+}
+
+/// Write a JSON manifest describing a batch of exports (source, destination, settings,
+/// duration, checksum and timestamp of each) for archival and later auditing
+#[tauri::command]
+fn write_export_manifest(results: Vec<ExportResult>, manifest_path: String) -> Result<ExportResult, ClipForgeError> {
+    let manifest = ExportManifest {
+        created_at: unix_timestamp(),
+        export_count: results.len(),
+        exports: results,
+    };
+
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| ClipForgeError::internal(format!("Failed to serialize export manifest: {}", e)))?;
+
+    std::fs::write(&manifest_path, json)
+        .map_err(|e| ClipForgeError::io(format!("Failed to write export manifest: {}", e), &e))?;
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Export manifest written with {} entries", manifest.export_count),
+        output_path: Some(manifest_path),
+        timestamp: Some(manifest.created_at),
+        ..Default::default()
+    })
+}
+
+/// Location of the persisted recording settings in the app config directory
+fn settings_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, ClipForgeError> {
+    let config_dir = app.path().app_config_dir()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to resolve app config directory: {}", e)))?;
+    Ok(config_dir.join(SETTINGS_FILE_NAME))
+}
+
+/// Save the user's recording choices so they can be restored on the next launch
+#[tauri::command]
+fn save_settings(app: tauri::AppHandle, settings: RecordingSettings) -> Result<(), ClipForgeError> {
+    let path = settings_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ClipForgeError::io(format!("Failed to create app config directory: {}", e), &e))?;
+    }
+
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| ClipForgeError::internal(format!("Failed to serialize recording settings: {}", e)))?;
+
+    // Write to a temporary file and rename so a crash mid-write can't leave a truncated file
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, json)
+        .map_err(|e| ClipForgeError::io(format!("Failed to write recording settings: {}", e), &e))?;
+    std::fs::rename(&temp_path, &path)
+        .map_err(|e| ClipForgeError::io(format!("Failed to save recording settings: {}", e), &e))
+}
+
+/// Load the saved recording choices
+/// Returns defaults (all unset) when nothing was saved yet or the file can't be parsed
+#[tauri::command]
+fn load_settings(app: tauri::AppHandle) -> Result<RecordingSettings, ClipForgeError> {
+    let path = settings_path(&app)?;
+    let json = match std::fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(RecordingSettings::default()),
+        Err(e) => return Err(ClipForgeError::io(format!("Failed to read recording settings: {}", e), &e)),
+    };
+
+    Ok(serde_json::from_str(&json).unwrap_or_else(|e| {
+        log::warn!("Ignoring malformed recording settings in {}: {}", path.display(), e);
+        RecordingSettings::default()
+    }))
+}
+
+/// Path of the current app log file, which records every FFmpeg command and its output
+/// Meant to be attached to bug reports; older logs sit next to it with a timestamp in the name
+#[tauri::command]
+fn get_log_path(app: tauri::AppHandle) -> Result<String, ClipForgeError> {
+    let log_dir = app.path().app_log_dir()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to resolve app log directory: {}", e)))?;
+    Ok(log_dir.join(format!("{}.log", LOG_FILE_NAME)).to_string_lossy().to_string())
+}
+
+/// Resolve the FFmpeg binary to run: the configured path if set, then the copy bundled
+/// with the app, otherwise "ffmpeg" on PATH
+fn ffmpeg_binary() -> String {
+    resolve_ffmpeg().path
+}
+
+/// Resolve the FFmpeg binary along with where it was found
+fn resolve_ffmpeg() -> FfmpegSource {
+    if let Some(path) = FFMPEG_PATH.lock().ok().and_then(|path| path.clone()) {
+        return FfmpegSource {
+            source: "configured".to_string(),
+            path,
+        };
+    }
+
+    if let Some(path) = BUNDLED_FFMPEG_PATH.lock().ok().and_then(|path| path.clone()) {
+        return FfmpegSource {
+            source: "bundled".to_string(),
+            path,
+        };
+    }
+
+    FfmpegSource {
+        source: "system".to_string(),
+        path: "ffmpeg".to_string(),
+    }
+}
+
+/// Look for an FFmpeg binary shipped in the app's resource directory
+/// Checked once at startup; the binary may sit at the root or under `binaries/`
+fn detect_bundled_ffmpeg(app: &tauri::AppHandle) -> Option<String> {
+    let resource_dir = app.path().resource_dir().ok()?;
+    let file_name = if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" };
+
+    [resource_dir.join(file_name), resource_dir.join("binaries").join(file_name)]
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .map(|candidate| candidate.to_string_lossy().to_string())
+}
+
+/// Report whether the resolved FFmpeg binary is configured, bundled with the app, or from the system PATH
+#[tauri::command]
+fn get_ffmpeg_source() -> FfmpegSource {
+    resolve_ffmpeg()
+}
+
+/// Check the FFmpeg that ClipForge will use and report its version and relevant encoders
+/// Lets the UI disable options (e.g. HEVC, VP9) the installed build can't encode
+#[tauri::command]
+fn validate_ffmpeg() -> Result<FfmpegInfo, ClipForgeError> {
+    let path = ffmpeg_binary();
+
+    let output = Command::new(&path)
+        .arg("-version")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("FFmpeg is not installed or could not be run ('{}'): {}", path, e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "'{}' did not run successfully with -version (status {:?})",
+            path, output.status
+        )));
+    }
+
+    // First line looks like "ffmpeg version 6.1.1 Copyright (c) 2000-2023 the FFmpeg developers"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("ffmpeg version "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .ok_or_else(|| ClipForgeError::invalid_argument(format!("'{}' does not appear to be an FFmpeg binary", path)))?
+        .to_string();
+
+    let output = Command::new(&path)
+        .arg("-hide_banner")
+        .arg("-encoders")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to list FFmpeg encoders: {}", e), &e))?;
+
+    // Encoder lines follow a " ------" separator, e.g. " V....D libx264   libx264 H.264 / AVC ..."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let encoders = stdout
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter(|name| RELEVANT_ENCODERS.contains(name))
+        .map(|name| name.to_string())
+        .collect();
+
+    Ok(FfmpegInfo { path, version, encoders })
+}
+
+/// Resolve the ffprobe binary, preferring the one installed next to the configured FFmpeg
+fn ffprobe_binary() -> String {
+    let ffmpeg = ffmpeg_binary();
+    let ffmpeg_path = std::path::Path::new(&ffmpeg);
+    if let Some(dir) = ffmpeg_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let file_name = if cfg!(windows) { "ffprobe.exe" } else { "ffprobe" };
+        let sibling = dir.join(file_name);
+        if sibling.exists() {
+            return sibling.to_string_lossy().to_string();
+        }
+    }
+    "ffprobe".to_string()
+}
+
+/// Set the FFmpeg binary used by all commands
+/// The path is validated by running `-version` before it is stored; returns the version line
+#[tauri::command]
+fn set_ffmpeg_path(path: String) -> Result<String, ClipForgeError> {
+    let output = Command::new(&path)
+        .arg("-version")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg at '{}': {}", path, e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "'{}' did not run successfully with -version (status {:?})",
+            path, output.status
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version_line = stdout.lines().next().unwrap_or("").trim().to_string();
+    if !version_line.starts_with("ffmpeg version") {
+        return Err(ClipForgeError::invalid_argument(format!("'{}' does not appear to be an FFmpeg binary", path)));
+    }
+
+    let mut ffmpeg_path = FFMPEG_PATH.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock FFmpeg path: {}", e)))?;
+    *ffmpeg_path = Some(path);
+
+    Ok(version_line)
+}
+
+/// Combine a screen recording and a webcam recording into a 1080x1920 vertical video
+/// for mobile: screen on the top half, webcam on the bottom, each scaled to fit and padded
+/// with `background_color`. `audio_source` is "mix" (default), "screen", or "webcam"
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn mobile_split(
+    app: tauri::AppHandle,
+    screen_path: String,
+    webcam_path: String,
+    output_path: String,
+    background_color: Option<String>,
+    audio_source: Option<String>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(
+        &app,
+        ExportJobRequest::MobileSplit { screen_path, webcam_path, output_path, background_color, audio_source, extra_args },
+    )
+}
+
+/// mobile_split itself, run on an export queue worker
+fn run_mobile_split(
+    screen_path: String,
+    webcam_path: String,
+    output_path: String,
+    background_color: Option<String>,
+    audio_source: Option<String>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    for path in [&screen_path, &webcam_path] {
+        if !std::path::Path::new(path).exists() {
+            return Err(ClipForgeError::not_found(format!("Source file not found: {}", path)));
+        }
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let background = background_color.unwrap_or_else(|| "black".to_string());
+    if background.is_empty()
+        || !background.chars().all(|c| c.is_ascii_alphanumeric() || c == '#' || c == '@' || c == '.')
+    {
+        return Err(ClipForgeError::invalid_argument(format!("Invalid background color '{}'", background)));
+    }
+
+    let screen_has_audio = has_audio_stream(&screen_path);
+    let webcam_has_audio = has_audio_stream(&webcam_path);
+
+    // Pick the audio mapping; "mix" degrades to whichever source actually has audio
+    let (audio_filter, audio_map) = match audio_source.as_deref().unwrap_or("mix") {
+        "mix" => match (screen_has_audio, webcam_has_audio) {
+            (true, true) => (Some("[0:a][1:a]amix=inputs=2:duration=shortest[a]"), Some("[a]")),
+            (true, false) => (None, Some("0:a")),
+            (false, true) => (None, Some("1:a")),
+            (false, false) => (None, None),
+        },
+        "screen" if screen_has_audio => (None, Some("0:a")),
+        "webcam" if webcam_has_audio => (None, Some("1:a")),
+        "screen" | "webcam" => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "The {} recording has no audio track",
+                audio_source.as_deref().unwrap_or_default()
+            )))
+        }
+        other => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Unknown audio source '{}'. Expected one of: mix, screen, webcam",
+                other
+            )))
+        }
+    };
+
+    // Each half of the 1080x1920 canvas is 1080x960
+    let half = |input: &str, label: &str| {
+        format!(
+            "[{}:v]scale={}:{}:force_original_aspect_ratio=decrease,pad={}:{}:(ow-iw)/2:(oh-ih)/2:color={},setsar=1[{}]",
+            input, MOBILE_WIDTH, MOBILE_HEIGHT / 2, MOBILE_WIDTH, MOBILE_HEIGHT / 2, background, label
+        )
+    };
+    let mut filter = format!(
+        "{};{};[top][bottom]vstack=inputs=2:shortest=1[v]",
+        half("0", "top"),
+        half("1", "bottom")
+    );
+    if let Some(audio_filter) = audio_filter {
+        filter.push(';');
+        filter.push_str(audio_filter);
+    }
+
+    let mut cmd = Command::new(ffmpeg_binary());
+    cmd.arg("-i")
+        .arg(&screen_path)
         .arg("-i")
-        .arg(&input_device);  // Screen capture device 4 (Capture screen 0), optional audio device
-    
-    // Add audio encoding parameters if audio device is provided
-    if audio_device_index.is_some() {
-        cmd.arg("-c:a")
-            .arg("aac")  // Audio codec
+        .arg(&webcam_path)
+        .arg("-filter_complex")
+        .arg(&filter)
+        .arg("-map")
+        .arg("[v]");
+
+    if let Some(audio_map) = audio_map {
+        cmd.arg("-map")
+            .arg(audio_map)
+            .arg("-c:a")
+            .arg("aac")
             .arg("-b:a")
-            .arg("192k")  // Audio bitrate (192 kbps)
-            .arg("-ar")
-            .arg("48000")  // Sample rate (48 kHz)
-            .arg("-ac")
-            .arg("2");  // Stereo (2 channels)
+            .arg("192k");
     }
-    
-    cmd.arg("-r")
-        .arg("30")  // Output framerate
+
+    let output = cmd
         .arg("-c:v")
-        .arg("libx264")  // Video codec
+        .arg("libx264")
         .arg("-preset")
-        .arg("fast")  // Encoding speed
+        .arg("fast")
         .arg("-crf")
-        .arg("23")  // Quality (lower = better, 18-28 is reasonable range)
+        .arg("23")
         .arg("-pix_fmt")
-        .arg("yuv420p")  // Pixel format for compatibility
-        .arg("-y")  // Overwrite output file
-        .arg(&output)
-        // Capture stderr to log errors for debugging
-        .stderr(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::null());
+        .arg("yuv420p")
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
 
-    // Spawn the FFmpeg process
-    let mut child = cmd.spawn()
-        .map_err(|e| format!("Failed to start FFmpeg process: {}. Make sure FFmpeg is installed and available in PATH.", e))?;
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to create the mobile split video: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
 
-    // Give FFmpeg a moment to initialize and check if it's still running
-    std::thread::sleep(std::time::Duration::from_millis(200));
-    
-    // Check if process immediately crashed
-    match child.try_wait() {
-        Ok(Some(status)) => {
-            // Process already exited - try to read stderr for error info
-            let error_msg = if let Some(mut stderr) = child.stderr.take() {
-                use std::io::Read;
-                let mut error_output = String::new();
-                let _ = stderr.read_to_string(&mut error_output);
-                if !error_output.is_empty() {
-                    format!("FFmpeg exited immediately with status {:?}. Error output: {}", status, error_output)
-                } else {
-                    format!("FFmpeg exited immediately with status {:?}", status)
-                }
-            } else {
-                format!("FFmpeg exited immediately with status {:?}", status)
-            };
-            return Err(error_msg);
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "mobile_split".to_string());
+    settings.insert("background_color".to_string(), background);
+    settings.insert("audio_source".to_string(), audio_source.unwrap_or_else(|| "mix".to_string()));
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: "Mobile split video created successfully".to_string(),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(screen_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Re-encode a video, unlike export_video which copies the file as-is
+/// Codecs follow the output extension (mp4/mkv: H.264 + AAC, webm: VP9 + Opus) and
+/// `quality`/`crf` work like the recording commands
+/// `normalize_audio` applies EBU R128 loudness normalization (-16 LUFS); `two_pass_loudnorm`
+/// measures the source first for an exact result at the cost of decoding it twice
+/// Fades apply to both picture and sound; the fade-out is positioned from the probed source duration
+/// `scale_to` larger than the source upscales, with a warning in the result message
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn transcode_video(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    quality: Option<String>, // "low", "medium", "high", "lossless"
+    crf: Option<u32>, // Explicit CRF (0-51), overrides the quality preset's CRF
+    normalize_audio: Option<bool>,
+    two_pass_loudnorm: Option<bool>,
+    fade_in_seconds: Option<f64>, // Fade from black (and silence) over the first N seconds
+    fade_out_seconds: Option<f64>, // Fade to black (and silence) over the last N seconds
+    scale_to: Option<String>, // "1920x1080" (fit inside, keeping aspect ratio) or shorthand like "1080p", "720p", "4k"
+    video_codec: Option<String>, // "h264" (default for mp4/mkv), "hevc" (libx265, slow), or "hevc_videotoolbox"
+    pixel_format: Option<String>, // Output pix_fmt, e.g. "yuv444p"; defaults to yuv420p (yuv444p for lossless)
+    color_range: Option<String>, // "tv" (limited, default) or "pc" (full)
+    audio_offset_ms: Option<i64>, // Shift the audio later (positive) or earlier (negative) to fix lip-sync drift
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::Transcode {
+        source_path,
+        output_path,
+        quality,
+        crf,
+        normalize_audio,
+        two_pass_loudnorm,
+        fade_in_seconds,
+        fade_out_seconds,
+        scale_to,
+        video_codec,
+        pixel_format,
+        color_range,
+        audio_offset_ms,
+        extra_args,
+    })
+}
+
+/// transcode_video itself, run on an export queue worker
+#[allow(clippy::too_many_arguments)]
+fn run_transcode_video(
+    source_path: String,
+    output_path: String,
+    quality: Option<String>,
+    crf: Option<u32>,
+    normalize_audio: Option<bool>,
+    two_pass_loudnorm: Option<bool>,
+    fade_in_seconds: Option<f64>,
+    fade_out_seconds: Option<f64>,
+    scale_to: Option<String>,
+    video_codec: Option<String>,
+    pixel_format: Option<String>,
+    color_range: Option<String>,
+    audio_offset_ms: Option<i64>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let video_quality = resolve_video_quality(quality.as_deref(), crf, None)?
+        .with_pixel_format(pixel_format.as_deref(), color_range.as_deref())?;
+    let container = resolve_output_container(std::path::Path::new(&output_path), video_codec.as_deref())?;
+    container.check_pixel_format(&video_quality)?;
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "transcode".to_string());
+    settings.insert("video_codec".to_string(), container.video_codec.to_string());
+
+    for (name, seconds) in [("fade_in_seconds", fade_in_seconds), ("fade_out_seconds", fade_out_seconds)] {
+        if let Some(seconds) = seconds {
+            if !(seconds.is_finite() && seconds > 0.0) {
+                return Err(ClipForgeError::invalid_argument(format!("{} must be greater than 0, got {}", name, seconds)));
+            }
         }
-        Ok(None) => {
-            // Process is still running, good!
+    }
+
+    let has_audio = has_audio_stream(&source_path);
+
+    let mut video_filters: Vec<String> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+    if let Some(target) = scale_to.as_deref() {
+        let (filter, warning) = build_scale_filter(target, &source_path)?;
+        video_filters.push(filter);
+        warnings.extend(warning);
+        settings.insert("scale_to".to_string(), target.to_string());
+    }
+
+    let mut fade_audio_filters: Vec<String> = Vec::new();
+    if let Some(duration) = fade_in_seconds {
+        video_filters.push(format!("fade=t=in:st=0:d={}", duration));
+        fade_audio_filters.push(format!("afade=t=in:st=0:d={}", duration));
+        settings.insert("fade_in_seconds".to_string(), duration.to_string());
+    }
+    if let Some(duration) = fade_out_seconds {
+        let source_duration = probe_duration_seconds(&source_path).ok_or_else(|| {
+            ClipForgeError::process_failed(format!("Could not determine the duration of {} for the fade-out", source_path))
+        })?;
+        if duration > source_duration {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "fade_out_seconds ({}) is longer than the video ({:.2}s)",
+                duration, source_duration
+            )));
         }
-        Err(e) => {
-            return Err(format!("Failed to check FFmpeg process status: {}", e));
+        let start = source_duration - duration;
+        video_filters.push(format!("fade=t=out:st={:.3}:d={}", start, duration));
+        fade_audio_filters.push(format!("afade=t=out:st={:.3}:d={}", start, duration));
+        settings.insert("fade_out_seconds".to_string(), duration.to_string());
+    }
+
+    // Loudness normalization is skipped for silent sources rather than failing the export
+    let mut audio_filters: Vec<String> = Vec::new();
+    // Shift first so the fades land on the output timeline
+    let audio_offset = audio_offset_filter(audio_offset_ms.unwrap_or(0))?;
+    if let (Some(filter), true) = (&audio_offset, has_audio) {
+        audio_filters.push(filter.clone());
+        settings.insert("audio_offset_ms".to_string(), audio_offset_ms.unwrap_or(0).to_string());
+    }
+    if normalize_audio.unwrap_or(false) && has_audio {
+        let loudnorm = if two_pass_loudnorm.unwrap_or(false) {
+            measure_loudnorm(&source_path)?
+        } else {
+            LOUDNORM_TARGET.to_string()
+        };
+        audio_filters.push(loudnorm);
+        settings.insert("normalize_audio".to_string(), "true".to_string());
+    }
+    // Fade after normalizing so loudnorm doesn't lift the faded edges back up
+    if has_audio {
+        audio_filters.extend(fade_audio_filters);
+    }
+
+    let mut cmd = Command::new(ffmpeg_binary());
+    cmd.arg("-i")
+        .arg(&source_path)
+        .arg("-c:v")
+        .arg(container.video_codec)
+        .args(container.tag_args())
+        .args(container.speed_args(&video_quality))
+        .args(container.rate_control_args(&video_quality))
+        .args(video_quality.pixel_format_args());
+
+    if !video_filters.is_empty() {
+        cmd.arg("-vf").arg(video_filters.join(","));
+    }
+
+    if has_audio {
+        cmd.arg("-c:a")
+            .arg(container.audio_codec)
+            .arg("-b:a")
+            .arg("192k");
+
+        // loudnorm upsamples internally to 192 kHz; pin the output back to 48 kHz
+        if !audio_filters.is_empty() {
+            cmd.arg("-af").arg(audio_filters.join(",")).arg("-ar").arg("48000");
+        }
+
+        // The shifted audio is padded with silence, so end it with the video
+        if audio_offset.is_some() {
+            cmd.arg("-shortest");
         }
     }
 
-    // Generate a unique process ID
-    let process_id = child.id();
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
 
-    // Store the process handle and output path
-    let mut processes = RECORDING_PROCESSES.lock()
-        .map_err(|e| format!("Failed to lock recording processes: {}", e))?;
-    
-    processes.insert(process_id, (child, output.clone()));
+    let output = cmd
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
 
-    Ok(RecordingResult {
-        process_id,
-        output_path: output,
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to transcode video: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut message = "Transcode completed successfully".to_string();
+    for warning in &warnings {
+        log::warn!("transcode_video: {}", warning);
+        message.push_str(&format!(" (warning: {})", warning));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message,
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
     })
 }
 
-/// Start webcam recording using FFmpeg
-/// Returns a process ID that can be used to stop the recording
-#[tauri::command]
-fn start_webcam_recording(output_path: Option<String>, device_index: Option<u32>, audio_device_index: Option<u32>) -> Result<RecordingResult, String> {
-    // Generate output path if not provided
-    let output = if let Some(path) = output_path {
-        path
+/// Audio filters moving the audio `offset_ms` later (positive) or earlier (negative) relative to the video
+/// Works on samples rather than container timestamps, so the shift is sample-accurate in every player;
+/// the audio is padded with silence at the end for -shortest to cut at the video's length
+fn audio_offset_filter(offset_ms: i64) -> Result<Option<String>, ClipForgeError> {
+    if offset_ms.abs() > MAX_AUDIO_OFFSET_MS {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "audio_offset_ms must be between -{} and {}, got {}",
+            MAX_AUDIO_OFFSET_MS, MAX_AUDIO_OFFSET_MS, offset_ms
+        )));
+    }
+
+    Ok(match offset_ms {
+        0 => None,
+        // Lead with silence
+        ms if ms > 0 => Some(format!("adelay={}:all=1,apad", ms)),
+        // Drop the start and restart timestamps at zero
+        ms => Some(format!("atrim=start={:.3},asetpts=PTS-STARTPTS,apad", ms.unsigned_abs() as f64 / 1000.0)),
+    })
+}
+
+/// Build a scale filter for `scale_to`, keeping the source's aspect ratio
+/// The bounding dimension is fixed and the other one is computed (-2 keeps it even for yuv420p)
+/// Also returns a warning when the target is larger than the source
+fn build_scale_filter(scale_to: &str, source_path: &str) -> Result<(String, Option<String>), ClipForgeError> {
+    let target = scale_to.trim().to_ascii_lowercase();
+    let invalid = || ClipForgeError::invalid_argument(format!(
+        "Invalid scale_to: {} (expected WIDTHxHEIGHT such as \"1920x1080\" or a height such as \"1080p\")",
+        scale_to
+    ));
+
+    let source = probe_video_resolution(source_path);
+    let (filter, target_height) = if target == "4k" {
+        ("scale=-2:2160".to_string(), 2160)
+    } else if let Some(height) = target.strip_suffix('p') {
+        let height: u32 = height.parse().ok().filter(|h| *h > 0).ok_or_else(invalid)?;
+        (format!("scale=-2:{}", height), height)
     } else {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let temp_dir = std::env::temp_dir();
-        temp_dir
-            .join(format!("clipforge-webcam-{}.mp4", timestamp))
-            .to_str()
-            .ok_or("Failed to create temp file path")?
-            .to_string()
+        let (width, height) = parse_video_size(&target).ok_or_else(invalid)?;
+        match source {
+            // Fix whichever side hits the box first
+            Some((source_width, source_height))
+                if (source_width as u64) * (height as u64) >= (width as u64) * (source_height as u64) =>
+            {
+                let scaled_height = (source_height as u64 * width as u64 / source_width as u64) as u32;
+                (format!("scale={}:-2", width), scaled_height)
+            }
+            Some(_) => (format!("scale=-2:{}", height), height),
+            None => (
+                format!("scale={}:{}:force_original_aspect_ratio=decrease:force_divisible_by=2", width, height),
+                height,
+            ),
+        }
     };
 
-    // Check if FFmpeg is available
-    let ffmpeg_check = Command::new("ffmpeg")
-        .arg("-version")
-        .output();
-    
-    match ffmpeg_check {
-        Ok(_) => {},
-        Err(_) => return Err("FFmpeg is not installed or not found in PATH. Please install FFmpeg to use webcam recording.".to_string()),
+    let warning = source
+        .filter(|(_, source_height)| target_height > *source_height)
+        .map(|(source_width, source_height)| {
+            format!("scale_to {} upscales the {}x{} source", scale_to, source_width, source_height)
+        });
+
+    Ok((filter, warning))
+}
+
+/// First loudnorm pass: measure the source and return a second-pass filter using those values
+fn measure_loudnorm(source_path: &str) -> Result<String, ClipForgeError> {
+    let output = Command::new(ffmpeg_binary())
+        .arg("-i")
+        .arg(source_path)
+        .arg("-vn")
+        .arg("-af")
+        .arg(format!("{}:print_format=json", LOUDNORM_TARGET))
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg loudness analysis: {}", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg loudness analysis failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
     }
 
-    // Use device index 0 by default (first webcam), or user-specified
-    let device_idx = device_index.unwrap_or(0);
-    
-    // Build input device string: "video_device:audio_device" or "video_device:" if no audio
-    let device_string = if let Some(audio_idx) = audio_device_index {
-        format!("{}:{}", device_idx, audio_idx)
-    } else {
-        format!("{}:", device_idx)
+    // loudnorm prints its measurements as a flat JSON object near the end of stderr,
+    // possibly followed by FFmpeg's final stats line
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let measured: serde_json::Value = stderr
+        .rfind('{')
+        .and_then(|start| stderr[start..].find('}').map(|end| &stderr[start..=start + end]))
+        .and_then(|json| serde_json::from_str(json).ok())
+        .ok_or_else(|| ClipForgeError::process_failed("Could not read loudness measurements from FFmpeg output"))?;
+
+    let value = |key: &str| -> Result<String, ClipForgeError> {
+        measured
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+            .ok_or_else(|| ClipForgeError::process_failed(format!("Loudness measurement '{}' missing from FFmpeg output", key)))
     };
 
-    // Construct FFmpeg command for macOS using avfoundation
-    // Format: ffmpeg -f avfoundation -i "0:0" -r 30 -c:v libx264 -preset fast -crf 23 -pix_fmt yuv420p -c:a aac -b:a 192k -ar 48000 output.mp4
-    // "0:0" means video device 0 (first webcam), audio device 0 (first microphone)
-    // "0:" means video device 0, no audio device
-    let mut cmd = Command::new("ffmpeg");
-    cmd.arg("-f")
-        .arg("avfoundation")
-        .arg("-framerate")
-        .arg("30")  // Input framerate
-        .arg("-video_size")
-        .arg("1280x720")  // Common webcam resolution, can be made configurable
+    Ok(format!(
+        "{}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+        LOUDNORM_TARGET,
+        value("input_i")?,
+        value("input_tp")?,
+        value("input_lra")?,
+        value("input_thresh")?,
+        value("target_offset")?
+    ))
+}
+
+/// Export a clip as an animated GIF using FFmpeg's two-pass palettegen/paletteuse chain
+/// Defaults to 10 fps and 480px wide; height is scaled proportionally
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn export_gif(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    fps: Option<u32>,
+    width: Option<u32>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::ExportGif { source_path, output_path, fps, width, extra_args })
+}
+
+/// export_gif itself, run on an export queue worker
+fn run_export_gif(
+    source_path: String,
+    output_path: String,
+    fps: Option<u32>,
+    width: Option<u32>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let fps = fps.unwrap_or(10);
+    let width = width.unwrap_or(480);
+    if fps == 0 || fps > 50 {
+        return Err(ClipForgeError::invalid_argument(format!("fps must be between 1 and 50, got {}", fps)));
+    }
+    if width == 0 {
+        return Err(ClipForgeError::invalid_argument("width must be greater than 0"));
+    }
+
+    // -1 keeps the aspect ratio; lanczos gives sharper downscaled text
+    let scale = format!("fps={},scale={}:-1:flags=lanczos", fps, width);
+    let palette_path = unique_temp_path("palette").with_extension("png");
+
+    // Pass 1: generate an optimized 256-color palette for the clip
+    let palette_result = Command::new(ffmpeg_binary())
         .arg("-i")
-        .arg(&device_string);  // Webcam device index, optional audio device
-    
-    // Add audio encoding parameters if audio device is provided
-    if audio_device_index.is_some() {
-        cmd.arg("-c:a")
-            .arg("aac")  // Audio codec
-            .arg("-b:a")
-            .arg("192k")  // Audio bitrate (192 kbps)
-            .arg("-ar")
-            .arg("48000")  // Sample rate (48 kHz)
-            .arg("-ac")
-            .arg("2");  // Stereo (2 channels)
+        .arg(&source_path)
+        .arg("-vf")
+        .arg(format!("{},palettegen", scale))
+        .arg("-y")
+        .arg(&palette_path)
+        .logged_output();
+
+    // Pass 2: render the GIF using that palette
+    let gif_result = match palette_result {
+        Ok(output) if output.status.success() => Command::new(ffmpeg_binary())
+            .arg("-i")
+            .arg(&source_path)
+            .arg("-i")
+            .arg(&palette_path)
+            .arg("-lavfi")
+            .arg(format!("{}[x];[x][1:v]paletteuse", scale))
+            .args(&extra_args)  // Caller's own flags, applied to the GIF pass only
+            .arg("-y")
+            .arg(&output_path)
+            .logged_output(),
+        other => other,
+    };
+
+    // The palette is only an intermediate, remove it whatever happened
+    let _ = std::fs::remove_file(&palette_path);
+
+    let output = gif_result
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to create GIF: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "gif".to_string());
+    settings.insert("fps".to_string(), fps.to_string());
+    settings.insert("width".to_string(), width.to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: "GIF exported successfully".to_string(),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Copy a recording with all metadata removed except the fields listed in `keep`
+/// Streams are copied without re-encoding; the message reports which fields were removed
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn strip_metadata(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    keep: Vec<String>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::StripMetadata { source_path, output_path, keep, extra_args })
+}
+
+/// strip_metadata itself, run on an export queue worker
+fn run_strip_metadata(
+    source_path: String,
+    output_path: String,
+    keep: Vec<String>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let metadata = probe_metadata_tags(&source_path)?;
+    let (kept, removed): (Vec<_>, Vec<_>) = metadata
+        .into_iter()
+        .partition(|(key, _)| keep.iter().any(|k| k.eq_ignore_ascii_case(key)));
+
+    let mut cmd = Command::new(ffmpeg_binary());
+    cmd.arg("-i")
+        .arg(&source_path)
+        .arg("-map")
+        .arg("0")
+        .arg("-map_metadata")
+        .arg("-1")  // Drop global and per-stream metadata
+        .arg("-map_chapters")
+        .arg("-1")
+        .arg("-c")
+        .arg("copy");
+
+    // Re-add only the fields the caller asked to keep
+    for (key, value) in &kept {
+        cmd.arg("-metadata").arg(format!("{}={}", key, value));
+    }
+
+    // mp4/mov only write non-standard keys (e.g. com.apple.quicktime.*) with this flag
+    let is_quicktime = std::path::Path::new(&output_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "mp4" | "mov" | "m4v"));
+    if is_quicktime && !kept.is_empty() {
+        cmd.arg("-movflags").arg("use_metadata_tags");
+    }
+
+    let output = cmd
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to strip metadata: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let removed_fields: Vec<String> = removed.into_iter().map(|(key, _)| key).collect();
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "strip_metadata".to_string());
+    settings.insert("removed_fields".to_string(), removed_fields.join(","));
+    settings.insert(
+        "kept_fields".to_string(),
+        kept.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>().join(","),
+    );
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    let message = if removed_fields.is_empty() {
+        "Metadata stripped; no fields needed removing".to_string()
+    } else {
+        format!("Metadata stripped; removed fields: {}", removed_fields.join(", "))
+    };
+
+    Ok(ExportResult {
+        success: true,
+        message,
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Extract a recording's audio track into its own file, e.g. for transcription
+/// `format` is "mp3", "wav" or "m4a"; when omitted it is taken from the output file's extension,
+/// falling back to "m4a"
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn extract_audio(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    format: Option<String>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::ExtractAudio { source_path, output_path, format, extra_args })
+}
+
+/// extract_audio itself, run on an export queue worker
+fn run_extract_audio(
+    source_path: String,
+    output_path: String,
+    format: Option<String>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let format = format
+        .or_else(|| {
+            std::path::Path::new(&output_path)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.to_ascii_lowercase())
+                .filter(|ext| matches!(ext.as_str(), "mp3" | "wav" | "m4a"))
+        })
+        .unwrap_or_else(|| "m4a".to_string());
+
+    let codec_args: &[&str] = match format.as_str() {
+        "mp3" => &["-c:a", "libmp3lame", "-q:a", "2"],  // VBR, ~190 kbps
+        "wav" => &["-c:a", "pcm_s16le"],
+        "m4a" => &["-c:a", "aac", "-b:a", "192k"],
+        other => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Unsupported audio format: {} (expected mp3, wav or m4a)",
+                other
+            )));
+        }
+    };
+
+    if !has_audio_stream(&source_path) {
+        return Err(ClipForgeError::invalid_argument(format!("Source has no audio stream: {}", source_path)));
+    }
+
+    let output = Command::new(ffmpeg_binary())
+        .arg("-i")
+        .arg(&source_path)
+        .arg("-vn")  // Drop video
+        .arg("-map")
+        .arg("0:a:0")
+        .args(codec_args)
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to extract audio: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "extract_audio".to_string());
+    settings.insert("format".to_string(), format.clone());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Audio extracted as {}", format),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Transcode to a small VP9/Opus WebM for embedding on the web
+/// Scales down to `target_width` (never up), keeping the aspect ratio, and strips metadata
+/// such as the recording's creation time and device names
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn export_for_web(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    target_width: Option<u32>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::ExportForWeb { source_path, output_path, target_width, extra_args })
+}
+
+/// export_for_web itself, run on an export queue worker
+fn run_export_for_web(
+    source_path: String,
+    output_path: String,
+    target_width: Option<u32>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let target_width = target_width.unwrap_or(DEFAULT_WEB_EXPORT_WIDTH);
+    if !(MIN_WEB_EXPORT_WIDTH..=MAX_WEB_EXPORT_WIDTH).contains(&target_width) || !target_width.is_multiple_of(2) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "target_width must be an even number between {} and {}, got {}",
+            MIN_WEB_EXPORT_WIDTH, MAX_WEB_EXPORT_WIDTH, target_width
+        )));
+    }
+
+    let output_path = std::path::Path::new(&output_path).with_extension("webm").to_string_lossy().to_string();
+
+    let output = Command::new(ffmpeg_binary())
+        .arg("-i")
+        .arg(&source_path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("0:a:0?")  // Audio is optional
+        .arg("-vf")
+        .arg(format!("scale='min({},iw)':-2", target_width))  // -2 keeps the height even for yuv420p
+        .arg("-c:v")
+        .arg("libvpx-vp9")
+        .arg("-crf")
+        .arg(WEB_EXPORT_CRF.to_string())
+        .arg("-b:v")
+        .arg("0")  // Constant quality; VP9 only honors -crf alone when the bitrate cap is 0
+        .arg("-row-mt")
+        .arg("1")  // Multithreaded VP9 encoding
+        .arg("-pix_fmt")
+        .arg("yuv420p")
+        .arg("-c:a")
+        .arg("libopus")
+        .arg("-b:a")
+        .arg("96k")
+        .arg("-map_metadata")
+        .arg("-1")  // Strip metadata
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to export for web: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "web".to_string());
+    settings.insert("video_codec".to_string(), "vp9".to_string());
+    settings.insert("crf".to_string(), WEB_EXPORT_CRF.to_string());
+    settings.insert("target_width".to_string(), target_width.to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: "Web export completed successfully".to_string(),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Rotate a video clockwise by 90, 180 or 270 degrees, e.g. footage from a sideways camera
+/// Re-encodes the video with the rotation applied to the pixels, with codecs chosen by the output extension
+/// like transcode_video; `metadata_only` instead stream copies
+/// and sets the rotation tag, which is lossless but relies on the player honoring the tag
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn rotate_video(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    degrees: u32,
+    metadata_only: Option<bool>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::Rotate { source_path, output_path, degrees, metadata_only, extra_args })
+}
+
+/// rotate_video itself, run on an export queue worker
+fn run_rotate_video(
+    source_path: String,
+    output_path: String,
+    degrees: u32,
+    metadata_only: Option<bool>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let rotation_filter = match degrees {
+        90 => "transpose=clock",
+        180 => "hflip,vflip",
+        270 => "transpose=cclock",
+        other => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "degrees must be 90, 180 or 270, got {}",
+                other
+            )));
+        }
+    };
+    let metadata_only = metadata_only.unwrap_or(false);
+    let container = resolve_output_container(std::path::Path::new(&output_path), None)?;
+    let video_quality = resolve_video_quality(None, None, None)?;
+
+    let mut cmd = Command::new(ffmpeg_binary());
+    cmd.arg("-i")
+        .arg(&source_path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("0:a?");
+
+    if metadata_only {
+        cmd.arg("-c")
+            .arg("copy")
+            .arg("-metadata:s:v:0")
+            .arg(format!("rotate={}", degrees));
+    } else {
+        cmd.arg("-vf")
+            .arg(rotation_filter)
+            .arg("-c:v")
+            .arg(container.video_codec)
+            .args(container.tag_args())
+            .args(container.speed_args(&video_quality))
+            .args(container.rate_control_args(&video_quality))
+            .args(video_quality.pixel_format_args());
+
+        // Audio is unaffected by the rotation, so copy it when the output container can hold the
+        // source codec (e.g. not pcm in mp4 or aac in webm) and re-encode it otherwise
+        let audio_copyable = match remux_container_codecs(container.extension)? {
+            None => true,
+            Some(accepted) => probe_stream_codecs(&source_path)?
+                .iter()
+                .filter(|(codec_type, _)| codec_type == "audio")
+                .all(|(_, codec_name)| accepted.contains(&codec_name.as_str())),
+        };
+        if audio_copyable {
+            cmd.arg("-c:a").arg("copy");
+        } else {
+            cmd.arg("-c:a").arg(container.audio_codec).arg("-b:a").arg("192k");
+        }
+    }
+
+    let output = cmd
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to rotate video: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "rotate".to_string());
+    settings.insert("degrees".to_string(), degrees.to_string());
+    settings.insert("metadata_only".to_string(), metadata_only.to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Video rotated {} degrees", degrees),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Hard-burn a subtitle file (SRT or ASS/SSA) into a clip, e.g. captions for accessibility
+/// `style` is passed to libass as force_style, e.g. "FontSize=28,PrimaryColour=&H0000FFFF";
+/// the video is re-encoded since the text becomes part of the pixels
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn burn_subtitles(
+    app: tauri::AppHandle,
+    source_path: String,
+    subtitle_path: String,
+    output_path: String,
+    style: Option<String>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::BurnSubtitles { source_path, subtitle_path, output_path, style, extra_args })
+}
+
+/// burn_subtitles itself, run on an export queue worker
+fn run_burn_subtitles(
+    source_path: String,
+    subtitle_path: String,
+    output_path: String,
+    style: Option<String>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let cue_count = validate_subtitle_file(&subtitle_path)?;
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let mut subtitle_filter = format!("subtitles='{}'", escape_filter_path(&subtitle_path));
+    if let Some(style) = style.as_deref() {
+        subtitle_filter.push_str(&format!(":force_style='{}'", validate_subtitle_style(style)?));
+    }
+
+    let output = Command::new(ffmpeg_binary())
+        .arg("-i")
+        .arg(&source_path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("0:a?")
+        .arg("-vf")
+        .arg(&subtitle_filter)
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("fast")
+        .arg("-crf")
+        .arg("23")
+        .arg("-pix_fmt")
+        .arg("yuv420p")
+        .arg("-c:a")
+        .arg("copy")  // Audio is unaffected by the captions
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to burn subtitles: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "burn_subtitles".to_string());
+    settings.insert("subtitle_path".to_string(), subtitle_path);
+    settings.insert("cue_count".to_string(), cue_count.to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+    if let Some(style) = style {
+        settings.insert("style".to_string(), style);
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Burned {} subtitle cues into video", cue_count),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Check that a subtitle file exists, has a supported extension and contains at least one cue;
+/// returns the number of cues
+/// Only the structure is checked (SRT timing lines, ASS Dialogue lines), which catches the wrong
+/// file being picked well before FFmpeg's less specific error after a full decode
+fn validate_subtitle_file(subtitle_path: &str) -> Result<usize, ClipForgeError> {
+    let path = std::path::Path::new(subtitle_path);
+    if !path.is_file() {
+        return Err(ClipForgeError::not_found(format!("Subtitle file not found: {}", subtitle_path)));
+    }
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase());
+    if !extension.as_deref().is_some_and(|ext| SUBTITLE_EXTENSIONS.contains(&ext)) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Unsupported subtitle file '{}'. Expected one of: {}",
+            subtitle_path,
+            SUBTITLE_EXTENSIONS.join(", ")
+        )));
+    }
+
+    let bytes = std::fs::read(path)
+        .map_err(|e| ClipForgeError::io(format!("Failed to read subtitle file {}: {}", subtitle_path, e), &e))?;
+    let contents = String::from_utf8_lossy(&bytes);
+
+    let cue_count = if extension.as_deref() == Some("srt") {
+        // "00:00:01,000 --> 00:00:04,000"
+        contents.lines().filter(|line| is_srt_timing_line(line)).count()
+    } else {
+        if !contents.lines().any(|line| line.trim().eq_ignore_ascii_case("[events]")) {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Subtitle file {} has no [Events] section",
+                subtitle_path
+            )));
+        }
+        contents.lines().filter(|line| line.trim_start().starts_with("Dialogue:")).count()
+    };
+
+    if cue_count == 0 {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Subtitle file {} contains no subtitle cues",
+            subtitle_path
+        )));
+    }
+    Ok(cue_count)
+}
+
+fn is_srt_timing_line(line: &str) -> bool {
+    let Some((start, end)) = line.trim().split_once("-->") else {
+        return false;
+    };
+    // Timestamps are HH:MM:SS,mmm; cue positioning may follow the end time
+    let is_timestamp = |value: &str| {
+        let value = value.trim();
+        value.len() >= 12
+            && value.is_char_boundary(12)
+            && value[..12].bytes().enumerate().all(|(i, b)| match i {
+                2 | 5 => b == b':',
+                8 => b == b',' || b == b'.',
+                _ => b.is_ascii_digit(),
+            })
+    };
+    is_timestamp(start) && is_timestamp(end)
+}
+
+/// Check a force_style string is a list of Key=Value pairs and return it trimmed
+/// A quote would end the filter option early, so it is rejected rather than escaped
+fn validate_subtitle_style(style: &str) -> Result<&str, ClipForgeError> {
+    let style = style.trim();
+    let valid = !style.is_empty()
+        && !style.contains(['\'', '\\'])
+        && style.split(',').all(|pair| {
+            pair.split_once('=').is_some_and(|(key, value)| {
+                !key.trim().is_empty()
+                    && key.trim().chars().all(|c| c.is_ascii_alphanumeric())
+                    && !value.trim().is_empty()
+            })
+        });
+    if !valid {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Invalid subtitle style '{}'. Expected comma-separated Key=Value pairs, e.g. \"FontSize=28,PrimaryColour=&H0000FFFF\"",
+            style
+        )));
+    }
+    Ok(style)
+}
+
+/// Rebuild the index of a recording that is unplayable because FFmpeg was killed before finishing it
+/// The original is left untouched; the repaired copy is written next to it with a "-repaired" suffix
+#[tauri::command]
+fn repair_recording(
+    path: String,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let output_path = path_with_stem_suffix(&path, "-repaired");
+    remux_for_repair(&path, &output_path, &extra_args)?;
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "repair".to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: "Recording repaired successfully".to_string(),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Bookend a recording with intro and/or outro clips, e.g. branded title cards
+/// All clips are re-encoded to the main recording's resolution and framerate (letterboxed if the
+/// aspect ratio differs) and joined as intro + main + outro, with codecs chosen by `output_path`'s extension
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn wrap_with_intro_outro(
+    app: tauri::AppHandle,
+    main_path: String,
+    intro_path: Option<String>,
+    outro_path: Option<String>,
+    output_path: String,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(
+        &app,
+        ExportJobRequest::WrapWithIntroOutro { main_path, intro_path, outro_path, output_path, extra_args },
+    )
+}
+
+/// wrap_with_intro_outro itself, run on an export queue worker
+fn run_wrap_with_intro_outro(
+    main_path: String,
+    intro_path: Option<String>,
+    outro_path: Option<String>,
+    output_path: String,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if intro_path.is_none() && outro_path.is_none() {
+        return Err(ClipForgeError::invalid_argument("Provide an intro clip, an outro clip, or both"));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+    let extension = std::path::Path::new(&output_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    if !extension.as_deref().is_some_and(|ext| WRAP_OUTPUT_EXTENSIONS.contains(&ext)) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Unsupported output file '{}'. Expected one of: {}",
+            output_path,
+            WRAP_OUTPUT_EXTENSIONS.join(", ")
+        )));
+    }
+
+    let inputs: Vec<&str> = intro_path.as_deref().into_iter()
+        .chain(std::iter::once(main_path.as_str()))
+        .chain(outro_path.as_deref())
+        .collect();
+    for path in &inputs {
+        if !std::path::Path::new(path).exists() {
+            return Err(ClipForgeError::not_found(format!("Source file not found: {}", path)));
+        }
+        if *path == output_path {
+            return Err(ClipForgeError::invalid_argument(format!("Output path must differ from the input clips: {}", path)));
+        }
+    }
+
+    concat_reencode(&inputs, &main_path, std::path::Path::new(&output_path), &extra_args)?;
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "wrap".to_string());
+    if let Some(intro_path) = &intro_path {
+        settings.insert("intro_path".to_string(), intro_path.clone());
+    }
+    if let Some(outro_path) = &outro_path {
+        settings.insert("outro_path".to_string(), outro_path.clone());
+    }
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: "Intro/outro added successfully".to_string(),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(main_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Queue any export job (see ExportJobRequest) to run in the background; returns the job ID
+/// Jobs run in submission order, `set_export_concurrency` at a time, and report through
+/// `export-progress` (status changes) and `export-complete` (the finished job) events
+#[tauri::command]
+fn queue_export(app: tauri::AppHandle, request: ExportJobRequest) -> u64 {
+    export_queue::enqueue(&app, request)
+}
+
+/// Queued, running and recently finished export jobs, oldest first
+#[tauri::command]
+fn list_export_jobs() -> Vec<ExportJob> {
+    export_queue::list()
+}
+
+/// Cancel an export job that hasn't started yet
+#[tauri::command]
+fn cancel_export_job(app: tauri::AppHandle, job_id: u64) -> Result<(), ClipForgeError> {
+    export_queue::cancel(&app, job_id)
+}
+
+/// Set how many queued export jobs run at once (1 by default)
+#[tauri::command]
+fn set_export_concurrency(app: tauri::AppHandle, concurrency: usize) -> Result<(), ClipForgeError> {
+    export_queue::set_concurrency(&app, concurrency)
+}
+
+/// Change a video's container without re-encoding, e.g. mp4 -> mov for an editor
+/// Video and audio streams are copied as-is; `output_path` gets the `container` extension.
+/// Fails before running FFmpeg when a source codec can't be stored in the target container
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn remux_video(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    container: String,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::Remux { source_path, output_path, container, extra_args })
+}
+
+/// remux_video itself, run on an export queue worker
+fn run_remux_video(
+    source_path: String,
+    output_path: String,
+    container: String,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+
+    let container = container.trim_start_matches('.').to_ascii_lowercase();
+    let accepted_codecs = remux_container_codecs(&container)?;
+
+    let streams = probe_stream_codecs(&source_path)?;
+    if streams.is_empty() {
+        return Err(ClipForgeError::invalid_argument(format!("Source has no video or audio streams: {}", source_path)));
+    }
+    if let Some(accepted_codecs) = accepted_codecs {
+        let incompatible: Vec<String> = streams
+            .iter()
+            .filter(|(_, codec)| !accepted_codecs.contains(&codec.as_str()))
+            .map(|(codec_type, codec)| format!("{} ({})", codec, codec_type))
+            .collect();
+        if !incompatible.is_empty() {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "{} can't be stored in {} without re-encoding; use transcode_video instead",
+                incompatible.join(", "),
+                container
+            )));
+        }
+    }
+
+    let output_path = std::path::Path::new(&output_path).with_extension(&container).to_string_lossy().to_string();
+
+    let output = Command::new(ffmpeg_binary())
+        .arg("-i")
+        .arg(&source_path)
+        .arg("-map")
+        .arg("0:v?")
+        .arg("-map")
+        .arg("0:a?")
+        .arg("-c")
+        .arg("copy")
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to remux video: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "remux".to_string());
+    settings.insert("container".to_string(), container.clone());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Video remuxed to {}", container),
+        duration_seconds: probe_duration_seconds(&output_path),
+        output_path: Some(output_path),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Video and audio codecs (by ffprobe codec_name) a container can hold without re-encoding
+/// None means the container takes any codec (mkv)
+fn remux_container_codecs(container: &str) -> Result<Option<&'static [&'static str]>, ClipForgeError> {
+    match container {
+        "mp4" | "m4v" => Ok(Some(&["h264", "hevc", "av1", "mpeg4", "aac", "mp3", "ac3", "eac3", "alac", "flac", "opus"])),
+        "mov" => Ok(Some(&["h264", "hevc", "prores", "mpeg4", "mjpeg", "aac", "mp3", "ac3", "alac", "pcm_s16le", "pcm_s24le", "pcm_f32le"])),
+        "webm" => Ok(Some(&["vp8", "vp9", "av1", "opus", "vorbis"])),
+        "mkv" => Ok(None),
+        other => Err(ClipForgeError::invalid_argument(format!(
+            "Unsupported container: {} (expected one of: mp4, m4v, mov, mkv, webm)",
+            other
+        ))),
+    }
+}
+
+/// (codec_type, codec_name) of every video and audio stream in a file, using ffprobe
+fn probe_stream_codecs(path: &str) -> Result<Vec<(String, String)>, ClipForgeError> {
+    let output = Command::new(ffprobe_binary())
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("stream=codec_type,codec_name")
+        .arg("-of")
+        .arg("compact=p=0")
+        .arg(path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run ffprobe: {}", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "ffprobe failed to read streams: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    // Lines look like "codec_name=h264|codec_type=video"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let mut codec_type = None;
+            let mut codec_name = None;
+            for (key, value) in line.split('|').filter_map(|field| field.split_once('=')) {
+                match key {
+                    "codec_type" => codec_type = Some(value.to_string()),
+                    "codec_name" => codec_name = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            Some((codec_type?, codec_name?))
+        })
+        .filter(|(codec_type, _)| codec_type == "video" || codec_type == "audio")
+        .collect())
+}
+
+/// Read a media file's container and stream metadata tags as (key, value) pairs using ffprobe
+/// Keys repeated across streams are only reported once
+fn probe_metadata_tags(path: &str) -> Result<Vec<(String, String)>, ClipForgeError> {
+    let output = Command::new(ffprobe_binary())
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format_tags:stream_tags")
+        .arg("-of")
+        .arg("default=noprint_wrappers=1")
+        .arg(path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run ffprobe: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "ffprobe failed to read metadata: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    // Output format: one "TAG:key=value" line per tag
+    let mut tags: Vec<(String, String)> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some((key, value)) = line.strip_prefix("TAG:").and_then(|tag| tag.split_once('=')) {
+            if !tags.iter().any(|(existing, _)| existing == key) {
+                tags.push((key.to_string(), value.to_string()));
+            }
+        }
+    }
+    Ok(tags)
+}
+
+/// Extract one thumbnail per chapter, taken at each chapter's start time
+/// Thumbnails are named `chapter_000.jpg`, `chapter_001.jpg`, ... by chapter index and scaled to
+/// `width` (default 320) keeping the aspect ratio. Returns the paths in chapter order
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn generate_chapter_thumbnails(
+    app: tauri::AppHandle,
+    source_path: String,
+    chapters: Vec<f64>,
+    output_dir: String,
+    width: Option<u32>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<Vec<String>, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::ChapterThumbnails { source_path, chapters, output_dir, width, extra_args })
+        .map(|result| result.output_paths)
+}
+
+/// generate_chapter_thumbnails itself, run on an export queue worker
+fn run_generate_chapter_thumbnails(
+    source_path: String,
+    chapters: Vec<f64>,
+    output_dir: String,
+    width: Option<u32>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    if !std::path::Path::new(&source_path).exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+    if let Some(invalid) = chapters.iter().find(|start| !start.is_finite() || **start < 0.0) {
+        return Err(ClipForgeError::invalid_argument(format!("Chapter start times must be non-negative, got {}", invalid)));
+    }
+
+    let width = width.unwrap_or(320);
+    if width == 0 {
+        return Err(ClipForgeError::invalid_argument("width must be greater than 0"));
+    }
+
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| ClipForgeError::io(format!("Failed to create thumbnail directory: {}", e), &e))?;
+
+    let mut thumbnails = Vec::with_capacity(chapters.len());
+    for (index, start) in chapters.iter().enumerate() {
+        let thumbnail_path = std::path::Path::new(&output_dir)
+            .join(format!("chapter_{:03}.jpg", index))
+            .to_string_lossy()
+            .to_string();
+
+        extract_frame_at(&source_path, *start, &thumbnail_path, Some(width), &extra_args)
+            .map_err(|e| ClipForgeError::process_failed(format!("Failed to create thumbnail for chapter {}: {}", index, e)))?;
+        thumbnails.push(thumbnail_path);
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "chapter_thumbnails".to_string());
+    settings.insert("width".to_string(), width.to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Created {} chapter thumbnails", thumbnails.len()),
+        output_path: Some(output_dir),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        output_paths: thumbnails,
+        ..Default::default()
+    })
+}
+
+/// Split a video into consecutive files of about `segment_seconds` each, without re-encoding
+/// Cuts land on the nearest keyframe, so segments can run slightly long
+/// Returns the produced file paths in order
+/// Runs through the export queue, so it waits behind queued and running exports
+#[tauri::command(async)]
+fn segment_video(
+    app: tauri::AppHandle,
+    source_path: String,
+    segment_seconds: u64,
+    output_dir: String,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<Vec<String>, ClipForgeError> {
+    export_queue::run(&app, ExportJobRequest::Segment { source_path, segment_seconds, output_dir, extra_args })
+        .map(|result| result.output_paths)
+}
+
+/// segment_video itself, run on an export queue worker
+fn run_segment_video(
+    source_path: String,
+    segment_seconds: u64,
+    output_dir: String,
+    extra_args: Option<Vec<String>>,
+) -> Result<ExportResult, ClipForgeError> {
+    let source = std::path::Path::new(&source_path);
+    if !source.exists() {
+        return Err(ClipForgeError::not_found(format!("Source file not found: {}", source_path)));
+    }
+    let extra_args = resolve_extra_args(extra_args)?;
+    if segment_seconds == 0 {
+        return Err(ClipForgeError::invalid_argument("segment_seconds must be greater than 0"));
+    }
+
+    let dir = std::path::Path::new(&output_dir);
+    std::fs::create_dir_all(dir)
+        .map_err(|e| ClipForgeError::io(format!("Failed to create output directory '{}': {}", output_dir, e), &e))?;
+
+    let stem = source.file_stem().and_then(|stem| stem.to_str()).unwrap_or("segment");
+    let extension = source.extension().and_then(|ext| ext.to_str()).unwrap_or("mp4");
+    let prefix = format!("{}_part", stem);
+    // FFmpeg lists the segments it wrote here, so leftovers from an earlier, longer split of the
+    // same file in `output_dir` aren't mistaken for part of this one
+    let list_path = unique_temp_path("segments").with_extension("txt");
+
+    let output = Command::new(ffmpeg_binary())
+        .arg("-i")
+        .arg(&source_path)
+        .arg("-map")
+        .arg("0")
+        .arg("-c")
+        .arg("copy")
+        .arg("-f")
+        .arg("segment")
+        .arg("-segment_time")
+        .arg(segment_seconds.to_string())
+        .arg("-reset_timestamps")
+        .arg("1")  // Each segment starts at 0 so it plays standalone
+        .arg("-segment_list")
+        .arg(&list_path)
+        .arg("-segment_list_type")
+        .arg("flat")  // One file name per line, in order
+        .args(&extra_args)
+        .arg("-y")
+        .arg(dir.join(format!("{}%03d.{}", prefix, extension)))
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    let list = std::fs::read_to_string(&list_path);
+    let _ = std::fs::remove_file(&list_path);
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to segment video: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let list = list.map_err(|e| ClipForgeError::io(format!("Failed to read FFmpeg's segment list: {}", e), &e))?;
+    let segments: Vec<String> = list
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| dir.join(name).to_string_lossy().to_string())
+        .collect();
+
+    if segments.is_empty() {
+        return Err(ClipForgeError::output_file_missing(format!("FFmpeg produced no segments in '{}'", output_dir)));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "segment".to_string());
+    settings.insert("segment_seconds".to_string(), segment_seconds.to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Split into {} segments", segments.len()),
+        output_path: Some(output_dir),
+        source_path: Some(source_path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        output_paths: segments,
+        ..Default::default()
+    })
+}
+
+/// Capture a single still frame of the screen to an image file
+/// `screen_device_index` is the avfoundation device (defaults to device 4, the first screen);
+/// without `output_path` a `clipforge-screenshot-<timestamp>.png` is written to the temp dir
+#[tauri::command]
+fn capture_screenshot(
+    output_path: Option<String>,
+    screen_device_index: Option<u32>,
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+) -> Result<ExportResult, ClipForgeError> {
+    let device_index = screen_device_index.unwrap_or(SCREEN_CAPTURE_DEVICE_OFFSET);
+    let extra_args = resolve_extra_args(extra_args)?;
+    let output = match output_path {
+        Some(path) => path,
+        None => default_recording_path("clipforge-screenshot", "screenshot", "png", false, None)?,
+    };
+
+    let result = Command::new(ffmpeg_binary())
+        .arg("-f")
+        .arg("avfoundation")
+        .arg("-capture_cursor")
+        .arg("1")
+        .arg("-i")
+        .arg(format!("{}:", device_index))
+        .arg("-frames:v")
+        .arg("1")
+        .args(&extra_args)
+        .arg("-y")
+        .arg(&output)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    if is_screen_permission_error(&stderr) {
+        return Err(ClipForgeError::permission_denied(SCREEN_RECORDING_PERMISSION_MESSAGE));
+    }
+    if is_device_open_error(&stderr) {
+        return Err(ClipForgeError::not_found(format!(
+            "Screen capture device {} could not be opened: {}",
+            device_index, stderr.trim()
+        )));
+    }
+    if !result.status.success() {
+        return Err(ClipForgeError::process_failed(format!("FFmpeg failed to capture screenshot: {}", stderr)));
+    }
+    if !std::path::Path::new(&output).exists() {
+        return Err(ClipForgeError::output_file_missing(format!("Screenshot was not written to '{}'", output)));
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "screenshot".to_string());
+    settings.insert("screen_device_index".to_string(), device_index.to_string());
+    if !extra_args.is_empty() {
+        settings.insert("extra_args".to_string(), extra_args.join(" "));
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: "Screenshot captured successfully".to_string(),
+        output_path: Some(output),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// Write the frame at `time_seconds` of a video to an image file, optionally scaled to `width`
+/// `extra_args` go right before the output path
+fn extract_frame_at(
+    source_path: &str,
+    time_seconds: f64,
+    output_path: &str,
+    width: Option<u32>,
+    extra_args: &[String],
+) -> Result<(), ClipForgeError> {
+    let mut cmd = Command::new(ffmpeg_binary());
+    // -ss before -i seeks on the input, which is much faster for late timestamps
+    cmd.arg("-ss")
+        .arg(format!("{:.3}", time_seconds))
+        .arg("-i")
+        .arg(source_path)
+        .arg("-frames:v")
+        .arg("1");
+
+    if let Some(width) = width {
+        cmd.arg("-vf").arg(format!("scale={}:-2", width));
+    }
+
+    let output = cmd
+        .arg("-q:v")
+        .arg("2")  // High JPEG quality
+        .args(extra_args)
+        .arg("-y")
+        .arg(output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to extract frame: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    // Seeking past the end succeeds without writing anything
+    if !std::path::Path::new(output_path).exists() {
+        return Err(ClipForgeError::invalid_argument(format!("No frame found at {:.3}s", time_seconds)));
+    }
+
+    Ok(())
+}
+
+/// Muxer for a live stream URL: FLV for RTMP(S), MPEG-TS for SRT, UDP and TCP
+/// udp:// and tcp:// let a local tool consume the capture, e.g. `ffplay "tcp://127.0.0.1:9000?listen"`
+fn stream_output_format(url: &str) -> Result<&'static str, ClipForgeError> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+    match scheme.as_deref() {
+        Some("rtmp") | Some("rtmps") => Ok("flv"),
+        Some("srt") | Some("udp") | Some("tcp") => Ok("mpegts"),
+        _ => Err(ClipForgeError::invalid_argument(format!(
+            "Unsupported stream_url '{}'. Expected an rtmp://, rtmps://, srt://, udp:// or tcp:// URL",
+            url
+        ))),
+    }
+}
+
+/// Whether a recording's output is a stream URL rather than a file on disk
+fn is_live_output(output_path: &str) -> bool {
+    output_path.contains("://")
+}
+
+/// Check whether a media file contains at least one audio stream using ffprobe
+fn has_audio_stream(path: &str) -> bool {
+    Command::new(ffprobe_binary())
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("a")
+        .arg("-show_entries")
+        .arg("stream=index")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(path)
+        .logged_output()
+        .map(|output| output.status.success() && !String::from_utf8_lossy(&output.stdout).trim().is_empty())
+        .unwrap_or(false)
+}
+
+/// Folder recordings are written to when no output_path is given
+/// The OS temp dir unless set_default_output_dir chose another folder
+fn recordings_dir() -> std::path::PathBuf {
+    DEFAULT_OUTPUT_DIR.lock().ok()
+        .and_then(|dir| dir.clone())
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Default recording path in the recordings folder: "<prefix>-<timestamp>.<extension>"
+/// With `auto_name` the sanitized foreground window title replaces the prefix,
+/// falling back to the prefix when no title is available
+/// A `filename_template` replaces the whole naming scheme, see expand_filename_template
+fn default_recording_path(
+    prefix: &str,
+    kind: &str,
+    extension: &str,
+    auto_name: bool,
+    filename_template: Option<&str>,
+) -> Result<String, ClipForgeError> {
+    if let Some(template) = filename_template {
+        return expand_filename_template(template, kind, extension, &recordings_dir())
+            .map(|path| path.to_string_lossy().to_string());
+    }
+
+    let name = if auto_name {
+        active_window::active_window_title()
+            .map(|title| active_window::sanitize_filename(&title))
+            .filter(|name| !name.is_empty())
+    } else {
+        None
+    };
+
+    recordings_dir()
+        .join(format!("{}-{}.{}", name.as_deref().unwrap_or(prefix), unix_timestamp(), extension))
+        .to_str()
+        .map(|path| path.to_string())
+        .ok_or_else(|| ClipForgeError::internal("Failed to create temp file path"))
+}
+
+/// Expand a file name template such as "{type} {date} {time}" into a path in `dir`
+/// Tokens: {date} (YYYY-MM-DD), {time} (HH-MM-SS, local time), {type} (screen, webcam, screen_webcam,
+/// dual_screen, screenshot) and {counter}, the lowest number from 1 whose file doesn't exist yet.
+/// Without {counter}, "-2", "-3", ... is appended when the name is taken, since FFmpeg's -y would overwrite it.
+/// The expanded name is sanitized like window titles, so characters illegal in file names become '-'
+fn expand_filename_template(template: &str, kind: &str, extension: &str, dir: &std::path::Path) -> Result<std::path::PathBuf, ClipForgeError> {
+    let now = chrono::Local::now();
+    let date = now.format("%Y-%m-%d").to_string();
+    let time = now.format("%H-%M-%S").to_string();
+
+    let render = |counter: u32, suffix: &str| -> Result<std::path::PathBuf, ClipForgeError> {
+        let mut name = String::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            name.push_str(&rest[..open]);
+            let close = rest[open..].find('}').map(|close| open + close).ok_or_else(|| {
+                ClipForgeError::invalid_argument(format!("Unclosed '{{' in filename_template '{}'", template))
+            })?;
+            match &rest[open + 1..close] {
+                "date" => name.push_str(&date),
+                "time" => name.push_str(&time),
+                "type" => name.push_str(kind),
+                "counter" => name.push_str(&format!("{:03}", counter)),
+                other => {
+                    return Err(ClipForgeError::invalid_argument(format!(
+                        "Unknown token '{{{}}}' in filename_template. Expected {{date}}, {{time}}, {{type}} or {{counter}}",
+                        other
+                    )))
+                }
+            }
+            rest = &rest[close + 1..];
+        }
+        name.push_str(rest);
+
+        let name = active_window::sanitize_filename(&name);
+        if name.is_empty() {
+            return Err(ClipForgeError::invalid_argument(format!("filename_template '{}' produced an empty file name", template)));
+        }
+        Ok(dir.join(format!("{}{}.{}", name, suffix, extension)))
+    };
+
+    let has_counter = template.contains("{counter}");
+    for counter in 1..=MAX_FILENAME_COUNTER {
+        let path = match (has_counter, counter) {
+            (true, _) => render(counter, "")?,
+            (false, 1) => render(1, "")?,
+            (false, _) => render(1, &format!("-{}", counter))?,
+        };
+        if !path.exists() {
+            return Ok(path);
+        }
+    }
+    Err(ClipForgeError::invalid_argument(format!(
+        "filename_template '{}' has no free name up to {}",
+        template, MAX_FILENAME_COUNTER
+    )))
+}
+
+/// Make sure the folder an output file will be written to exists and is writable
+/// Missing folders are created; a read-only one is reported before any work starts
+fn ensure_output_dir_writable(output_path: &str) -> Result<(), ClipForgeError> {
+    let dir = match std::path::Path::new(output_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+
+    std::fs::create_dir_all(dir)
+        .map_err(|e| ClipForgeError::io(format!("Output folder '{}' does not exist and could not be created: {}", dir.display(), e), &e))?;
+
+    // Metadata permissions don't account for ACLs or read-only volumes, so try an actual write
+    let probe_path = dir.join(format!(".clipforge-write-test-{}", std::process::id()));
+    std::fs::write(&probe_path, b"")
+        .map_err(|e| ClipForgeError::io(format!("Output folder '{}' is not writable: {}", dir.display(), e), &e))?;
+    let _ = std::fs::remove_file(&probe_path);
+
+    Ok(())
+}
+
+/// Current time as Unix seconds
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Read a media file's duration in seconds using ffprobe
+/// Returns None if ffprobe is unavailable or the duration can't be determined
+fn probe_duration_seconds(path: &str) -> Option<f64> {
+    let output = Command::new(ffprobe_binary())
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format=duration")
+        .arg("-of")
+        .arg("default=noprint_wrappers=1:nokey=1")
+        .arg(path)
+        .logged_output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout).trim().parse::<f64>().ok()
+}
+
+/// Read the first video stream's width and height using ffprobe
+fn probe_video_resolution(path: &str) -> Option<(u32, u32)> {
+    let output = Command::new(ffprobe_binary())
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
+        .arg("-show_entries")
+        .arg("stream=width,height")
+        .arg("-of")
+        .arg("csv=s=x:p=0")
+        .arg(path)
+        .logged_output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // Output looks like "1920x1080"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (width, height) = stdout.trim().split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Read the first video stream's frame rate using ffprobe, as a rational like "30/1" or "30000/1001"
+fn probe_video_framerate(path: &str) -> Option<String> {
+    let output = Command::new(ffprobe_binary())
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
+        .arg("-show_entries")
+        .arg("stream=r_frame_rate")
+        .arg("-of")
+        .arg("default=noprint_wrappers=1:nokey=1")
+        .arg(path)
+        .logged_output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let rate = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    // ffprobe reports "0/0" when the rate is unknown
+    (!rate.is_empty() && !rate.starts_with('0')).then_some(rate)
+}
+
+/// Build a crop filter that cuts the main screen capture down to one window
+/// Window bounds are in points while avfoundation captures pixels, so they are scaled by the display's
+/// pixel density, clipped to the screen, and rounded down to even sizes for yuv420p
+/// Returns the filter and the cropped size
+fn window_crop_filter(window_id: u32) -> Result<(String, (u32, u32)), ClipForgeError> {
+    let window = active_window::find_window(window_id)
+        .ok_or_else(|| ClipForgeError::not_found(format!("No on-screen window with id {}", window_id)))?;
+    let display = active_window::main_display()
+        .ok_or_else(|| ClipForgeError::internal("Failed to read the main display's geometry"))?;
+
+    // Only the main display is captured; clip the window to it
+    let left = window.x.max(0.0);
+    let top = window.y.max(0.0);
+    let right = (window.x + window.width).min(display.width);
+    let bottom = (window.y + window.height).min(display.height);
+    if right <= left || bottom <= top {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Window {} ({}) is not on the main display",
+            window_id, window.owner_name
+        )));
+    }
+
+    let to_even_pixels = |points: f64| ((points * display.scale) as u32) & !1;
+    let width = to_even_pixels(right - left);
+    let height = to_even_pixels(bottom - top);
+    if width == 0 || height == 0 {
+        return Err(ClipForgeError::invalid_argument(format!("Window {} is too small to record", window_id)));
+    }
+
+    let filter = format!(
+        "crop={}:{}:{}:{}",
+        width,
+        height,
+        to_even_pixels(left),
+        to_even_pixels(top)
+    );
+    Ok((filter, (width, height)))
+}
+
+/// Pixel size of "Capture screen `screen_index`" as avfoundation captures it, formatted "WIDTHxHEIGHT"
+fn display_resolution(screen_index: u32) -> Option<String> {
+    let display = active_window::display(screen_index)?;
+    Some(format!(
+        "{}x{}",
+        (display.width * display.scale) as u32,
+        (display.height * display.scale) as u32
+    ))
+}
+
+// Settings of start_screen_recording, taken by the command as one `options` object and built with
+// `..Default::default()` by the hotkey starter and internal callers
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ScreenRecordingOptions {
+    output_path: Option<String>,
+    audio_device_index: Option<u32>,
+    auto_stop_on_inactivity_seconds: Option<u64>,
+    inactivity_motion_threshold: Option<f64>, // freezedetect noise tolerance (0.0-1.0), higher ignores more motion
+    preroll_seconds: Option<u64>, // Prepend this many buffered seconds (requires start_preroll_buffer)
+    quality: Option<String>, // "low", "medium", "high", "lossless"
+    crf: Option<u32>, // Explicit CRF (0-51), overrides the quality preset's CRF
+    framerate: Option<u32>, // Capture and output framerate (1-120), defaults to 30
+    auto_name_from_active_window: Option<bool>, // Name the file after the foreground window when output_path is not set
+    delay_seconds: Option<u64>, // Count down this many seconds (emitting recording-countdown) before starting
+    audio_gain_db: Option<f32>, // Boost or cut captured audio (-30 to +30 dB); ignored without an audio device
+    denoise: Option<bool>, // Suppress steady background noise (fans, hum) on captured audio
+    container: Option<String>, // "mp4" (default), "webm" (VP9 + Opus), or "mkv"
+    bitrate: Option<String>, // Target video bitrate such as "5M" (capped VBR); mutually exclusive with crf
+    watermark_text: Option<String>, // Text burned into the bottom-left corner
+    show_timestamp: Option<bool>, // Burn the wall-clock time into the bottom-right corner
+    audio_bitrate: Option<String>, // e.g. "64k"; defaults to 192k
+    audio_sample_rate: Option<u32>, // Hz; defaults to 48000
+    audio_channels: Option<u8>, // 1 (mono) or 2 (stereo); defaults to 2
+    dry_run: Option<bool>, // Return the FFmpeg command in `command` without starting anything
+    speed_factor: Option<f64>, // Time-lapse speed-up (> 1.0), e.g. 10.0 plays 10 minutes back in 1
+    window_id: Option<u32>, // Record only this window (from list_windows) by cropping the main screen to it
+    highlight_cursor: Option<bool>, // Draw a ring around the pointer whenever the mouse is clicked
+    show_cursor: Option<bool>, // Include the mouse pointer in the capture; defaults to true
+    system_audio_device_index: Option<u32>, // Loopback device carrying desktop audio (e.g. BlackHole), recorded alongside the mic
+    separate_audio_tracks: Option<bool>, // Keep mic and system audio as two tracks instead of mixing them; defaults the container to mkv
+    video_codec: Option<String>, // "h264" (default), "hevc" (libx265; slow in software), or "hevc_videotoolbox" (hardware)
+    trim_start_ms: Option<u64>, // Drop this much from the start to hide garbled first frames; see DEFAULT_TRIM_START_MS, 0 disables
+    pixel_format: Option<String>, // Output pix_fmt, e.g. "yuv444p" for sharper small text; defaults to yuv420p
+    color_range: Option<String>, // "tv" (limited, default) or "pc" (full)
+    stream_url: Option<String>, // Send the capture live to an rtmp://, rtmps://, srt://, udp:// or tcp:// URL instead of writing a file
+    filename_template: Option<String>, // Name for generated paths, e.g. "{type} {date} {time}"; see expand_filename_template
+    detached: Option<bool>, // Keep FFmpeg running if the app quits; reattach_recordings picks it up again (macOS/Linux)
+    auto_framerate: Option<bool>, // Record at the display's refresh rate, capped at `framerate` (or 60 when not set)
+    low_priority: Option<bool>, // Run FFmpeg at a lowered CPU priority (nice 10 / BELOW_NORMAL) to keep the system responsive; may drop frames under load
+    keyframe_interval_seconds: Option<f64>, // Force a keyframe this often so later trims can cut close to the requested time
+    audio_device_indices: Option<Vec<u32>>, // More microphones, mixed with audio_device_index into one track (e.g. two interview mics)
+    overlay_image_path: Option<String>, // Logo composited over the video, e.g. a transparent PNG
+    overlay_position: Option<String>, // Corner for overlay_image_path: "top-right" (default), "top-left", "bottom-right", "bottom-left"
+    max_size_bytes: Option<u64>, // Stop automatically once the file reaches this size (FFmpeg -fs), e.g. for an upload cap
+    preview: Option<bool>, // Keep a small JPEG of the latest captured frame for get_preview_frame (refreshed about once a second)
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+    audio_highpass_hz: Option<u32>, // Cut the microphone below this frequency, e.g. 80 to remove rumble from voice
+    audio_lowpass_hz: Option<u32>, // Cut the microphone above this frequency, e.g. 12000 to tame hiss
+    // Only set by internal callers, not command parameters
+    #[serde(skip)]
+    screen_index: Option<u32>, // "Capture screen N" to record; 0 (the main display) when not set
+    #[serde(skip)]
+    internal: bool, // Started by ClipForge itself: not listed, stopped by the hotkey, or remembered for it
+}
+
+/// Start screen recording using FFmpeg
+/// Returns a process ID that can be used to stop the recording
+/// When `auto_stop_on_inactivity_seconds` is set, the recording is stopped automatically once
+/// the screen has not changed for that long, and a `recording-auto-stopped-idle` event is emitted
+/// A `speed_factor` above 1 records a time-lapse: the screen is sampled at framerate / speed_factor,
+/// which keeps CPU and file size low but means fast on-screen changes between samples are missed
+/// With `window_id` the capture is cropped to the window's bounds when recording starts; moving or
+/// resizing the window afterwards is not followed, and anything overlapping it is recorded too
+/// `highlight_cursor` uses avfoundation's click highlighting: macOS doesn't report the pointer position to
+/// FFmpeg, so a ring is drawn around the pointer while the mouse button is pressed rather than continuously
+/// `system_audio_device_index` opens a second audio input; it is mixed with the microphone unless
+/// `separate_audio_tracks` is set, which only has an effect when both audio devices are given
+/// For live output, `stream_url` sends FLV (RTMP) or MPEG-TS (SRT, UDP, TCP) to a server or a local
+/// consumer; start/stop work as usual but no file is left behind. `output_path: "-"` is refused, since
+/// ClipForge's own stdout isn't connected to anything that could read the stream
+/// Settings are passed as one `options` object keyed by the ScreenRecordingOptions field names, all optional
+#[tauri::command(async)]
+fn start_screen_recording(app: tauri::AppHandle, options: ScreenRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    start_screen_recording_with(app, options)
+}
+
+/// start_screen_recording with its settings already gathered into options
+fn start_screen_recording_with(app: tauri::AppHandle, options: ScreenRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    // Same settings with a fresh file name, for the recording hotkey; pre-roll can only be claimed once
+    let starter: RecordingStarter = {
+        let options = ScreenRecordingOptions { output_path: None, preroll_seconds: None, dry_run: None, ..options.clone() };
+        std::sync::Arc::new(move |app| start_screen_recording_with(app, options.clone()))
+    };
+    let ScreenRecordingOptions {
+        output_path,
+        audio_device_index,
+        auto_stop_on_inactivity_seconds,
+        inactivity_motion_threshold,
+        preroll_seconds,
+        quality,
+        crf,
+        framerate,
+        auto_name_from_active_window,
+        delay_seconds,
+        audio_gain_db,
+        denoise,
+        container,
+        bitrate,
+        watermark_text,
+        show_timestamp,
+        audio_bitrate,
+        audio_sample_rate,
+        audio_channels,
+        dry_run,
+        speed_factor,
+        window_id,
+        highlight_cursor,
+        show_cursor,
+        system_audio_device_index,
+        separate_audio_tracks,
+        video_codec,
+        trim_start_ms,
+        pixel_format,
+        color_range,
+        stream_url,
+        filename_template,
+        detached,
+        auto_framerate,
+        low_priority,
+        keyframe_interval_seconds,
+        audio_device_indices,
+        overlay_image_path,
+        overlay_position,
+        max_size_bytes,
+        preview,
+        extra_args,
+        audio_highpass_hz,
+        audio_lowpass_hz,
+        screen_index,
+        internal,
+    } = options;
+
+    let screen_index = screen_index.unwrap_or(0);
+    let (audio_device_index, extra_microphones) = resolve_microphones(audio_device_index, audio_device_indices)?;
+    let video_quality = resolve_video_quality(quality.as_deref(), crf, bitrate.as_deref())?
+        .with_pixel_format(pixel_format.as_deref(), color_range.as_deref())?;
+    let framerate = if auto_framerate.unwrap_or(false) {
+        resolve_auto_framerate(framerate, screen_index)?
+    } else {
+        resolve_framerate(framerate)?
+    };
+    let keyframe_args = keyframe_interval_args(keyframe_interval_seconds, framerate)?;
+    let size_limit_args = size_limit_args(max_size_bytes)?;
+    let (preview_path, preview_args) = preview_output(preview.unwrap_or(false));
+    let extra_args = resolve_extra_args(extra_args)?;
+    let image_overlay = image_overlay_filter(overlay_image_path.as_deref(), overlay_position.as_deref())?;
+    let audio_filters = build_audio_filters(audio_gain_db, denoise.unwrap_or(false), audio_highpass_hz, audio_lowpass_hz)?;
+    // mp4 players often only play the first audio track, so default to mkv for separate tracks
+    let separate_audio_tracks = separate_audio_tracks.unwrap_or(false);
+    let container = resolve_container(container.as_deref().or(separate_audio_tracks.then_some("mkv")), video_codec.as_deref())?;
+    container.check_pixel_format(&video_quality)?;
+    let audio_encoding = resolve_audio_encoding(container.audio_codec, audio_bitrate.as_deref(), audio_sample_rate, audio_channels)?;
+    let (text_file, text_filters) = build_text_overlay_filters(&app, watermark_text.as_deref(), show_timestamp.unwrap_or(false), dry_run.unwrap_or(false))?;
+
+    // Live output goes to a streaming server or a local consumer listening on udp:// or tcp://
+    let live_format = match (output_path.as_deref(), stream_url.as_deref()) {
+        (Some(_), Some(_)) => {
+            return Err(ClipForgeError::invalid_argument("output_path and stream_url are mutually exclusive; set only one"));
+        }
+        (Some("-"), None) => {
+            return Err(ClipForgeError::invalid_argument(
+                "output_path \"-\" is not supported: ClipForge's stdout has no reader. Use stream_url with a udp:// or tcp:// URL to feed another tool",
+            ));
+        }
+        (None, Some(url)) => Some(stream_output_format(url)?),
+        _ => None,
+    };
+    if live_format.is_some() {
+        if container.video_codec != "libx264" || container.audio_codec != "aac" {
+            return Err(ClipForgeError::invalid_argument("Live output requires H.264 video and AAC audio (the mp4 or mkv container)"));
+        }
+        if preroll_seconds.is_some() || separate_audio_tracks {
+            return Err(ClipForgeError::invalid_argument("preroll_seconds and separate_audio_tracks can't be used with live output"));
+        }
+        if max_size_bytes.is_some() {
+            return Err(ClipForgeError::invalid_argument("max_size_bytes can't be used with live output"));
+        }
+    }
+
+    // Pre-roll segments are H.264/AAC and are joined without re-encoding
+    if preroll_seconds.is_some() && container.video_codec != "libx264" {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "preroll_seconds is not supported with the {} container",
+            container.extension
+        )));
+    }
+    if preroll_seconds.is_some() && audio_encoding != AudioEncoding::default() {
+        return Err(ClipForgeError::invalid_argument(
+            "preroll_seconds requires the default audio bitrate, sample rate, and channels",
+        ));
+    }
+
+    // Time-lapse: capture fewer frames per second and retime them to the output framerate
+    let speed_factor = speed_factor.unwrap_or(1.0);
+    if !(1.0..=MAX_SPEED_FACTOR).contains(&speed_factor) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "speed_factor must be between 1.0 and {}, got {}",
+            MAX_SPEED_FACTOR, speed_factor
+        )));
+    }
+    let timelapse = speed_factor > 1.0;
+    let trim_start_ms = resolve_trim_start_ms(trim_start_ms, preroll_seconds.is_none() && !timelapse)?;
+    if timelapse && (audio_device_index.is_some() || system_audio_device_index.is_some()) {
+        return Err(ClipForgeError::invalid_argument("speed_factor can't be combined with audio recording"));
+    }
+    if timelapse && preroll_seconds.is_some() {
+        return Err(ClipForgeError::invalid_argument("speed_factor can't be combined with preroll_seconds"));
+    }
+    if !extra_microphones.is_empty() && preroll_seconds.is_some() {
+        return Err(ClipForgeError::invalid_argument("audio_device_indices can't be combined with preroll_seconds"));
+    }
+    if system_audio_device_index.is_some() && preroll_seconds.is_some() {
+        return Err(ClipForgeError::invalid_argument("system_audio_device_index can't be combined with preroll_seconds"));
+    }
+    // The pre-roll is joined on at stop, which would push the file past the limit
+    if max_size_bytes.is_some() && preroll_seconds.is_some() {
+        return Err(ClipForgeError::invalid_argument("max_size_bytes can't be combined with preroll_seconds"));
+    }
+    let capture_framerate = (framerate as f64 / speed_factor).max(MIN_TIMELAPSE_CAPTURE_FRAMERATE);
+    let highlight_cursor = highlight_cursor.unwrap_or(false);
+    let show_cursor = show_cursor.unwrap_or(true);
+
+    // Resolve the window before the countdown; the crop uses where it is right now
+    let window_crop = match window_id {
+        Some(_) if preroll_seconds.is_some() => {
+            return Err(ClipForgeError::invalid_argument("window_id can't be combined with preroll_seconds"));
+        }
+        // Window bounds are relative to the main display
+        Some(_) if screen_index != 0 => {
+            return Err(ClipForgeError::invalid_argument("window_id can only be recorded from the main display (screen 0)"));
+        }
+        Some(id) => Some(window_crop_filter(id)?),
+        None => None,
+    };
+    let resolution = match &window_crop {
+        Some((_, (width, height))) => Some(format!("{}x{}", width, height)),
+        None => display_resolution(screen_index),
+    };
+    let audio_enabled = audio_device_index.is_some() || system_audio_device_index.is_some();
+
+    // Validate inactivity detection settings before doing any work
+    if auto_stop_on_inactivity_seconds == Some(0) {
+        return Err(ClipForgeError::invalid_argument("auto_stop_on_inactivity_seconds must be greater than 0"));
+    }
+    let motion_threshold = inactivity_motion_threshold.unwrap_or(DEFAULT_INACTIVITY_MOTION_THRESHOLD);
+    if !(motion_threshold > 0.0 && motion_threshold <= 1.0) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "inactivity_motion_threshold must be between 0.0 (exclusive) and 1.0, got {}",
+            motion_threshold
+        )));
+    }
+
+    let dry_run = dry_run.unwrap_or(false);
+    let detached = detached.unwrap_or(false);
+    if !dry_run {
+        validate_capture_devices(&[], &microphones_and(&[audio_device_index, system_audio_device_index], &extra_microphones))?;
+    }
+
+    // Open the device the listing names "Capture screen N", so the refresh rate and size reported
+    // for screen N match what is recorded; only guess the device without a listing
+    let listed_screen_device = if dry_run { None } else { screen_capture_device(screen_index) };
+    let screen_device_candidates = screen_device_candidates(listed_screen_device, screen_index);
+
+    // Count down before picking the file name so the default timestamp reflects the actual start
+    if let (Some(delay), false) = (delay_seconds, dry_run) {
+        run_countdown(&app, delay)?;
+    }
+
+    // Generate output path if not provided
+    let output = if let Some(url) = stream_url {
+        url
+    } else if let Some(path) = output_path {
+        path
+    } else {
+        default_recording_path("clipforge-recording", "screen", container.extension, auto_name_from_active_window.unwrap_or(false), filename_template.as_deref())?
+    };
+
+    // Fail early on a missing or read-only folder instead of after FFmpeg starts
+    if live_format.is_none() && !dry_run {
+        ensure_output_dir_writable(&output)?;
+    }
+
+    // Check if FFmpeg is available
+    let ffmpeg_check = Command::new(ffmpeg_binary())
+        .arg("-version")
+        .logged_output();
+    
+    match ffmpeg_check {
+        Ok(_) => {},
+        Err(_) => return Err(ClipForgeError::ffmpeg_not_found("FFmpeg is not installed or not found in PATH. Please install FFmpeg to use screen recording.")),
+    }
+
+    // Claim the buffered pre-roll before spawning so the rolling capture releases the screen
+    let preroll_segments = match preroll_seconds {
+        Some(seconds) if !dry_run => {
+            Some(take_preroll_segments(seconds, audio_device_index, screen_index, show_cursor, framerate, &video_quality)?)
+        }
+        _ => None,
+    };
+
+    // Construct FFmpeg command for macOS using avfoundation
+    // Screen capture devices start at index 4 (Capture screen 0), 5 (Capture screen 1), etc.
+    // Format: ffmpeg -f avfoundation -i "4:0" -r 30 -c:v libx264 -preset fast -crf 23 -pix_fmt yuv420p -c:a aac -b:a 192k -ar 48000 output.mp4
+    // "4:0" means screen capture device 4 (first screen), audio device 0 (first microphone)
+    // "4:" means screen capture device 4, no audio device
+    
+    // Without a listing the screen is usually device 4, but machines with fewer cameras list it earlier,
+    // so retry neighboring indices when avfoundation can't open the device
+    let mut failed_attempts: Vec<String> = Vec::new();
+    let mut started = None;
+    for &screen_device_index in &screen_device_candidates {
+        // Build input device string: "video_device:audio_device" or "video_device:" if no audio
+        let input_device = match audio_device_index {
+            Some(audio_idx) => format!("{}:{}", screen_device_index, audio_idx),
+            None => format!("{}:", screen_device_index),
+        };
+
+        let mut cmd = Command::new(ffmpeg_binary());
+        cmd.arg("-f")
+            .arg("avfoundation")
+            .arg("-capture_cursor")
+            .arg(if show_cursor { "1" } else { "0" })  // Capture cursor
+            .arg("-capture_mouse_clicks")
+            .arg(if highlight_cursor { "1" } else { "0" })  // Ring around the pointer on clicks
+            .arg("-framerate")
+            .arg(capture_framerate.to_string())  // Input framerate (lowered for time-lapse)
+            .arg("-i")
+            .arg(&input_device);  // Screen capture device, optional audio device
+
+        // Second avfoundation input with only the system audio device (":N" means no video)
+        if let Some(system_audio_idx) = system_audio_device_index {
+            cmd.arg("-f")
+                .arg("avfoundation")
+                .arg("-i")
+                .arg(format!(":{}", system_audio_idx));
+        }
+        let first_extra_microphone_input = 1 + usize::from(system_audio_device_index.is_some());
+        add_microphone_inputs(&mut cmd, &extra_microphones);
+    
+        // Add audio encoding parameters if audio device is provided
+        if audio_device_index.is_some() || system_audio_device_index.is_some() {
+            cmd.arg("-c:a")
+                .arg(container.audio_codec)  // Audio codec
+                .arg("-b:a")
+                .arg(&audio_encoding.bitrate)  // Audio bitrate (192k by default)
+                .arg("-ar")
+                .arg(audio_encoding.sample_rate.to_string())  // Sample rate (48 kHz by default)
+                .arg("-ac")
+                .arg(audio_encoding.channels.to_string());  // Channels (stereo by default)
+        }
+
+        // Gain and denoise only ever apply to the microphone
+        // Extra microphones are mixed into the primary one first (resolve_microphones guarantees there is one)
+        let mic_mix = (!extra_microphones.is_empty()).then(|| {
+            microphone_mix_filter(0, first_extra_microphone_input, extra_microphones.len(), &audio_filters)
+        });
+        match (audio_device_index, system_audio_device_index, mic_mix) {
+            (_, Some(_), Some(mic_mix)) if separate_audio_tracks => {
+                cmd.arg("-filter_complex")
+                    .arg(mic_mix)
+                    .arg("-map")
+                    .arg("0:v")
+                    .arg("-map")
+                    .arg("[mic]")  // Track 1: mixed microphones
+                    .arg("-map")
+                    .arg("1:a")  // Track 2: system audio
+                    .arg("-metadata:s:a:0")
+                    .arg("title=Microphones")
+                    .arg("-metadata:s:a:1")
+                    .arg("title=System audio");
+            }
+            (_, Some(_), Some(mic_mix)) => {
+                cmd.arg("-filter_complex")
+                    .arg(format!("{};[mic][1:a]amix=inputs=2:duration=longest[audio]", mic_mix))
+                    .arg("-map")
+                    .arg("0:v")
+                    .arg("-map")
+                    .arg("[audio]");
+            }
+            (_, None, Some(mic_mix)) => {
+                cmd.arg("-filter_complex").arg(mic_mix).arg("-map").arg("0:v").arg("-map").arg("[mic]");
+            }
+            (Some(_), Some(_), None) if separate_audio_tracks => {
+                cmd.arg("-map")
+                    .arg("0:v")
+                    .arg("-map")
+                    .arg("0:a")  // Track 1: microphone
+                    .arg("-map")
+                    .arg("1:a")  // Track 2: system audio
+                    .arg("-metadata:s:a:0")
+                    .arg("title=Microphone")
+                    .arg("-metadata:s:a:1")
+                    .arg("title=System audio");
+
+                if !audio_filters.is_empty() {
+                    cmd.arg("-filter:a:0").arg(audio_filters.join(","));
+                }
+            }
+            (Some(_), Some(_), None) => {
+                let mic = if audio_filters.is_empty() {
+                    "[0:a]".to_string()
+                } else {
+                    format!("[0:a]{}[mic];[mic]", audio_filters.join(","))
+                };
+                cmd.arg("-filter_complex")
+                    .arg(format!("{}[1:a]amix=inputs=2:duration=longest[audio]", mic))
+                    .arg("-map")
+                    .arg("0:v")
+                    .arg("-map")
+                    .arg("[audio]");
+            }
+            (None, Some(_), None) => {
+                cmd.arg("-map").arg("0:v").arg("-map").arg("1:a");
+            }
+            (Some(_), None, None) => {
+                if !audio_filters.is_empty() {
+                    cmd.arg("-af").arg(audio_filters.join(","));
+                }
+            }
+            (None, None, None) => {}
+        }
+
+        // Collect video filters so they can be applied as a single -vf chain
+        let mut video_filters: Vec<String> = Vec::new();
+
+        // Crop first so inactivity detection and overlays only see the window
+        video_filters.extend(window_crop.iter().map(|(filter, _)| filter.clone()));
+
+        // freezedetect logs "freeze_start" to stderr once the picture has been static for the given duration
+        if let Some(idle_seconds) = auto_stop_on_inactivity_seconds {
+            video_filters.push(format!("freezedetect=n={}:d={}", motion_threshold, idle_seconds));
+        }
+
+        video_filters.extend(text_filters.iter().cloned());
+        video_filters.extend(image_overlay.iter().cloned());
+
+        // Compress timestamps so the sparse capture plays back at the full output rate;
+        // -r below then keeps the output constant-framerate
+        if timelapse {
+            video_filters.push(format!("setpts=PTS/{}", speed_factor));
+        }
+
+        if !video_filters.is_empty() {
+            cmd.arg("-vf").arg(video_filters.join(","));
+        }
+
+        cmd.args(trim_start_args(trim_start_ms));
+
+        cmd.arg("-r")
+            .arg(framerate.to_string())  // Output framerate
+            .arg("-c:v")
+            .arg(container.video_codec)  // Video codec
+            .args(container.tag_args())  // hvc1 tag for HEVC in mp4
+            .args(container.speed_args(&video_quality))  // Encoding speed
+            .args(container.rate_control_args(&video_quality))  // Quality: CRF or capped bitrate
+            .args(video_quality.pixel_format_args())  // Pixel format and color range
+            .args(&keyframe_args)  // GOP length
+            .args(&size_limit_args);  // Stop at max_size_bytes
+
+        match live_format {
+            Some(format) => {
+                cmd.arg("-f").arg(format);  // No file extension to infer the muxer from
+            }
+            None => {
+                cmd.args(container.recording_mux_args());  // Fragmented mp4 survives a killed FFmpeg
+            }
+        }
+
+        cmd.args(&extra_args)  // Caller's own flags, applied to the main output
+            .arg("-y")  // Overwrite output file
+            .arg(&output)
+            .args(&preview_args)  // Second output refreshing the preview JPEG
+            // Piped for the startup check; once tracked, StderrLog drains it so FFmpeg never blocks on a full pipe
+            .stderr(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stdin(std::process::Stdio::piped());  // Stop sends FFmpeg's "q" command here
+
+        if detached {
+            detach_from_app(&mut cmd)?;
+        }
+
+        if low_priority.unwrap_or(false) {
+            lower_priority(&mut cmd);
+        }
+
+        if dry_run {
+            return Ok(RecordingResult {
+                process_id: 0,
+                output_path: output,
+                screen_device_index: Some(screen_device_index),
+                command: Some(command_argv(&cmd)),
+                framerate,
+                resolution,
+                audio_enabled,
+            });
+        }
+
+        // Spawn the FFmpeg process
+        let mut child = cmd.logged_spawn()
+            .map_err(|e| ClipForgeError::spawn(format!("Failed to start FFmpeg process: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+        // Give FFmpeg a moment to initialize and check if it's still running
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        // Check if process immediately crashed
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Process already exited - try to read stderr for error info
+                let mut error_output = String::new();
+                if let Some(mut stderr) = child.stderr.take() {
+                    use std::io::Read;
+                    let _ = stderr.read_to_string(&mut error_output);
+                }
+
+                // Only a device that couldn't be opened is worth retrying on another index;
+                // without Screen Recording permission no index will open
+                if !is_device_open_error(&error_output) || is_screen_permission_error(&error_output) {
+                    return Err(immediate_exit_error(status, &error_output, true));
+                }
+                failed_attempts.push(format!("Device {}: {}", screen_device_index, error_output.trim()));
+            }
+            Ok(None) => {
+                // Process is still running, good!
+                started = Some((child, screen_device_index));
+                break;
+            }
+            Err(e) => {
+                return Err(ClipForgeError::internal(format!("Failed to check FFmpeg process status: {}", e)));
+            }
+        }
+    }
+
+    let (child, screen_device_index) = started.ok_or_else(|| {
+        ClipForgeError::not_found(format!(
+            "Could not open a screen capture device (tried {:?}).\n{}",
+            screen_device_candidates,
+            failed_attempts.join("\n")
+        ))
+    })?;
+
+    // A guessed neighbor may be a camera or another screen, whose size isn't known
+    let resolution = match (&window_crop, &listed_screen_device) {
+        (None, None) if screen_device_index != SCREEN_CAPTURE_DEVICE_OFFSET + screen_index => None,
+        _ => resolution,
+    };
+
+    // Key the recording by a ClipForge ID rather than the PID, which the OS can reuse
+    let process_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
+
+    // Inactivity detection watches FFmpeg's stderr, so have the stderr reader forward its lines
+    let (idle_monitor_sender, idle_monitor_lines) = match auto_stop_on_inactivity_seconds {
+        Some(_) => {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            (Some(sender), Some(receiver))
+        }
+        None => (None, None),
+    };
+
+    let info = RecordingInfo::new("screen", framerate, audio_device_index, max_size_bytes)
+        .with_preview(preview_path)
+        .with_text_file(text_file)
+        .with_internal(internal);
+    track_recording(process_id, RecordingProcess::with_stderr_forwarding(child, output.clone(), info, idle_monitor_sender), detached);
+
+    if let Some(segments) = preroll_segments {
+        let mut preroll = PREROLL_SEGMENTS.lock()
+            .map_err(|e| ClipForgeError::internal(format!("Failed to lock pre-roll segments: {}", e)))?;
+        preroll.insert(process_id, segments);
+    }
+
+    if let (Some(idle_seconds), Some(lines)) = (auto_stop_on_inactivity_seconds, idle_monitor_lines) {
+        std::thread::spawn(move || monitor_recording_inactivity(app, process_id, idle_seconds, lines));
+    }
+
+    if !internal {
+        remember_recording_starter(starter);
+    }
+
+    Ok(RecordingResult {
+        process_id,
+        output_path: output,
+        screen_device_index: Some(screen_device_index),
+        command: None,
+        framerate,
+        resolution,
+        audio_enabled,
+    })
+}
+
+/// Watch a recording's stderr lines for freezedetect output and stop the recording once the screen goes idle
+/// Runs until FFmpeg closes stderr (i.e. the process exits)
+fn monitor_recording_inactivity(
+    app: tauri::AppHandle,
+    process_id: u64,
+    idle_seconds: u64,
+    lines: std::sync::mpsc::Receiver<String>,
+) {
+    for line in lines {
+        if !line.contains("freeze_start") {
+            continue;
+        }
+
+        match stop_screen_recording(process_id, None, None, None) {
+            Ok(result) => {
+                let event = RecordingAutoStoppedEvent {
+                    process_id,
+                    file_path: result.file_path,
+                    idle_seconds,
+                };
+                if let Err(e) = app.emit("recording-auto-stopped-idle", event) {
+                    log::error!("Failed to emit recording-auto-stopped-idle event: {}", e);
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to auto-stop idle recording {}: {}", process_id, e);
+            }
+        }
+        break;
+    }
+}
+
+/// libx264 settings resolved from a quality preset and optional explicit CRF or bitrate
+#[derive(Debug, Clone, PartialEq)]
+struct VideoQuality {
+    preset: &'static str,
+    crf: u32,
+    pix_fmt: String,
+    color_range: &'static str, // "tv" (limited) unless "pc" (full) is requested; always tagged and converted to
+    bitrate: Option<u64>, // Target bits per second; replaces CRF with capped VBR when set
+}
+
+impl VideoQuality {
+    /// Override the preset's pixel format and color range, both checked before FFmpeg runs
+    /// `pixel_format` must be an output format this FFmpeg build knows (see `ffmpeg -pix_fmts`)
+    fn with_pixel_format(mut self, pixel_format: Option<&str>, color_range: Option<&str>) -> Result<Self, ClipForgeError> {
+        if let Some(pixel_format) = pixel_format {
+            if !ffmpeg_output_pixel_formats()?.iter().any(|known| known == pixel_format) {
+                return Err(ClipForgeError::invalid_argument(format!(
+                    "Unknown pixel_format '{}'. Run `ffmpeg -pix_fmts` for the formats this FFmpeg supports",
+                    pixel_format
+                )));
+            }
+            self.pix_fmt = pixel_format.to_string();
+        }
+
+        self.color_range = match color_range {
+            None | Some("tv") => "tv",
+            Some("pc") => "pc",
+            Some(other) => {
+                return Err(ClipForgeError::invalid_argument(format!(
+                    "Unknown color_range '{}'. Expected one of: tv, pc",
+                    other
+                )))
+            }
+        };
+
+        Ok(self)
+    }
+
+    /// "-pix_fmt" and "-color_range" output options
+    fn pixel_format_args(&self) -> Vec<&str> {
+        vec!["-pix_fmt", self.pix_fmt.as_str(), "-color_range", self.color_range]
+    }
+}
+
+/// Pixel formats the FFmpeg build can write, parsed from `ffmpeg -pix_fmts`
+fn ffmpeg_output_pixel_formats() -> Result<Vec<String>, ClipForgeError> {
+    let output = Command::new(ffmpeg_binary())
+        .arg("-hide_banner")
+        .arg("-pix_fmts")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    // Rows look like "IO... yuv420p   3   12   8-8-8" after a "-----" separator;
+    // the second flag is 'O' when the format is supported for output
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("-----"))
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let flags = columns.next()?;
+            let name = columns.next()?;
+            (flags.chars().nth(1) == Some('O')).then(|| name.to_string())
+        })
+        .collect())
+}
+
+/// Map a friendly quality preset onto libx264 settings
+/// An explicit `crf` always wins over the preset's CRF; no preset means "medium"
+/// `bitrate` (e.g. "5M", "2500k") switches to capped VBR and can't be combined with `crf`
+fn resolve_video_quality(quality: Option<&str>, crf: Option<u32>, bitrate: Option<&str>) -> Result<VideoQuality, ClipForgeError> {
+    let mut video_quality = match quality.unwrap_or("medium") {
+        "low" => VideoQuality { preset: "veryfast", crf: 28, pix_fmt: "yuv420p".to_string(), color_range: "tv", bitrate: None },
+        "medium" => VideoQuality { preset: "fast", crf: 23, pix_fmt: "yuv420p".to_string(), color_range: "tv", bitrate: None },
+        "high" => VideoQuality { preset: "medium", crf: 18, pix_fmt: "yuv420p".to_string(), color_range: "tv", bitrate: None },
+        // CRF 0 is mathematically lossless in libx264; keep full chroma so text stays exact
+        "lossless" => VideoQuality { preset: "ultrafast", crf: 0, pix_fmt: "yuv444p".to_string(), color_range: "tv", bitrate: None },
+        other => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Unknown quality '{}'. Expected one of: low, medium, high, lossless",
+                other
+            )))
+        }
+    };
+
+    if let Some(crf) = crf {
+        if crf > 51 {
+            return Err(ClipForgeError::invalid_argument(format!("crf must be between 0 and 51, got {}", crf)));
+        }
+        video_quality.crf = crf;
+    }
+
+    if let Some(bitrate) = bitrate {
+        if crf.is_some() {
+            return Err(ClipForgeError::invalid_argument("crf and bitrate are mutually exclusive; set only one"));
+        }
+        video_quality.bitrate = Some(parse_bitrate(bitrate).ok_or_else(|| {
+            ClipForgeError::invalid_argument(format!(
+                "Invalid bitrate '{}'. Expected a number of bits per second with an optional k or M suffix, e.g. \"5M\"",
+                bitrate
+            ))
+        })?);
+    }
+
+    Ok(video_quality)
+}
+
+/// Parse an FFmpeg-style bitrate such as "5M", "2500k", or "800000" into bits per second
+fn parse_bitrate(bitrate: &str) -> Option<u64> {
+    let bitrate = bitrate.trim();
+    let (number, multiplier) = match bitrate.char_indices().last()? {
+        (i, 'k') | (i, 'K') => (&bitrate[..i], 1_000.0),
+        (i, 'm') | (i, 'M') => (&bitrate[..i], 1_000_000.0),
+        _ => (bitrate, 1.0),
+    };
+    let bits = number.parse::<f64>().ok()? * multiplier;
+    if bits.is_finite() && bits >= 1.0 {
+        Some(bits as u64)
+    } else {
+        None
+    }
+}
+
+/// Output container and the codecs it is recorded with
+/// H.264 by default; libx265 and hevc_videotoolbox can be chosen for mp4/mkv
+struct RecordingContainer {
+    extension: &'static str,
+    video_codec: &'static str,
+    audio_codec: &'static str,
+}
+
+impl RecordingContainer {
+    /// Encoder speed arguments for the container's video codec
+    /// libvpx-vp9 has no -preset, so the x264 preset is mapped onto realtime -cpu-used levels
+    fn speed_args(&self, video_quality: &VideoQuality) -> Vec<String> {
+        // The hardware encoder has no speed presets
+        if self.video_codec == "hevc_videotoolbox" {
+            return Vec::new();
+        }
+        if self.video_codec != "libvpx-vp9" {
+            return vec!["-preset".to_string(), video_quality.preset.to_string()];
+        }
+
+        let cpu_used = match video_quality.preset {
+            "ultrafast" | "veryfast" => "8",
+            "fast" => "6",
+            _ => "5",
+        };
+        ["-deadline", "realtime", "-cpu-used", cpu_used, "-row-mt", "1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    /// Rate control arguments: constant quality (-crf) by default, or capped VBR when a bitrate is set
+    fn rate_control_args(&self, video_quality: &VideoQuality) -> Vec<String> {
+        if self.video_codec == "hevc_videotoolbox" {
+            if let Some(bitrate) = video_quality.bitrate {
+                return vec!["-b:v".to_string(), bitrate.to_string()];
+            }
+            // VideoToolbox takes a 1-100 quality instead of a CRF and has no lossless mode,
+            // so CRF 0 maps onto its best quality
+            let quality = 100u32.saturating_sub(video_quality.crf * 2).max(1);
+            return vec!["-q:v".to_string(), quality.to_string()];
+        }
+
+        if let Some(bitrate) = video_quality.bitrate {
+            // Cap peaks at the target rate with a two-second buffer so file size stays predictable
+            return vec![
+                "-b:v".to_string(),
+                bitrate.to_string(),
+                "-maxrate".to_string(),
+                bitrate.to_string(),
+                "-bufsize".to_string(),
+                (bitrate * 2).to_string(),
+            ];
+        }
+
+        let mut args = vec!["-crf".to_string(), video_quality.crf.to_string()];
+        if self.video_codec == "libvpx-vp9" {
+            // VP9 only honors -crf in constant-quality mode (-b:v 0); CRF 0 alone isn't lossless
+            if video_quality.crf == 0 {
+                args.extend(["-lossless".to_string(), "1".to_string()]);
+            } else {
+                args.extend(["-b:v".to_string(), "0".to_string()]);
+            }
+        }
+        if self.video_codec == "libx265" && video_quality.crf == 0 {
+            // Like VP9, x265 needs lossless mode explicitly
+            args.extend(["-x265-params".to_string(), "lossless=1".to_string()]);
+        }
+        args
+    }
+
+    /// Refuse pixel formats the video encoder can't take; VideoToolbox HEVC only encodes 4:2:0,
+    /// so e.g. the lossless preset's yuv444p would fail once FFmpeg is already running
+    fn check_pixel_format(&self, video_quality: &VideoQuality) -> Result<(), ClipForgeError> {
+        if self.video_codec == "hevc_videotoolbox" && !HEVC_VIDEOTOOLBOX_PIXEL_FORMATS.contains(&video_quality.pix_fmt.as_str()) {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "hevc_videotoolbox can't encode pixel_format '{}'. Use one of: {}",
+                video_quality.pix_fmt,
+                HEVC_VIDEOTOOLBOX_PIXEL_FORMATS.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Muxer flags for a recording; mp4 is written fragmented with the index up front, so a recording
+    /// whose FFmpeg is killed before writing the trailer still plays up to the last fragment
+    fn recording_mux_args(&self) -> Vec<String> {
+        if self.extension == "mp4" {
+            vec!["-movflags".to_string(), "+frag_keyframe+empty_moov".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Codec tag arguments; QuickTime only plays HEVC in mp4 when it is tagged hvc1 rather than hev1
+    fn tag_args(&self) -> Vec<String> {
+        let is_hevc = matches!(self.video_codec, "libx265" | "hevc_videotoolbox");
+        if is_hevc && self.extension == "mp4" {
+            vec!["-tag:v".to_string(), "hvc1".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Map a container name and optional video codec ("h264", "hevc", "hevc_videotoolbox") onto the
+/// extension and encoders to use; without a codec the container's default is used
+fn resolve_container(container: Option<&str>, video_codec: Option<&str>) -> Result<RecordingContainer, ClipForgeError> {
+    let mut resolved = resolve_container_defaults(container)?;
+    let Some(video_codec) = video_codec else {
+        return Ok(resolved);
+    };
+
+    if resolved.extension == "webm" {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "video_codec '{}' is not supported in webm, which always uses VP9",
+            video_codec
+        )));
+    }
+    resolved.video_codec = match video_codec {
+        "h264" => "libx264",
+        "hevc" => {
+            log::warn!("libx265 encodes several times slower than libx264 in software and may drop frames on live captures");
+            "libx265"
+        }
+        "hevc_videotoolbox" => "hevc_videotoolbox",
+        other => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Unknown video_codec '{}'. Expected one of: h264, hevc, hevc_videotoolbox",
+                other
+            )))
+        }
+    };
+    Ok(resolved)
+}
+
+fn resolve_container_defaults(container: Option<&str>) -> Result<RecordingContainer, ClipForgeError> {
+    match container.unwrap_or("mp4") {
+        "mp4" => Ok(RecordingContainer { extension: "mp4", video_codec: "libx264", audio_codec: "aac" }),
+        "mkv" => Ok(RecordingContainer { extension: "mkv", video_codec: "libx264", audio_codec: "aac" }),
+        // WebM only carries VP8/VP9/AV1 video and Vorbis/Opus audio
+        "webm" => Ok(RecordingContainer { extension: "webm", video_codec: "libvpx-vp9", audio_codec: "libopus" }),
+        other => Err(ClipForgeError::invalid_argument(format!(
+            "Unknown container '{}'. Expected one of: mp4, webm, mkv",
+            other
+        ))),
+    }
+}
+
+/// Pick the codecs for a re-encode from the output file's extension (webm: VP9 + Opus,
+/// mkv and anything else: H.264 + AAC unless `video_codec` says otherwise)
+fn resolve_output_container(output_path: &std::path::Path, video_codec: Option<&str>) -> Result<RecordingContainer, ClipForgeError> {
+    let extension = output_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    match extension.as_deref() {
+        Some("webm") => resolve_container(Some("webm"), video_codec),
+        Some("mkv") => resolve_container(Some("mkv"), video_codec),
+        _ => resolve_container(Some("mp4"), video_codec),
+    }
+}
+
+/// Validate how much to trim from the start of a recording, defaulting to DEFAULT_TRIM_START_MS
+/// unless `trim_by_default` is false (pre-roll and time-lapse recordings)
+fn resolve_trim_start_ms(trim_start_ms: Option<u64>, trim_by_default: bool) -> Result<u64, ClipForgeError> {
+    match trim_start_ms {
+        None if trim_by_default => Ok(DEFAULT_TRIM_START_MS),
+        None => Ok(0),
+        Some(ms) if ms <= MAX_TRIM_START_MS => Ok(ms),
+        Some(ms) => Err(ClipForgeError::invalid_argument(format!(
+            "trim_start_ms must be at most {}, got {}",
+            MAX_TRIM_START_MS, ms
+        ))),
+    }
+}
+
+/// Output "-ss" dropping the first `trim_start_ms` of the recording; none when it is 0
+fn trim_start_args(trim_start_ms: u64) -> Vec<String> {
+    if trim_start_ms == 0 {
+        return Vec::new();
+    }
+    vec!["-ss".to_string(), format!("{:.3}", trim_start_ms as f64 / 1000.0)]
+}
+
+/// Encoder arguments for a fixed keyframe interval; none when unset, leaving FFmpeg's default GOP
+/// `-g` caps the GOP in frames and `-force_key_frames` keeps keyframes on the interval even when
+/// the encoder would place them elsewhere (e.g. on scene cuts)
+fn keyframe_interval_args(keyframe_interval_seconds: Option<f64>, framerate: u32) -> Result<Vec<String>, ClipForgeError> {
+    let Some(interval) = keyframe_interval_seconds else {
+        return Ok(Vec::new());
+    };
+    if !(MIN_KEYFRAME_INTERVAL_SECONDS..=MAX_KEYFRAME_INTERVAL_SECONDS).contains(&interval) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "keyframe_interval_seconds must be between {} and {}, got {}",
+            MIN_KEYFRAME_INTERVAL_SECONDS, MAX_KEYFRAME_INTERVAL_SECONDS, interval
+        )));
+    }
+
+    let gop_frames = ((interval * framerate as f64).round() as u32).max(1);
+    Ok(vec![
+        "-g".to_string(),
+        gop_frames.to_string(),
+        "-force_key_frames".to_string(),
+        format!("expr:gte(t,n_forced*{})", interval),
+    ])
+}
+
+/// Check caller-supplied extra FFmpeg arguments; none when unset
+/// They are passed through as-is (no shell is involved), so only empty entries and runaway lists
+/// are rejected; whether the flags make sense is left to FFmpeg
+fn resolve_extra_args(extra_args: Option<Vec<String>>) -> Result<Vec<String>, ClipForgeError> {
+    let extra_args = extra_args.unwrap_or_default();
+    if extra_args.len() > MAX_EXTRA_ARGS {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "extra_args can hold at most {} arguments, got {}",
+            MAX_EXTRA_ARGS,
+            extra_args.len()
+        )));
+    }
+    if extra_args.iter().any(|arg| arg.trim().is_empty()) {
+        return Err(ClipForgeError::invalid_argument("extra_args can't contain empty arguments"));
+    }
+    if !extra_args.is_empty() {
+        log::info!("Passing extra FFmpeg arguments: {:?}", extra_args);
+    }
+    Ok(extra_args)
+}
+
+/// Output arguments that make FFmpeg stop by itself once the file reaches `max_size_bytes`
+/// FFmpeg finalizes the file normally when the limit hits, so the recording just exits and
+/// waits for stop_screen_recording to collect it
+fn size_limit_args(max_size_bytes: Option<u64>) -> Result<Vec<String>, ClipForgeError> {
+    match max_size_bytes {
+        None => Ok(Vec::new()),
+        Some(bytes) if bytes >= MIN_MAX_SIZE_BYTES => Ok(vec!["-fs".to_string(), bytes.to_string()]),
+        Some(bytes) => Err(ClipForgeError::invalid_argument(format!(
+            "max_size_bytes must be at least {}, got {}",
+            MIN_MAX_SIZE_BYTES, bytes
+        ))),
+    }
+}
+
+/// Second FFmpeg output for a live preview: a small JPEG in the temp dir overwritten with the latest frame
+/// It reads the first input's video directly, so crops and overlays applied to the recording don't show,
+/// and dual_screen and screen_webcam recordings only preview the first screen, not the composed frame
+fn preview_output(preview: bool) -> (Option<std::path::PathBuf>, Vec<String>) {
+    if !preview {
+        return (None, Vec::new());
+    }
+
+    let preview_path = std::env::temp_dir().join(format!(
+        "clipforge-preview-{}-{}.jpg",
+        std::process::id(),
+        NEXT_PREVIEW_ID.fetch_add(1, Ordering::SeqCst)
+    ));
+    let args = vec![
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-vf".to_string(),
+        format!("fps={},scale={}:-2", PREVIEW_FRAMERATE, PREVIEW_WIDTH),
+        "-q:v".to_string(),
+        "5".to_string(),
+        "-f".to_string(),
+        "image2".to_string(),
+        "-update".to_string(),
+        "1".to_string(),  // Keep overwriting one file instead of numbering frames
+        "-y".to_string(),
+        preview_path.to_string_lossy().to_string(),
+    ];
+    (Some(preview_path), args)
+}
+
+/// Whether an exited recording stopped because its file reached max_size_bytes rather than failing
+/// FFmpeg exits successfully at the limit, so a failed exit is a crash even when the file is large;
+/// `exit_success` is None when the status is unknown (reattached processes)
+fn reached_size_limit(info: &RecordingInfo, output_path: &str, exit_success: Option<bool>) -> bool {
+    let Some(limit) = info.max_size_bytes else {
+        return false;
+    };
+    if exit_success == Some(false) {
+        return false;
+    }
+    std::fs::metadata(output_path)
+        .map(|metadata| metadata.len() as f64 >= limit as f64 * SIZE_LIMIT_REACHED_FRACTION)
+        .unwrap_or(false)
+}
+
+/// Validate a requested capture framerate, defaulting to 30 fps
+fn resolve_framerate(framerate: Option<u32>) -> Result<u32, ClipForgeError> {
+    match framerate {
+        None => Ok(DEFAULT_FRAMERATE),
+        Some(fps) if (MIN_FRAMERATE..=MAX_FRAMERATE).contains(&fps) => Ok(fps),
+        Some(fps) => Err(ClipForgeError::invalid_argument(format!(
+            "framerate must be between {} and {}, got {}",
+            MIN_FRAMERATE, MAX_FRAMERATE, fps
+        ))),
+    }
+}
+
+/// Framerate for `auto_framerate`: the refresh rate of "Capture screen `screen_index`", capped at `framerate`
+/// when given and at AUTO_FRAMERATE_CAP otherwise; falls back to the default rate when the display doesn't report one
+fn resolve_auto_framerate(framerate: Option<u32>, screen_index: u32) -> Result<u32, ClipForgeError> {
+    let cap = resolve_framerate(Some(framerate.unwrap_or(AUTO_FRAMERATE_CAP)))?;
+    match active_window::display_refresh_rate(screen_index) {
+        Some(refresh_rate) => Ok((refresh_rate.round() as u32).clamp(MIN_FRAMERATE, cap)),
+        None => {
+            log::warn!("Could not read the display refresh rate, recording at {} fps", DEFAULT_FRAMERATE.min(cap));
+            Ok(DEFAULT_FRAMERATE.min(cap))
+        }
+    }
+}
+
+/// Encoder settings for captured audio
+#[derive(Debug, PartialEq)]
+struct AudioEncoding {
+    bitrate: String,
+    sample_rate: u32,
+    channels: u8,
+}
+
+impl Default for AudioEncoding {
+    fn default() -> Self {
+        AudioEncoding {
+            bitrate: "192k".to_string(),
+            sample_rate: 48000,
+            channels: 2,
+        }
+    }
+}
+
+/// Validate the audio encoding options of the recording commands, defaulting to 192k/48 kHz/stereo
+fn resolve_audio_encoding(
+    audio_codec: &str,
+    audio_bitrate: Option<&str>,
+    audio_sample_rate: Option<u32>,
+    audio_channels: Option<u8>,
+) -> Result<AudioEncoding, ClipForgeError> {
+    let mut encoding = AudioEncoding::default();
+
+    if let Some(bitrate) = audio_bitrate {
+        if parse_bitrate(bitrate).is_none() {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Invalid audio_bitrate '{}'. Expected e.g. \"64k\" or \"192k\"",
+                bitrate
+            )));
+        }
+        encoding.bitrate = bitrate.trim().to_string();
+    }
+
+    if let Some(sample_rate) = audio_sample_rate {
+        let supported = if audio_codec == "libopus" { OPUS_AUDIO_SAMPLE_RATES } else { SUPPORTED_AUDIO_SAMPLE_RATES };
+        if !supported.contains(&sample_rate) {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "audio_sample_rate must be one of {:?} for {}, got {}",
+                supported, audio_codec, sample_rate
+            )));
+        }
+        encoding.sample_rate = sample_rate;
+    }
+
+    if let Some(channels) = audio_channels {
+        if !(1..=2).contains(&channels) {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "audio_channels must be 1 (mono) or 2 (stereo), got {}",
+                channels
+            )));
+        }
+        encoding.channels = channels;
+    }
+
+    Ok(encoding)
+}
+
+/// Parse a "WIDTHxHEIGHT" size such as "1280x720"
+fn parse_video_size(size: &str) -> Option<(u32, u32)> {
+    let (width, height) = size.split_once('x')?;
+    let width: u32 = width.trim().parse().ok()?;
+    let height: u32 = height.trim().parse().ok()?;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some((width, height))
+}
+
+/// Build the -af filter chain applied to captured microphone audio
+fn build_audio_filters(
+    audio_gain_db: Option<f32>,
+    denoise: bool,
+    highpass_hz: Option<u32>,
+    lowpass_hz: Option<u32>,
+) -> Result<Vec<String>, ClipForgeError> {
+    let mut filters = Vec::new();
+
+    for (param_name, frequency) in [("audio_highpass_hz", highpass_hz), ("audio_lowpass_hz", lowpass_hz)] {
+        if let Some(hz) = frequency {
+            if !(MIN_AUDIO_FILTER_HZ..=MAX_AUDIO_FILTER_HZ).contains(&hz) {
+                return Err(ClipForgeError::invalid_argument(format!(
+                    "{} must be between {} and {}, got {}",
+                    param_name, MIN_AUDIO_FILTER_HZ, MAX_AUDIO_FILTER_HZ, hz
+                )));
+            }
+        }
+    }
+    if let (Some(highpass), Some(lowpass)) = (highpass_hz, lowpass_hz) {
+        if highpass >= lowpass {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "audio_highpass_hz ({}) must be below audio_lowpass_hz ({}), or nothing would be left",
+                highpass, lowpass
+            )));
+        }
+    }
+
+    // Band-limit first so denoise doesn't spend its effort on rumble and hiss that get cut anyway
+    if let Some(hz) = highpass_hz {
+        filters.push(format!("highpass=f={}", hz));
+    }
+    if let Some(hz) = lowpass_hz {
+        filters.push(format!("lowpass=f={}", hz));
+    }
+
+    // Denoise before applying gain so the noise floor isn't boosted first
+    if denoise {
+        filters.push("afftdn".to_string());
+    }
+
+    if let Some(gain) = audio_gain_db {
+        if !(MIN_AUDIO_GAIN_DB..=MAX_AUDIO_GAIN_DB).contains(&gain) {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "audio_gain_db must be between {} and {}, got {}",
+                MIN_AUDIO_GAIN_DB, MAX_AUDIO_GAIN_DB, gain
+            )));
+        }
+        if gain != 0.0 {
+            filters.push(format!("volume={}dB", gain));
+        }
+    }
+
+    Ok(filters)
+}
+
+/// Split the requested microphones into the one opened with the capture device and the extra ones,
+/// which get their own avfoundation inputs and are mixed in
+fn resolve_microphones(
+    audio_device_index: Option<u32>,
+    audio_device_indices: Option<Vec<u32>>,
+) -> Result<(Option<u32>, Vec<u32>), ClipForgeError> {
+    let mut microphones: Vec<u32> = Vec::new();
+    for index in audio_device_index.into_iter().chain(audio_device_indices.unwrap_or_default()) {
+        if microphones.contains(&index) {
+            return Err(ClipForgeError::invalid_argument(format!("Audio device {} is listed more than once", index)));
+        }
+        microphones.push(index);
+    }
+    if microphones.len() > MAX_MICROPHONES {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "At most {} microphones can be mixed, got {}",
+            MAX_MICROPHONES,
+            microphones.len()
+        )));
+    }
+
+    let mut microphones = microphones.into_iter();
+    Ok((microphones.next(), microphones.collect()))
+}
+
+/// Every audio device a recording opens, for validate_capture_devices
+fn microphones_and(devices: &[Option<u32>], extra_microphones: &[u32]) -> Vec<u32> {
+    devices.iter().flatten().chain(extra_microphones).copied().collect()
+}
+
+/// Open each extra microphone as its own audio-only avfoundation input (":N" means no video)
+fn add_microphone_inputs(cmd: &mut Command, extra_microphones: &[u32]) {
+    for index in extra_microphones {
+        cmd.arg("-f").arg("avfoundation").arg("-i").arg(format!(":{}", index));
+    }
+}
+
+/// filter_complex chain mixing the primary microphone (audio of input `primary_input`) with
+/// `extra_count` extra microphone inputs starting at `first_extra_input`, then applying the
+/// microphone filters; the result is labelled `[mic]`
+fn microphone_mix_filter(primary_input: usize, first_extra_input: usize, extra_count: usize, audio_filters: &[String]) -> String {
+    let inputs: String = std::iter::once(primary_input)
+        .chain(first_extra_input..first_extra_input + extra_count)
+        .map(|input| format!("[{}:a]", input))
+        .collect();
+    let filters: String = audio_filters.iter().map(|filter| format!(",{}", filter)).collect();
+    // amix otherwise scales every input down by the input count, so each mic would come out quieter
+    format!("{}amix=inputs={}:duration=longest:normalize=0{}[mic]", inputs, extra_count + 1, filters)
+}
+
+/// Build drawtext filters for a watermark (bottom-left) and a wall-clock timestamp (bottom-right)
+/// drawtext needs a font file: a system font is used when present, otherwise the bundled
+/// fonts/DejaVuSans.ttf from the app resources. The watermark is passed via a temp textfile
+/// so user text never needs filtergraph escaping; the textfile is returned alongside the filters
+/// and must outlive FFmpeg's startup. With `dry_run` the filters name the textfile without writing it
+fn build_text_overlay_filters(
+    app: &tauri::AppHandle,
+    watermark_text: Option<&str>,
+    show_timestamp: bool,
+    dry_run: bool,
+) -> Result<(Option<TempPathGuard>, Vec<String>), ClipForgeError> {
+    let watermark_text = watermark_text.filter(|text| !text.trim().is_empty());
+    if watermark_text.is_none() && !show_timestamp {
+        return Ok((None, Vec::new()));
+    }
+
+    let font = resolve_drawtext_font(app).ok_or_else(|| {
+        ClipForgeError::not_found("No font found for the watermark/timestamp overlay: no system font was found and the bundled fonts/DejaVuSans.ttf is missing from the app resources")
+    })?;
+    let style = format!(
+        "fontfile='{}':fontsize=24:fontcolor=white:box=1:boxcolor=black@0.5:boxborderw=6",
+        escape_filter_path(&font)
+    );
+
+    let mut filters = Vec::new();
+
+    let mut text_file = None;
+    if let Some(text) = watermark_text {
+        let text_path = unique_temp_path("watermark").with_extension("txt");
+        filters.push(format!(
+            "drawtext={}:textfile='{}':expansion=none:x=10:y=h-th-10",
+            style,
+            escape_filter_path(&text_path.to_string_lossy())
+        ));
+        if !dry_run {
+            text_file = Some(TempPathGuard::write_file(text_path, text)?);
+        }
+    }
+
+    if show_timestamp {
+        filters.push(format!("drawtext={}:text='%{{localtime}}':x=w-tw-10:y=h-th-10", style));
+    }
+
+    Ok((text_file, filters))
+}
+
+/// First font drawtext can use: a common system font, then the one bundled with the app
+fn resolve_drawtext_font(app: &tauri::AppHandle) -> Option<String> {
+    let bundled = app.path().resource_dir().ok().map(|dir| dir.join("fonts").join("DejaVuSans.ttf"));
+
+    DRAWTEXT_FONT_CANDIDATES
+        .iter()
+        .map(std::path::PathBuf::from)
+        .chain(bundled)
+        .find(|candidate| candidate.is_file())
+        .map(|candidate| candidate.to_string_lossy().to_string())
+}
+
+/// overlay x:y for a corner name, 10px in from the edges; a typo is reported rather than
+/// silently falling back to the default corner
+fn corner_overlay_position(param_name: &str, corner: &str) -> Result<&'static str, ClipForgeError> {
+    match corner {
+        "bottom-right" => Ok("W-w-10:H-h-10"),
+        "bottom-left" => Ok("10:H-h-10"),
+        "top-right" => Ok("W-w-10:10"),
+        "top-left" => Ok("10:10"),
+        other => Err(ClipForgeError::invalid_argument(format!(
+            "Unknown {} '{}'. Expected one of: bottom-right, bottom-left, top-right, top-left",
+            param_name, other
+        ))),
+    }
+}
+
+/// Filter chain element compositing an image (e.g. a logo) over the video at a corner
+/// The image is read with the movie source filter, so the element can be appended to an existing
+/// -vf or filter_complex chain like the text overlays, without renumbering the command's inputs
+fn image_overlay_filter(image_path: Option<&str>, position: Option<&str>) -> Result<Option<String>, ClipForgeError> {
+    let Some(image_path) = image_path else {
+        if position.is_some() {
+            return Err(ClipForgeError::invalid_argument("overlay_position requires overlay_image_path"));
+        }
+        return Ok(None);
+    };
+
+    let path = std::path::Path::new(image_path);
+    if !path.is_file() {
+        return Err(ClipForgeError::not_found(format!("Overlay image not found: {}", image_path)));
+    }
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase());
+    if !extension.as_deref().is_some_and(|ext| OVERLAY_IMAGE_EXTENSIONS.contains(&ext)) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Unsupported overlay image '{}'. Expected one of: {}",
+            image_path,
+            OVERLAY_IMAGE_EXTENSIONS.join(", ")
+        )));
+    }
+
+    let position = corner_overlay_position("overlay_position", position.unwrap_or("top-right"))?;
+    // overlay keeps showing the image's single frame for the whole recording (eof_action=repeat)
+    Ok(Some(format!(
+        "null[logo_base];movie='{}'[logo];[logo_base][logo]overlay={}",
+        escape_filter_path(image_path),
+        position
+    )))
+}
+
+/// Escape a path for use inside a single-quoted filter option value
+/// The filtergraph and the filter's option parser each unescape once, so quotes need two levels
+fn escape_filter_path(path: &str) -> String {
+    path.replace('\\', "/").replace('\'', "'\\\\\\''").replace(':', "\\:")
+}
+
+/// Count down before a delayed recording start, emitting `recording-countdown` once per second
+/// Returns a `Cancelled` error if cancel_recording_countdown is called before it reaches zero
+fn run_countdown(app: &tauri::AppHandle, delay_seconds: u64) -> Result<(), ClipForgeError> {
+    COUNTDOWN_CANCELLED.store(false, Ordering::SeqCst);
+
+    for seconds_remaining in (1..=delay_seconds).rev() {
+        if let Err(e) = app.emit("recording-countdown", RecordingCountdownEvent { seconds_remaining }) {
+            log::error!("Failed to emit recording-countdown event: {}", e);
+        }
+
+        // Check the flag several times per tick so cancelling feels immediate
+        for _ in 0..(1000 / COUNTDOWN_POLL_MS) {
+            std::thread::sleep(std::time::Duration::from_millis(COUNTDOWN_POLL_MS));
+            if COUNTDOWN_CANCELLED.swap(false, Ordering::SeqCst) {
+                return Err(ClipForgeError::cancelled("Recording was cancelled during the countdown"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Cancel a recording that is still counting down (see `delay_seconds`)
+#[tauri::command]
+fn cancel_recording_countdown() {
+    COUNTDOWN_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Remember how the latest recording was started so the hotkey can start another one like it
+fn remember_recording_starter(starter: RecordingStarter) {
+    match LAST_RECORDING_STARTER.lock() {
+        Ok(mut last) => *last = Some(starter),
+        Err(e) => log::error!("Failed to remember recording settings: {}", e),
+    }
+}
+
+/// Bind a global keyboard shortcut (e.g. "CmdOrCtrl+Shift+R") that toggles recording from any app
+/// Pressing it stops the most recently started recording, or when none is running starts a new one with
+/// the settings of the last recording (a default screen recording if nothing was recorded yet)
+/// Results are reported through recording-hotkey-started, recording-hotkey-stopped and recording-hotkey-failed
+/// Replaces any previously registered recording hotkey
+#[tauri::command]
+fn register_recording_hotkey(app: tauri::AppHandle, accelerator: String) -> Result<(), ClipForgeError> {
+    let mut hotkey = RECORDING_HOTKEY.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock recording hotkey: {}", e)))?;
+
+    let shortcuts = app.global_shortcut();
+    if let Some(previous) = hotkey.take() {
+        shortcuts.unregister(previous.as_str())
+            .map_err(|e| ClipForgeError::internal(format!("Failed to unregister hotkey '{}': {}", previous, e)))?;
+    }
+
+    shortcuts
+        .on_shortcut(accelerator.as_str(), |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                on_recording_hotkey(app.clone());
+            }
+        })
+        .map_err(|e| ClipForgeError::invalid_argument(format!("Failed to register hotkey '{}': {}", accelerator, e)))?;
+
+    *hotkey = Some(accelerator);
+    Ok(())
+}
+
+/// Remove the recording hotkey; does nothing if none is registered
+#[tauri::command]
+fn unregister_recording_hotkey(app: tauri::AppHandle) -> Result<(), ClipForgeError> {
+    let mut hotkey = RECORDING_HOTKEY.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock recording hotkey: {}", e)))?;
+
+    if let Some(accelerator) = hotkey.take() {
+        app.global_shortcut().unregister(accelerator.as_str())
+            .map_err(|e| ClipForgeError::internal(format!("Failed to unregister hotkey '{}': {}", accelerator, e)))?;
+    }
+    Ok(())
+}
+
+/// Toggle recording off the shortcut handler's thread; starting can block for the countdown
+fn on_recording_hotkey(app: tauri::AppHandle) {
+    if HOTKEY_TOGGLE_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        toggle_recording_from_hotkey(&app);
+        HOTKEY_TOGGLE_IN_PROGRESS.store(false, Ordering::SeqCst);
+    });
+}
+
+fn toggle_recording_from_hotkey(app: &tauri::AppHandle) {
+    // IDs increase monotonically, so the largest belongs to the most recent recording
+    let latest = lock_recording_processes()
+        .iter()
+        .filter(|(_, recording)| !recording.info.internal)
+        .map(|(process_id, _)| *process_id)
+        .max();
+
+    if let Some(process_id) = latest {
+        match stop_screen_recording(process_id, None, None, None) {
+            Ok(result) => {
+                let event = RecordingHotkeyStoppedEvent { process_id, file_path: result.file_path };
+                if let Err(e) = app.emit("recording-hotkey-stopped", event) {
+                    log::error!("Failed to emit recording-hotkey-stopped event: {}", e);
+                }
+            }
+            Err(e) => emit_hotkey_failure(app, e),
+        }
+        return;
+    }
+
+    let starter = LAST_RECORDING_STARTER.lock().ok().and_then(|last| last.clone());
+    let started = match starter {
+        Some(start) => start(app.clone()),
+        None => start_screen_recording_with(app.clone(), ScreenRecordingOptions::default()),
+    };
+    match started {
+        Ok(result) => {
+            if let Err(e) = app.emit("recording-hotkey-started", result) {
+                log::error!("Failed to emit recording-hotkey-started event: {}", e);
+            }
+        }
+        Err(e) => emit_hotkey_failure(app, e),
+    }
+}
+
+fn emit_hotkey_failure(app: &tauri::AppHandle, error: ClipForgeError) {
+    log::error!("Recording hotkey failed: {}", error);
+    if let Err(e) = app.emit("recording-hotkey-failed", error) {
+        log::error!("Failed to emit recording-hotkey-failed event: {}", e);
+    }
+}
+
+/// Whether FFmpeg's stderr shows avfoundation failed to open the requested device
+fn is_device_open_error(stderr: &str) -> bool {
+    stderr.contains("Input/output error") || stderr.contains("Invalid device index")
+}
+
+/// Whether FFmpeg failed to open the screen because macOS is withholding Screen Recording permission
+/// avfoundation only reports a generic "Input/output error" then, so the permission itself is checked
+/// to tell it apart from a bad device index
+fn is_screen_permission_error(stderr: &str) -> bool {
+    if !stderr.contains("Input/output error") {
+        return false;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        !active_window::screen_capture_allowed()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        false
+    }
+}
+
+/// Build the error for an FFmpeg process that exited right after spawning
+/// Unsupported framerate/size errors from avfoundation become `UnsupportedCaptureMode`
+/// so the UI can offer the valid modes instead of raw stderr; for commands capturing the screen,
+/// a missing Screen Recording permission becomes `PermissionDenied` with how to grant it
+fn immediate_exit_error(status: std::process::ExitStatus, error_output: &str, captures_screen: bool) -> ClipForgeError {
+    if captures_screen && is_screen_permission_error(error_output) {
+        log::warn!("FFmpeg could not open the screen without Screen Recording permission:\n{}", error_output);
+        return ClipForgeError::permission_denied(SCREEN_RECORDING_PERMISSION_MESSAGE);
+    }
+
+    if let Some((message, supported_modes)) = parse_unsupported_mode_error(error_output) {
+        return ClipForgeError::UnsupportedCaptureMode { message, supported_modes };
+    }
+
+    if !error_output.is_empty() {
+        ClipForgeError::process_failed(format!("FFmpeg exited immediately with status {:?}. Error output: {}", status, error_output))
+    } else {
+        ClipForgeError::process_failed(format!("FFmpeg exited immediately with status {:?}", status))
+    }
+}
+
+/// Detect avfoundation's "Selected framerate/video size is not supported" error and
+/// collect the supported modes it lists on stderr
+fn parse_unsupported_mode_error(stderr: &str) -> Option<(String, Vec<CaptureMode>)> {
+    // FFmpeg output format for avfoundation:
+    // [avfoundation @ ...] Selected framerate (29.970030) is not supported by the device.
+    // [avfoundation @ ...] Supported modes:
+    // [avfoundation @ ...]   1280x720@[1.000000 30.000000]fps
+    // [avfoundation @ ...]   640x480@[1.000000 30.000000]fps
+    let rejected_line = stderr.lines().find(|line| {
+        (line.contains("Selected framerate") || line.contains("Selected video size"))
+            && line.contains("is not supported by the device")
+    })?;
+
+    // Strip the "[avfoundation @ ...]" prefix to keep just the message
+    let message = match rejected_line.find("] ") {
+        Some(prefix_end) => rejected_line[prefix_end + 2..].trim().to_string(),
+        None => rejected_line.trim().to_string(),
+    };
+
+    let supported_modes = stderr
+        .lines()
+        .skip_while(|line| !line.contains("Supported modes:"))
+        .skip(1)
+        .filter_map(|line| {
+            let mode = match line.find("] ") {
+                Some(prefix_end) => &line[prefix_end + 2..],
+                None => line,
+            };
+            parse_capture_mode(mode.trim())
+        })
+        .collect();
+
+    Some((message, supported_modes))
+}
+
+/// Parse a single avfoundation mode such as "1280x720@[1.000000 30.000000]fps"
+fn parse_capture_mode(mode: &str) -> Option<CaptureMode> {
+    let (size, rates) = mode.split_once('@')?;
+    let (width, height) = size.split_once('x')?;
+    let rates = rates.strip_prefix('[')?;
+    let (rates, _) = rates.split_once(']')?;
+    let mut rates = rates.split_whitespace();
+    let min_framerate = rates.next()?.parse::<f64>().ok()?;
+    let max_framerate = rates.next().map_or(Some(min_framerate), |rate| rate.parse::<f64>().ok())?;
+
+    Some(CaptureMode {
+        width: width.trim().parse().ok()?,
+        height: height.trim().parse().ok()?,
+        min_framerate,
+        max_framerate,
+    })
+}
+
+// Settings of start_webcam_recording, taken by the command as one `options` object and built with
+// `..Default::default()` by the hotkey starter and internal callers
+#[derive(Debug, Clone, Default, Deserialize)]
+struct WebcamRecordingOptions {
+    output_path: Option<String>,
+    device_index: Option<u32>,
+    audio_device_index: Option<u32>,
+    quality: Option<String>, // "low", "medium", "high", "lossless"
+    crf: Option<u32>, // Explicit CRF (0-51), overrides the quality preset's CRF
+    framerate: Option<u32>, // Capture and output framerate (1-120), defaults to 30
+    delay_seconds: Option<u64>, // Count down this many seconds (emitting recording-countdown) before starting
+    audio_gain_db: Option<f32>, // Boost or cut captured audio (-30 to +30 dB); ignored without an audio device
+    denoise: Option<bool>, // Suppress steady background noise (fans, hum) on captured audio
+    mirror: Option<bool>, // Flip the webcam horizontally
+    container: Option<String>, // "mp4" (default), "webm" (VP9 + Opus), or "mkv"
+    bitrate: Option<String>, // Target video bitrate such as "5M" (capped VBR); mutually exclusive with crf
+    watermark_text: Option<String>, // Text burned into the bottom-left corner
+    show_timestamp: Option<bool>, // Burn the wall-clock time into the bottom-right corner
+    video_size: Option<String>, // Webcam capture size such as "640x480"; defaults to 1280x720
+    audio_bitrate: Option<String>, // e.g. "64k"; defaults to 192k
+    audio_sample_rate: Option<u32>, // Hz; defaults to 48000
+    audio_channels: Option<u8>, // 1 (mono) or 2 (stereo); defaults to 2
+    dry_run: Option<bool>, // Return the FFmpeg command in `command` without starting anything
+    video_codec: Option<String>, // "h264" (default), "hevc" (libx265; slow in software), or "hevc_videotoolbox" (hardware)
+    trim_start_ms: Option<u64>, // Drop this much from the start to hide garbled first frames; see DEFAULT_TRIM_START_MS, 0 disables
+    pixel_format: Option<String>, // Output pix_fmt, e.g. "yuv444p" for sharper small text; defaults to yuv420p
+    color_range: Option<String>, // "tv" (limited, default) or "pc" (full)
+    filename_template: Option<String>, // Name for generated paths, e.g. "{type} {date} {time}"; see expand_filename_template
+    detached: Option<bool>, // Keep FFmpeg running if the app quits; reattach_recordings picks it up again (macOS/Linux)
+    low_priority: Option<bool>, // Run FFmpeg at a lowered CPU priority (nice 10 / BELOW_NORMAL) to keep the system responsive; may drop frames under load
+    keyframe_interval_seconds: Option<f64>, // Force a keyframe this often so later trims can cut close to the requested time
+    audio_device_indices: Option<Vec<u32>>, // More microphones, mixed with audio_device_index into one track (e.g. two interview mics)
+    overlay_image_path: Option<String>, // Logo composited over the video, e.g. a transparent PNG
+    overlay_position: Option<String>, // Corner for overlay_image_path: "top-right" (default), "top-left", "bottom-right", "bottom-left"
+    max_size_bytes: Option<u64>, // Stop automatically once the file reaches this size (FFmpeg -fs), e.g. for an upload cap
+    preview: Option<bool>, // Keep a small JPEG of the latest captured frame for get_preview_frame (refreshed about once a second)
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+    audio_highpass_hz: Option<u32>, // Cut the microphone below this frequency, e.g. 80 to remove rumble from voice
+    audio_lowpass_hz: Option<u32>, // Cut the microphone above this frequency, e.g. 12000 to tame hiss
+    // Only set by internal callers, not command parameters
+    #[serde(skip)]
+    internal: bool, // Started by ClipForge itself: not listed, stopped by the hotkey, or remembered for it
+}
+
+/// Start webcam recording using FFmpeg
+/// Returns a process ID that can be used to stop the recording
+/// Settings are passed as one `options` object keyed by the WebcamRecordingOptions field names, all optional
+#[tauri::command(async)]
+fn start_webcam_recording(app: tauri::AppHandle, options: WebcamRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    start_webcam_recording_with(app, options)
+}
+
+/// start_webcam_recording with its settings already gathered into options
+fn start_webcam_recording_with(app: tauri::AppHandle, options: WebcamRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    // Same settings with a fresh file name, for the recording hotkey
+    let starter: RecordingStarter = {
+        let options = WebcamRecordingOptions { output_path: None, dry_run: None, ..options.clone() };
+        std::sync::Arc::new(move |app| start_webcam_recording_with(app, options.clone()))
+    };
+    let WebcamRecordingOptions {
+        output_path,
+        device_index,
+        audio_device_index,
+        quality,
+        crf,
+        framerate,
+        delay_seconds,
+        audio_gain_db,
+        denoise,
+        mirror,
+        container,
+        bitrate,
+        watermark_text,
+        show_timestamp,
+        video_size,
+        audio_bitrate,
+        audio_sample_rate,
+        audio_channels,
+        dry_run,
+        video_codec,
+        trim_start_ms,
+        pixel_format,
+        color_range,
+        filename_template,
+        detached,
+        low_priority,
+        keyframe_interval_seconds,
+        audio_device_indices,
+        overlay_image_path,
+        overlay_position,
+        max_size_bytes,
+        preview,
+        extra_args,
+        audio_highpass_hz,
+        audio_lowpass_hz,
+        internal,
+    } = options;
+
+    let (audio_device_index, extra_microphones) = resolve_microphones(audio_device_index, audio_device_indices)?;
+    let video_quality = resolve_video_quality(quality.as_deref(), crf, bitrate.as_deref())?
+        .with_pixel_format(pixel_format.as_deref(), color_range.as_deref())?;
+    let framerate = resolve_framerate(framerate)?;
+    let keyframe_args = keyframe_interval_args(keyframe_interval_seconds, framerate)?;
+    let size_limit_args = size_limit_args(max_size_bytes)?;
+    let (preview_path, preview_args) = preview_output(preview.unwrap_or(false));
+    let extra_args = resolve_extra_args(extra_args)?;
+    let image_overlay = image_overlay_filter(overlay_image_path.as_deref(), overlay_position.as_deref())?;
+    let trim_start_ms = resolve_trim_start_ms(trim_start_ms, true)?;
+    let audio_filters = build_audio_filters(audio_gain_db, denoise.unwrap_or(false), audio_highpass_hz, audio_lowpass_hz)?;
+    let container = resolve_container(container.as_deref(), video_codec.as_deref())?;
+    container.check_pixel_format(&video_quality)?;
+    let audio_encoding = resolve_audio_encoding(container.audio_codec, audio_bitrate.as_deref(), audio_sample_rate, audio_channels)?;
+    let video_size = video_size.unwrap_or_else(|| DEFAULT_WEBCAM_VIDEO_SIZE.to_string());
+    if parse_video_size(&video_size).is_none() {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Invalid video_size '{}'. Expected WIDTHxHEIGHT, e.g. \"640x480\"",
+            video_size
+        )));
+    }
+    let (text_file, text_filters) = build_text_overlay_filters(&app, watermark_text.as_deref(), show_timestamp.unwrap_or(false), dry_run.unwrap_or(false))?;
+
+    let dry_run = dry_run.unwrap_or(false);
+    let detached = detached.unwrap_or(false);
+    // A dry run skips the device listing, so it shows device 0 when none is requested
+    let device_idx = match device_index {
+        Some(index) => index,
+        None if dry_run => 0,
+        None => default_webcam_device_index()?,
+    };
+    if !dry_run {
+        validate_capture_devices(&[device_idx], &microphones_and(&[audio_device_index], &extra_microphones))?;
+    }
+
+    // Count down before picking the file name so the default timestamp reflects the actual start
+    if let (Some(delay), false) = (delay_seconds, dry_run) {
+        run_countdown(&app, delay)?;
+    }
+
+    // Generate output path if not provided
+    let output = if let Some(path) = output_path {
+        path
+    } else {
+        default_recording_path("clipforge-webcam", "webcam", container.extension, false, filename_template.as_deref())?
+    };
+
+    // Fail early on a missing or read-only folder instead of after FFmpeg starts
+    if !dry_run {
+        ensure_output_dir_writable(&output)?;
+    }
+
+    // Check if FFmpeg is available
+    let ffmpeg_check = Command::new(ffmpeg_binary())
+        .arg("-version")
+        .logged_output();
+    
+    match ffmpeg_check {
+        Ok(_) => {},
+        Err(_) => return Err(ClipForgeError::ffmpeg_not_found("FFmpeg is not installed or not found in PATH. Please install FFmpeg to use webcam recording.")),
+    }
+
+    
+    // Build input device string: "video_device:audio_device" or "video_device:" if no audio
+    let device_string = if let Some(audio_idx) = audio_device_index {
+        format!("{}:{}", device_idx, audio_idx)
+    } else {
+        format!("{}:", device_idx)
+    };
+
+    // Construct FFmpeg command for macOS using avfoundation
+    // Format: ffmpeg -f avfoundation -i "0:0" -r 30 -c:v libx264 -preset fast -crf 23 -pix_fmt yuv420p -c:a aac -b:a 192k -ar 48000 output.mp4
+    // "0:0" means video device 0 (first webcam), audio device 0 (first microphone)
+    // "0:" means video device 0, no audio device
+    let mut cmd = Command::new(ffmpeg_binary());
+    cmd.arg("-f")
+        .arg("avfoundation")
+        .arg("-framerate")
+        .arg(framerate.to_string())  // Input framerate
+        .arg("-video_size")
+        .arg(&video_size)  // Webcam capture resolution
+        .arg("-i")
+        .arg(&device_string);  // Webcam device index, optional audio device
+    add_microphone_inputs(&mut cmd, &extra_microphones);
+
+    let mut video_filters: Vec<String> = Vec::new();
+    if mirror.unwrap_or(false) {
+        video_filters.push("hflip".to_string());
+    }
+    video_filters.extend(text_filters);
+    video_filters.extend(image_overlay);
+    if !video_filters.is_empty() {
+        cmd.arg("-vf").arg(video_filters.join(","));
+    }
+    
+    // Add audio encoding parameters if audio device is provided
+    if audio_device_index.is_some() {
+        cmd.arg("-c:a")
+            .arg(container.audio_codec)  // Audio codec
+            .arg("-b:a")
+            .arg(&audio_encoding.bitrate)  // Audio bitrate (192k by default)
+            .arg("-ar")
+            .arg(audio_encoding.sample_rate.to_string())  // Sample rate (48 kHz by default)
+            .arg("-ac")
+            .arg(audio_encoding.channels.to_string());  // Channels (stereo by default)
+
+        if !extra_microphones.is_empty() {
+            cmd.arg("-filter_complex")
+                .arg(microphone_mix_filter(0, 1, extra_microphones.len(), &audio_filters))
+                .arg("-map")
+                .arg("0:v")
+                .arg("-map")
+                .arg("[mic]");
+        } else if !audio_filters.is_empty() {
+            cmd.arg("-af").arg(audio_filters.join(","));
+        }
+    }
+
+    cmd.args(trim_start_args(trim_start_ms));
+
+    cmd.arg("-r")
+        .arg(framerate.to_string())  // Output framerate
+        .arg("-c:v")
+        .arg(container.video_codec)  // Video codec
+        .args(container.tag_args())  // hvc1 tag for HEVC in mp4
+        .args(container.speed_args(&video_quality))  // Encoding speed
+        .args(container.rate_control_args(&video_quality))  // Quality: CRF or capped bitrate
+        .args(video_quality.pixel_format_args())  // Pixel format and color range
+        .args(&keyframe_args)  // GOP length
+        .args(&size_limit_args)  // Stop at max_size_bytes
+        .args(container.recording_mux_args())  // Fragmented mp4 survives a killed FFmpeg
+        .args(&extra_args)  // Caller's own flags, applied to the main output
+        .arg("-y")  // Overwrite output file
+        .arg(&output)
+        .args(&preview_args)  // Second output refreshing the preview JPEG
+        // Piped for the startup check; once tracked, StderrLog drains it so FFmpeg never blocks on a full pipe
+        .stderr(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stdin(std::process::Stdio::piped());  // Stop sends FFmpeg's "q" command here
+
+    if detached {
+        detach_from_app(&mut cmd)?;
+    }
+
+    if low_priority.unwrap_or(false) {
+        lower_priority(&mut cmd);
+    }
+
+    if dry_run {
+        return Ok(RecordingResult {
+            process_id: 0,
+            output_path: output,
+            screen_device_index: None,
+            command: Some(command_argv(&cmd)),
+            framerate,
+            resolution: Some(video_size),
+            audio_enabled: audio_device_index.is_some(),
+        });
+    }
+
+    // Spawn the FFmpeg process
+    let mut child = cmd.logged_spawn()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to start FFmpeg process: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    // Give FFmpeg a moment to initialize and check if it's still running
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    
+    // Check if process immediately crashed
+    match child.try_wait() {
+        Ok(Some(status)) => {
+            // Process already exited - try to read stderr for error info
+            let error_msg = if let Some(mut stderr) = child.stderr.take() {
+                use std::io::Read;
+                let mut error_output = String::new();
+                let _ = stderr.read_to_string(&mut error_output);
+                immediate_exit_error(status, &error_output, false)
+            } else {
+                ClipForgeError::process_failed(format!("FFmpeg exited immediately with status {:?}", status))
+            };
+            return Err(error_msg);
+        }
+        Ok(None) => {
+            // Process is still running, good!
+        }
+        Err(e) => {
+            return Err(ClipForgeError::internal(format!("Failed to check FFmpeg process status: {}", e)));
+        }
+    }
+
+    // Key the recording by a ClipForge ID rather than the PID, which the OS can reuse
+    let process_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
+
+    // Store the process handle and output path
+    let info = RecordingInfo::new("webcam", framerate, audio_device_index, max_size_bytes)
+        .with_preview(preview_path)
+        .with_text_file(text_file)
+        .with_internal(internal);
+    track_recording(process_id, RecordingProcess::new(child, output.clone(), info), detached);
+
+    if !internal {
+        remember_recording_starter(starter);
+    }
+
+    Ok(RecordingResult {
+        process_id,
+        output_path: output,
+        screen_device_index: None,
+        command: None,
+        framerate,
+        resolution: Some(video_size),
+        audio_enabled: audio_device_index.is_some(),
+    })
+}
+
+/// Start a rolling background screen capture that keeps the last `preroll_seconds` on disk
+/// A following start_screen_recording with `preroll_seconds` prepends the buffered footage
+/// Use the same screen, cursor, audio device and video quality settings as the recording so the
+/// streams can be joined without re-encoding
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn start_preroll_buffer(
+    preroll_seconds: u64,
+    audio_device_index: Option<u32>,
+    screen_index: Option<u32>, // Display to capture, 0 (default) being the main one; must match the recording
+    show_cursor: Option<bool>, // Include the mouse pointer; defaults to true and must match the recording
+    framerate: Option<u32>,
+    quality: Option<String>, // "low", "medium", "high", "lossless"; must match the recording
+    crf: Option<u32>, // Explicit CRF (0-51); must match the recording
+    bitrate: Option<String>, // Target video bitrate such as "5M"; must match the recording
+    pixel_format: Option<String>, // Output pix_fmt; must match the recording
+    color_range: Option<String>, // "tv" or "pc"; must match the recording
+) -> Result<String, ClipForgeError> {
+    let framerate = resolve_framerate(framerate)?;
+    let video_quality = resolve_video_quality(quality.as_deref(), crf, bitrate.as_deref())?
+        .with_pixel_format(pixel_format.as_deref(), color_range.as_deref())?;
+    let container = resolve_container(Some("mp4"), None)?;
+    if preroll_seconds == 0 || preroll_seconds > MAX_PREROLL_SECONDS {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "preroll_seconds must be between 1 and {}, got {}",
+            MAX_PREROLL_SECONDS, preroll_seconds
+        )));
+    }
+
+    let screen_index = screen_index.unwrap_or(0);
+    let show_cursor = show_cursor.unwrap_or(true);
+
+    let mut buffer = PREROLL_BUFFER.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock pre-roll buffer: {}", e)))?;
+    if buffer.is_some() {
+        return Err(ClipForgeError::invalid_argument("Pre-roll buffer is already running"));
+    }
+
+    let segment_dir = TempPathGuard::create_dir(unique_temp_path("preroll"))?;
+
+    // Keep one spare segment beyond the requested window plus the one currently being written
+    let segment_wrap = preroll_seconds / PREROLL_SEGMENT_SECONDS + 2;
+
+    // Open the screen the same way start_screen_recording does, so both capture the same display
+    let screen_device_candidates = screen_device_candidates(screen_capture_device(screen_index), screen_index);
+    let mut failed_attempts: Vec<String> = Vec::new();
+    let mut started = None;
+    for &screen_device_index in &screen_device_candidates {
+        let input_device = match audio_device_index {
+            Some(audio_idx) => format!("{}:{}", screen_device_index, audio_idx),
+            None => format!("{}:", screen_device_index),
+        };
+
+        // Encode exactly like start_screen_recording so segments can be concatenated with -c copy
+        let mut cmd = Command::new(ffmpeg_binary());
+        cmd.arg("-f")
+            .arg("avfoundation")
+            .arg("-capture_cursor")
+            .arg(if show_cursor { "1" } else { "0" })
+            .arg("-framerate")
+            .arg(framerate.to_string())
+            .arg("-i")
+            .arg(&input_device);
+
+        if audio_device_index.is_some() {
+            cmd.arg("-c:a")
+                .arg("aac")
+                .arg("-b:a")
+                .arg("192k")
+                .arg("-ar")
+                .arg("48000")
+                .arg("-ac")
+                .arg("2");
+        }
+
+        cmd.arg("-r")
+            .arg(framerate.to_string())
+            .arg("-c:v")
+            .arg(container.video_codec)
+            .args(container.speed_args(&video_quality))
+            .args(container.rate_control_args(&video_quality))
+            .args(video_quality.pixel_format_args())
+            // Force a keyframe at every segment boundary so each segment starts cleanly
+            .arg("-force_key_frames")
+            .arg(format!("expr:gte(t,n_forced*{})", PREROLL_SEGMENT_SECONDS))
+            .arg("-f")
+            .arg("segment")
+            .arg("-segment_time")
+            .arg(PREROLL_SEGMENT_SECONDS.to_string())
+            .arg("-segment_wrap")
+            .arg(segment_wrap.to_string())
+            .arg("-reset_timestamps")
+            .arg("1")
+            .arg("-y")
+            .arg(segment_dir.path().join("preroll%03d.mp4"))
+            // Piped for the startup check; StderrLog drains it afterwards so FFmpeg never blocks on a full pipe
+            .stderr(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stdin(std::process::Stdio::piped());  // Stop sends FFmpeg's "q" command here
+
+        let mut child = cmd.logged_spawn()
+            .map_err(|e| ClipForgeError::spawn(format!("Failed to start pre-roll FFmpeg process: {}", e), &e))?;
+
+        // Give FFmpeg a moment to initialize and check if it's still running
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let mut error_output = String::new();
+                if let Some(mut stderr) = child.stderr.take() {
+                    use std::io::Read;
+                    let _ = stderr.read_to_string(&mut error_output);
+                }
+
+                // Only a device that couldn't be opened is worth retrying on another index
+                if !is_device_open_error(&error_output) || is_screen_permission_error(&error_output) {
+                    return Err(immediate_exit_error(status, &error_output, true));
+                }
+                failed_attempts.push(format!("Device {}: {}", screen_device_index, error_output.trim()));
+            }
+            Ok(None) => {
+                started = Some(child);
+                break;
+            }
+            Err(e) => {
+                return Err(ClipForgeError::internal(format!("Failed to check FFmpeg process status: {}", e)));
+            }
+        }
+    }
+
+    let mut child = started.ok_or_else(|| {
+        ClipForgeError::not_found(format!(
+            "Could not open a screen capture device for the pre-roll buffer (tried {:?}).\n{}",
+            screen_device_candidates,
+            failed_attempts.join("\n")
+        ))
+    })?;
+    let stderr_log = StderrLog::start(child.stderr.take(), None);
+
+    let segment_dir = segment_dir.keep();
+    let segment_dir_string = segment_dir.to_string_lossy().to_string();
+    *buffer = Some(PrerollBuffer {
+        child: RecordingChild::Spawned(child),
+        stderr_log,
+        segment_dir,
+        preroll_seconds,
+        audio_device_index,
+        screen_index,
+        show_cursor,
+        framerate,
+        video_quality,
+    });
+
+    Ok(segment_dir_string)
+}
+
+/// Stop the rolling pre-roll capture and discard its buffered footage
+#[tauri::command(async)]
+fn stop_preroll_buffer() -> Result<(), ClipForgeError> {
+    let buffer = PREROLL_BUFFER.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock pre-roll buffer: {}", e)))?
+        .take()
+        .ok_or_else(|| ClipForgeError::not_found("Pre-roll buffer is not running"))?;
+
+    let segment_dir = buffer.segment_dir.clone();
+    finish_preroll_capture(buffer);
+    let _ = std::fs::remove_dir_all(segment_dir);
+    Ok(())
+}
+
+/// Gracefully stop the pre-roll FFmpeg process so the segment being written is finalized
+/// Killed only if it hasn't exited within the default flush timeout of a stop request
+fn finish_preroll_capture(mut buffer: PrerollBuffer) {
+    let flush_timeout = std::time::Duration::from_millis(DEFAULT_FLUSH_TIMEOUT_MS);
+    match buffer.child.request_stop() {
+        Ok(()) if wait_for_exit(&mut buffer.child, flush_timeout) => {}
+        Ok(()) => log::warn!("Pre-roll buffer did not exit within {:?} of the stop request, killing it", flush_timeout),
+        Err(e) => log::warn!("Failed to ask the pre-roll buffer to stop gracefully: {}", e),
+    }
+
+    if let Ok(None) = buffer.child.try_wait() {
+        let _ = buffer.child.kill();
+    }
+    let _ = buffer.child.wait();
+
+    let stderr_output = buffer.stderr_log.finish();
+    if !stderr_output.is_empty() {
+        log::debug!("Pre-roll FFmpeg stderr:\n{}", stderr_output);
+    }
+}
+
+/// Stop the pre-roll buffer and return the segments covering the last `preroll_seconds`, oldest first
+fn take_preroll_segments(
+    preroll_seconds: u64,
+    audio_device_index: Option<u32>,
+    screen_index: u32,
+    show_cursor: bool,
+    framerate: u32,
+    video_quality: &VideoQuality,
+) -> Result<PrerollSegments, ClipForgeError> {
+    let buffer = PREROLL_BUFFER.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock pre-roll buffer: {}", e)))?
+        .take()
+        .ok_or_else(|| ClipForgeError::not_found("Pre-roll buffer is not running. Call start_preroll_buffer before recording with preroll_seconds."))?;
+    // The buffer is gone after this call either way, so its footage goes with any error below
+    let segment_dir = TempPathGuard(Some(buffer.segment_dir.clone()));
+
+    if preroll_seconds > buffer.preroll_seconds {
+        let buffered_seconds = buffer.preroll_seconds;
+        finish_preroll_capture(buffer);
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Requested {} seconds of pre-roll but the buffer only keeps {} seconds",
+            preroll_seconds, buffered_seconds
+        )));
+    }
+
+    if buffer.audio_device_index != audio_device_index {
+        finish_preroll_capture(buffer);
+        return Err(ClipForgeError::invalid_argument("Pre-roll buffer was started with a different audio device than the recording"));
+    }
+
+    if buffer.screen_index != screen_index {
+        let buffered_screen = buffer.screen_index;
+        finish_preroll_capture(buffer);
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Pre-roll buffer captures screen {} but the recording uses screen {}",
+            buffered_screen, screen_index
+        )));
+    }
+
+    if buffer.show_cursor != show_cursor {
+        finish_preroll_capture(buffer);
+        return Err(ClipForgeError::invalid_argument("Pre-roll buffer was started with a different show_cursor setting than the recording"));
+    }
+
+    if buffer.framerate != framerate {
+        let buffered_framerate = buffer.framerate;
+        finish_preroll_capture(buffer);
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Pre-roll buffer captures at {} fps but the recording uses {} fps",
+            buffered_framerate, framerate
+        )));
+    }
+
+    if buffer.video_quality != *video_quality {
+        finish_preroll_capture(buffer);
+        return Err(ClipForgeError::invalid_argument(
+            "Pre-roll buffer was started with different quality, crf, bitrate, pixel_format or color_range settings than the recording",
+        ));
+    }
+
+    finish_preroll_capture(buffer);
+
+    let mut segments: Vec<(SystemTime, std::path::PathBuf)> = std::fs::read_dir(segment_dir.path())
+        .map_err(|e| ClipForgeError::io(format!("Failed to read pre-roll directory: {}", e), &e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mp4"))
+        .filter(|path| std::fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .collect();
+
+    // Segment files are reused in a ring, so order by write time rather than name
+    segments.sort_by_key(|(modified, _)| *modified);
+
+    // The newest segment is usually partial, so keep one extra to cover the full window
+    let wanted = (preroll_seconds / PREROLL_SEGMENT_SECONDS + 1) as usize;
+    let skip = segments.len().saturating_sub(wanted);
+    Ok(PrerollSegments {
+        files: segments.into_iter().skip(skip).map(|(_, path)| path).collect(),
+        _dir: segment_dir,
+    })
+}
+
+/// Prepend pre-roll segments to a finished recording, replacing the recording in place
+fn prepend_preroll(output_path: &str, segments: &PrerollSegments) -> Result<(), ClipForgeError> {
+    let output = std::path::Path::new(output_path);
+    let segments = &segments.files;
+    let segment_dir = segments
+        .first()
+        .and_then(|segment| segment.parent())
+        .ok_or_else(|| ClipForgeError::not_found("No pre-roll segments to prepend"))?;
+
+    // Buffered segments followed by the recording itself
+    let inputs: Vec<&std::path::Path> = segments.iter().map(|p| p.as_path()).chain(std::iter::once(output)).collect();
+    let extension = output.extension().and_then(|ext| ext.to_str()).unwrap_or("mp4");
+    let joined_path = segment_dir.join(format!("joined.{}", extension));
+    concat_copy(&inputs, segment_dir, &joined_path)?;
+
+    // rename fails across filesystems (temp dir vs. user folder), so fall back to copy
+    if std::fs::rename(&joined_path, output).is_err() {
+        std::fs::copy(&joined_path, output)
+            .map_err(|e| ClipForgeError::io(format!("Failed to replace recording with pre-roll version: {}", e), &e))?;
+    }
+
+    Ok(())
+}
+
+/// Join media files end to end with the concat demuxer, without re-encoding
+/// The files must share codecs and encoding settings; the concat list is written to `work_dir`
+fn concat_copy(inputs: &[&std::path::Path], work_dir: &std::path::Path, output_path: &std::path::Path) -> Result<(), ClipForgeError> {
+    let list_path = work_dir.join("concat.txt");
+    let mut list = String::new();
+    for path in inputs {
+        list.push_str(&format!("file '{}'\n", path.to_string_lossy().replace('\'', "'\\''")));
+    }
+    std::fs::write(&list_path, list)
+        .map_err(|e| ClipForgeError::io(format!("Failed to write concat list: {}", e), &e))?;
+
+    let result = Command::new(ffmpeg_binary())
+        .arg("-f")
+        .arg("concat")
+        .arg("-safe")
+        .arg("0")
+        .arg("-i")
+        .arg(&list_path)
+        .arg("-c")
+        .arg("copy")
+        .arg("-y")
+        .arg(output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg concat: {}", e), &e))?;
+
+    let _ = std::fs::remove_file(&list_path);
+
+    if !result.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg concat failed: {}",
+            String::from_utf8_lossy(&result.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Append a running recording to an existing file once it is stopped
+/// On stop the new take is joined onto the end of `existing_path`, which is replaced with the result,
+/// and the take's own file is removed. If the append fails both files are left untouched
+#[tauri::command]
+fn append_recording(existing_path: String, process_id: u64) -> Result<(), ClipForgeError> {
+    if !std::path::Path::new(&existing_path).is_file() {
+        return Err(ClipForgeError::not_found(format!("File not found: {}", existing_path)));
+    }
+
+    let take_path = lock_recording_processes()
+        .get(&process_id)
+        .map(|recording| recording.output_path.clone())
+        .ok_or_else(|| ClipForgeError::not_found(format!("No recording found with process ID {}", process_id)))?;
+
+    if is_live_output(&take_path) {
+        return Err(ClipForgeError::invalid_argument("A live stream can't be appended to a file"));
+    }
+    if std::fs::canonicalize(&existing_path).ok() == std::fs::canonicalize(&take_path).ok() {
+        return Err(ClipForgeError::invalid_argument("A recording can't be appended to its own output file"));
+    }
+
+    APPEND_TARGETS.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock append targets: {}", e)))?
+        .insert(process_id, existing_path);
+
+    Ok(())
+}
+
+/// Join `take_path` onto the end of `existing_path` and replace the existing file with the result
+/// Stream copies when both files were encoded the same way, otherwise re-encodes the take to match
+/// the existing file's resolution; returns whether a re-encode was needed
+fn append_take(existing_path: &str, take_path: &str) -> Result<bool, ClipForgeError> {
+    let existing = std::path::Path::new(existing_path);
+    let extension = existing.extension().and_then(|ext| ext.to_str()).unwrap_or("mp4");
+    let work_dir = std::env::temp_dir().join(format!("clipforge-append-{}", unix_timestamp()));
+    std::fs::create_dir_all(&work_dir)
+        .map_err(|e| ClipForgeError::io(format!("Failed to create append work directory: {}", e), &e))?;
+    let joined_path = work_dir.join(format!("joined.{}", extension));
+
+    let existing_layout = probe_stream_layout(existing_path);
+    let stream_copy = existing_layout.is_some() && existing_layout == probe_stream_layout(take_path);
+    let joined = if stream_copy {
+        concat_copy(&[existing, std::path::Path::new(take_path)], &work_dir, &joined_path)
+    } else {
+        log::info!("'{}' and '{}' differ in codecs or encoding settings, re-encoding to append", existing_path, take_path);
+        concat_reencode(&[existing_path, take_path], existing_path, &joined_path, &[])
+    };
+    if let Err(e) = joined {
+        let _ = std::fs::remove_dir_all(&work_dir);
+        return Err(e);
+    }
+
+    // rename fails across filesystems (temp dir vs. user folder), so fall back to copying next to
+    // the existing file and renaming over it there; copying straight onto it would truncate it first
+    if std::fs::rename(&joined_path, existing).is_err() {
+        let staged_path = existing.with_file_name(format!(
+            ".{}.clipforge-append",
+            existing.file_name().and_then(|name| name.to_str()).unwrap_or("recording")
+        ));
+        let replaced = std::fs::copy(&joined_path, &staged_path).and_then(|_| std::fs::rename(&staged_path, existing));
+        if let Err(e) = replaced {
+            let _ = std::fs::remove_file(&staged_path);
+            let _ = std::fs::remove_dir_all(&work_dir);
+            return Err(ClipForgeError::io(format!("Failed to replace '{}' with the appended version: {}", existing_path, e), &e));
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&work_dir);
+    Ok(!stream_copy)
+}
+
+/// Codec and format of every stream in a file, one line per stream
+/// Two files with the same layout can be joined by concat_copy
+fn probe_stream_layout(path: &str) -> Option<String> {
+    let output = Command::new(ffprobe_binary())
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("stream=codec_type,codec_name,profile,width,height,pix_fmt,sample_rate,channels")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(path)
+        .logged_output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Join files end to end with the concat filter, re-encoding with the codecs for `output_path`'s
+/// extension (see resolve_output_container) at the "medium" quality preset
+/// Every input is scaled and padded to `reference_path`'s resolution and converted to its framerate,
+/// since the concat filter can't join mismatched video; inputs without audio get silence instead
+/// `extra_args` go right before the output path
+fn concat_reencode(
+    inputs: &[&str],
+    reference_path: &str,
+    output_path: &std::path::Path,
+    extra_args: &[String],
+) -> Result<(), ClipForgeError> {
+    let container = resolve_output_container(output_path, None)?;
+    let video_quality = resolve_video_quality(None, None, None)?;
+
+    let (width, height) = probe_video_resolution(reference_path)
+        .ok_or_else(|| ClipForgeError::process_failed(format!("Failed to read the resolution of '{}'", reference_path)))?;
+    let framerate = probe_video_framerate(reference_path)
+        .ok_or_else(|| ClipForgeError::process_failed(format!("Failed to read the framerate of '{}'", reference_path)))?;
+
+    let has_audio: Vec<bool> = inputs.iter().map(|path| has_audio_stream(path)).collect();
+    let with_audio = has_audio.contains(&true);
+
+    let mut chains = Vec::new();
+    let mut segments = String::new();
+    for (index, path) in inputs.iter().enumerate() {
+        chains.push(format!(
+            "[{i}:v]scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps}[v{i}]",
+            i = index,
+            w = width,
+            h = height,
+            fps = framerate
+        ));
+        segments.push_str(&format!("[v{}]", index));
+        if !with_audio {
+            continue;
+        }
+        if has_audio[index] {
+            chains.push(format!("[{i}:a]aresample=48000,aformat=channel_layouts=stereo[a{i}]", i = index));
+        } else {
+            let duration = probe_duration_seconds(path)
+                .ok_or_else(|| ClipForgeError::process_failed(format!("Failed to read the duration of '{}'", path)))?;
+            chains.push(format!("anullsrc=r=48000:cl=stereo,atrim=duration={:.3}[a{}]", duration, index));
+        }
+        segments.push_str(&format!("[a{}]", index));
+    }
+    if with_audio {
+        segments.push_str(&format!("concat=n={}:v=1:a=1[v][a]", inputs.len()));
+    } else {
+        segments.push_str(&format!("concat=n={}:v=1:a=0[v]", inputs.len()));
+    }
+    chains.push(segments);
+    let filter = chains.join(";");
+
+    let mut cmd = Command::new(ffmpeg_binary());
+    for path in inputs {
+        cmd.arg("-i").arg(path);
+    }
+    cmd.arg("-filter_complex")
+        .arg(&filter)
+        .arg("-map")
+        .arg("[v]");
+
+    if with_audio {
+        cmd.arg("-map")
+            .arg("[a]")
+            .arg("-c:a")
+            .arg(container.audio_codec)
+            .arg("-b:a")
+            .arg("192k");
+    }
+
+    let output = cmd
+        .arg("-c:v")
+        .arg(container.video_codec)
+        .args(container.tag_args())
+        .args(container.speed_args(&video_quality))
+        .args(container.rate_control_args(&video_quality))
+        .args(video_quality.pixel_format_args())
+        .args(extra_args)
+        .arg("-y")
+        .arg(output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg failed to join the clips: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Rebuild a recording from segment files left behind by a crash
+/// Segments are ordered by the number at the end of their file name (e.g. "segment012.mp4"),
+/// checked with ffprobe, and the readable ones are concatenated into `output_path`
+#[tauri::command]
+fn recover_segments(segment_dir: String, output_path: String) -> Result<RecoverSegmentsResult, ClipForgeError> {
+    let dir = std::path::Path::new(&segment_dir);
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| ClipForgeError::io(format!("Failed to read segment directory '{}': {}", segment_dir, e), &e))?;
+
+    let mut segments: Vec<(Option<u32>, std::path::PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| SEGMENT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .map(|path| (segment_number(&path), path))
+        .collect();
+
+    if segments.is_empty() {
+        return Err(ClipForgeError::not_found(format!("No segment files found in '{}'", segment_dir)));
+    }
+    segments.sort();
+
+    // Gaps in the numbering mean segments were never written or were deleted
+    let numbers: Vec<u32> = segments.iter().filter_map(|(number, _)| *number).collect();
+    let missing_segments: Vec<u32> = match (numbers.first(), numbers.last()) {
+        (Some(&first), Some(&last)) => (first..=last).filter(|n| !numbers.contains(n)).collect(),
+        _ => Vec::new(),
+    };
+
+    // The segment being written during the crash is usually truncated and unreadable
+    let mut valid_segments = Vec::new();
+    let mut corrupt_segments = Vec::new();
+    for (_, path) in &segments {
+        match probe_duration_seconds(&path.to_string_lossy()) {
+            Some(duration) if duration > 0.0 => valid_segments.push(path.as_path()),
+            _ => corrupt_segments.push(path.to_string_lossy().to_string()),
+        }
+    }
+
+    if valid_segments.is_empty() {
+        return Err(ClipForgeError::process_failed(format!(
+            "None of the {} segment files in '{}' could be read",
+            segments.len(), segment_dir
+        )));
+    }
+
+    concat_copy(&valid_segments, dir, std::path::Path::new(&output_path))?;
+
+    let recovered_seconds = probe_duration_seconds(&output_path).unwrap_or(0.0);
+    let message = if missing_segments.is_empty() && corrupt_segments.is_empty() {
+        format!("Recovered {:.1} seconds from {} segments", recovered_seconds, valid_segments.len())
+    } else {
+        format!(
+            "Recovered {:.1} seconds from {} segments ({} missing, {} corrupt)",
+            recovered_seconds, valid_segments.len(), missing_segments.len(), corrupt_segments.len()
+        )
+    };
+
+    Ok(RecoverSegmentsResult {
+        success: true,
+        output_path,
+        recovered_seconds,
+        segment_count: valid_segments.len(),
+        missing_segments,
+        corrupt_segments,
+        message,
+    })
+}
+
+/// Trailing number of a segment file name, e.g. 12 for "segment012.mp4"
+fn segment_number(path: &std::path::Path) -> Option<u32> {
+    let stem = path.file_stem()?.to_str()?;
+    let digits_start = stem.rfind(|c: char| !c.is_ascii_digit()).map(|i| i + 1).unwrap_or(0);
+    stem[digits_start..].parse().ok()
+}
+
+/// Stop a screen recording process
+/// Returns the path to the saved recording file
+/// If the recording claimed pre-roll footage, it is prepended before returning
+/// `flush_timeout_ms` bounds how long FFmpeg may take to finalize the file before it is killed;
+/// raise it for high-bitrate recordings that report an empty file
+#[tauri::command(async)]
+fn stop_screen_recording(
+    process_id: u64,
+    write_metadata: Option<bool>,
+    flush_timeout_ms: Option<u64>, // Defaults to 5000
+    repair_if_killed: Option<bool>, // Rebuild the file's index (see repair_recording) if FFmpeg had to be killed
+) -> Result<StopRecordingResult, ClipForgeError> {
+    let flush_timeout_ms = flush_timeout_ms.unwrap_or(DEFAULT_FLUSH_TIMEOUT_MS);
+    if flush_timeout_ms > MAX_FLUSH_TIMEOUT_MS {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "flush_timeout_ms must be at most {}, got {}",
+            MAX_FLUSH_TIMEOUT_MS, flush_timeout_ms
+        )));
+    }
+
+    // Grab the capture details before the process is removed from the map; live outputs have no file to describe
+    let info = if write_metadata.unwrap_or(false) {
+        lock_recording_processes()
+            .get(&process_id)
+            .filter(|recording| !is_live_output(&recording.output_path))
+            .map(|recording| recording.info.clone())
+    } else {
+        None
+    };
+
+    let mut result = stop_recording_process(process_id, std::time::Duration::from_millis(flush_timeout_ms), repair_if_killed.unwrap_or(false))?;
+
+    let preroll_segments = PREROLL_SEGMENTS.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock pre-roll segments: {}", e)))?
+        .remove(&process_id);
+
+    if let Some(segments) = preroll_segments.filter(|segments| !segments.files.is_empty()) {
+        match prepend_preroll(&result.file_path, &segments) {
+            Ok(()) => {
+                result.message = "Recording saved successfully with pre-roll".to_string();
+            }
+            Err(e) => {
+                log::error!("Failed to prepend pre-roll to recording {}: {}", process_id, e);
+                result.message = format!("Recording saved successfully, but pre-roll could not be added: {}", e);
+            }
+        }
+    }
+
+    let append_target = APPEND_TARGETS.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock append targets: {}", e)))?
+        .remove(&process_id);
+
+    if let Some(existing_path) = append_target {
+        match append_take(&existing_path, &result.file_path) {
+            Ok(re_encoded) => {
+                let _ = std::fs::remove_file(&result.file_path);
+                result.file_path = existing_path;
+                result.message = if re_encoded {
+                    "Recording appended successfully (re-encoded to match the existing file)".to_string()
+                } else {
+                    "Recording appended successfully".to_string()
+                };
+            }
+            Err(e) => {
+                log::error!("Failed to append recording {} to '{}': {}", process_id, existing_path, e);
+                result.message = format!("Recording saved successfully, but it could not be appended to '{}': {}", existing_path, e);
+            }
+        }
+    }
+
+    // Written last so the duration includes any prepended pre-roll or appended takes
+    if let Some(info) = info {
+        if let Err(e) = write_metadata_sidecar(&result.file_path, &info) {
+            log::error!("Failed to write metadata sidecar for recording {}: {}", process_id, e);
+            result.message = format!("{}, but the metadata sidecar could not be written: {}", result.message, e);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Write "<recording>.json" next to a finished recording describing how it was captured
+fn write_metadata_sidecar(file_path: &str, info: &RecordingInfo) -> Result<(), ClipForgeError> {
+    let resolution = probe_video_resolution(file_path);
+    let metadata = RecordingMetadata {
+        capture_type: info.capture_type.to_string(),
+        file_path: file_path.to_string(),
+        framerate: info.framerate,
+        width: resolution.map(|(width, _)| width),
+        height: resolution.map(|(_, height)| height),
+        audio_device_index: info.audio_device_index,
+        audio_device_name: info.audio_device_index.and_then(audio_device_name),
+        started_at: info.started_at,
+        stopped_at: unix_timestamp(),
+        duration_seconds: probe_duration_seconds(file_path),
+    };
+
+    let json = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| ClipForgeError::internal(format!("Failed to serialize recording metadata: {}", e)))?;
+
+    let sidecar_path = std::path::Path::new(file_path).with_extension("json");
+    std::fs::write(&sidecar_path, json)
+        .map_err(|e| ClipForgeError::io(format!("Failed to write metadata sidecar: {}", e), &e))
+}
+
+/// Name of the avfoundation audio device at `index`, if it is still connected
+fn audio_device_name(index: u32) -> Option<String> {
+    list_audio_devices()
+        .ok()?
+        .devices
+        .into_iter()
+        .find(|device| device.index == index)
+        .map(|device| device.name)
+}
+
+/// Stop a tracked FFmpeg recording process and verify its output file
+/// FFmpeg gets up to `flush_timeout` after the stop request (see RecordingChild::request_stop) to finish
+/// writing before it is killed;
+/// the output file is then polled until it has data, since slow disks can lag behind the exit
+/// A killed FFmpeg may not have written the mp4 index; `repair_if_killed` remuxes the file in place
+fn stop_recording_process(
+    process_id: u64,
+    flush_timeout: std::time::Duration,
+    repair_if_killed: bool,
+) -> Result<StopRecordingResult, ClipForgeError> {
+    let mut processes = lock_recording_processes();
+
+    // Find and remove the process
+    let RecordingProcess { mut child, output_path, mut stderr_log, detached, info, size_limit_reached, .. } = processes.remove(&process_id)
+        .ok_or_else(|| ClipForgeError::not_found(format!("Recording with ID {} not found", process_id)))?;
+    drop(processes);
+
+    if detached {
+        forget_detached_recording(process_id);
+    }
+    info.remove_temp_files();
+
+    // FFmpeg may have already stopped itself at max_size_bytes, before or since the watcher noticed
+    let size_limit_reached = size_limit_reached
+        || (matches!(child.try_wait(), Ok(Some(_))) && reached_size_limit(&info, &output_path, child.exit_success()));
+    if size_limit_reached {
+        let stderr_output = stderr_log.finish();
+        if !stderr_output.is_empty() {
+            log::info!("Recording {} FFmpeg stderr:\n{}", process_id, stderr_output);
+        }
+        return Ok(StopRecordingResult {
+            success: true,
+            file_path: output_path,
+            message: format!(
+                "Recording stopped automatically at its size limit of {} bytes",
+                info.max_size_bytes.unwrap_or_default()
+            ),
+            clean_shutdown: true,
+            size_limit_reached: true,
+        });
+    }
+
+    // Try to gracefully stop FFmpeg first
+    match child.request_stop() {
+        Ok(()) => {
+            // Wait for FFmpeg to flush buffers and write the trailer
+            if wait_for_exit(&mut child, flush_timeout) {
+                // Process already exited, so the stderr reader is done too
+                let stderr_output = stderr_log.finish();
+                if !stderr_output.is_empty() {
+                    log::info!("Recording {} FFmpeg stderr:\n{}", process_id, stderr_output);
+                }
+
+                // Check file
+                if is_live_output(&output_path) || wait_for_nonempty_file(&output_path) {
+                    return Ok(StopRecordingResult {
+                        success: true,
+                        file_path: output_path,
+                        message: "Recording saved successfully".to_string(),
+                        clean_shutdown: true,
+                        size_limit_reached: false,
+                    });
+                }
+            } else {
+                log::warn!("Recording {} did not exit within {:?} of the stop request, killing it", process_id, flush_timeout);
+            }
+        }
+        Err(e) => log::warn!("Failed to ask recording {} to stop gracefully: {}", process_id, e),
+    }
+
+    // Kill the process if it's still running
+    let _ = child.kill();
+
+    // Wait for the process to finish
+    let wait_result = child.wait();
+    
+    // Collect what the stderr reader captured for error messages
+    let stderr_output = stderr_log.finish();
+    if !stderr_output.is_empty() {
+        log::info!("Recording {} FFmpeg stderr:\n{}", process_id, stderr_output);
+    }
+
+    // Nothing was written to disk for a pipe or stream
+    if is_live_output(&output_path) {
+        return Ok(StopRecordingResult {
+            success: true,
+            file_path: output_path,
+            message: "Stream stopped".to_string(),
+            clean_shutdown: false,
+            size_limit_reached: false,
+        });
+    }
+
+    // Give the file system time to sync (FFmpeg might still be flushing)
+    wait_for_nonempty_file(&output_path);
+
+    // Check if the output file exists
+    if std::path::Path::new(&output_path).exists() {
+        // Verify file is not empty
+        if let Ok(metadata) = std::fs::metadata(&output_path) {
+            if metadata.len() > 0 {
+                if repair_if_killed {
+                    log::info!("Recording {} was killed, repairing '{}'", process_id, output_path);
+                    return Ok(match repair_in_place(&output_path) {
+                        Ok(()) => StopRecordingResult {
+                            success: true,
+                            file_path: output_path,
+                            message: "Recording saved successfully after repairing its index".to_string(),
+                            clean_shutdown: false,
+                            size_limit_reached: false,
+                        },
+                        Err(e) => {
+                            log::error!("Failed to repair recording {}: {}", process_id, e);
+                            StopRecordingResult {
+                                success: false,
+                                file_path: output_path,
+                                message: format!("Recording saved, but FFmpeg was killed and the file could not be repaired: {}", e),
+                                clean_shutdown: false,
+                                size_limit_reached: false,
+                            }
+                        }
+                    });
+                }
+                return Ok(StopRecordingResult {
+                    success: true,
+                    file_path: output_path,
+                    message: "Recording saved successfully".to_string(),
+                    clean_shutdown: false,
+                    size_limit_reached: false,
+                });
+            } else {
+                return Err(ClipForgeError::output_file_missing(format!(
+                    "Recording file exists but is empty (0 bytes). FFmpeg may have failed to record. Stderr: {}",
+                    if stderr_output.is_empty() { "No error output".to_string() } else { stderr_output }
+                )));
+            }
+        }
+    }
+    
+    // File doesn't exist - provide detailed error information
+    let error_details = match wait_result {
+        Ok(status) => {
+            let mut details = format!("Process exited with status: {}", status);
+            if !stderr_output.is_empty() {
+                details.push_str(&format!("\nFFmpeg stderr output:\n{}", stderr_output));
+            }
+            details
+        },
+        Err(e) => format!("Failed to wait for process: {}", e),
+    };
+    
+    Err(ClipForgeError::output_file_missing(format!(
+        "Recording file not found at '{}'.\n{}",
+        output_path, error_details
+    )))
+}
+
+/// Rebuild a recording's index by stream copying it into a new file
+/// Recovers files whose process was killed before FFmpeg finished writing the mp4 trailer,
+/// as long as FFmpeg can still read the streams
+fn remux_for_repair(source_path: &str, output_path: &str, extra_args: &[String]) -> Result<(), ClipForgeError> {
+    let output = Command::new(ffmpeg_binary())
+        .arg("-err_detect")
+        .arg("ignore_err")
+        .arg("-fflags")
+        .arg("+genpts+discardcorrupt")
+        .arg("-i")
+        .arg(source_path)
+        .arg("-map")
+        .arg("0:v?")
+        .arg("-map")
+        .arg("0:a?")
+        .arg("-c")
+        .arg("copy")
+        .arg("-movflags")
+        .arg("+faststart")
+        .args(extra_args)
+        .arg("-y")
+        .arg(output_path)
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    if !output.status.success() || probe_duration_seconds(output_path).is_none() {
+        let _ = std::fs::remove_file(output_path);
+        return Err(ClipForgeError::process_failed(format!(
+            "FFmpeg could not rebuild '{}': {}",
+            source_path,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(())
+}
+
+/// `path` with `suffix` added to the file stem, e.g. "take.mp4" -> "take-repaired.mp4"
+fn path_with_stem_suffix(path: &str, suffix: &str) -> String {
+    let path = std::path::Path::new(path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("recording");
+    let file_name = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("{}{}.{}", stem, suffix, ext),
+        None => format!("{}{}", stem, suffix),
+    };
+    path.with_file_name(file_name).to_string_lossy().to_string()
+}
+
+/// Repair a recording and replace the original with the result
+fn repair_in_place(path: &str) -> Result<(), ClipForgeError> {
+    let repaired_path = path_with_stem_suffix(path, "-repairing");
+    remux_for_repair(path, &repaired_path, &[])?;
+    std::fs::rename(&repaired_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&repaired_path);
+        ClipForgeError::io(format!("Failed to replace '{}' with the repaired file: {}", path, e), &e)
+    })
+}
+
+/// Start FFmpeg in its own session so it isn't signalled or killed along with the app
+/// FFmpeg ignores SIGPIPE, so it keeps recording after the app's end of the stderr pipe closes
+fn detach_from_app(cmd: &mut Command) -> Result<(), ClipForgeError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // setsid is async-signal-safe, as required between fork and exec
+        unsafe {
+            cmd.pre_exec(|| nix::unistd::setsid().map(|_| ()).map_err(std::io::Error::from));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = cmd;
+        Err(ClipForgeError::invalid_argument("detached recordings are only supported on macOS and Linux"))
+    }
+}
+
+/// Start FFmpeg at a lowered scheduling priority so encoding yields the CPU to other apps
+/// Under load this can make FFmpeg fall behind the capture and drop frames
+fn lower_priority(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Same as nice(10) from a normal-priority app; setpriority is async-signal-safe and,
+        // unlike nice, reports failure unambiguously
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, LOW_PRIORITY_NICENESS) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+        cmd.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+    }
+}
+
+/// Start tracking a recording; detached ones are also written to the recovery file
+/// The recording is already running, so a recovery file that can't be written is only logged
+fn track_recording(process_id: u64, mut recording: RecordingProcess, detached: bool) {
+    if detached {
+        recording.detached = true;
+        let entry = DetachedRecording {
+            process_id,
+            pid: recording.child.id(),
+            output_path: recording.output_path.clone(),
+            capture_type: recording.info.capture_type.to_string(),
+            framerate: recording.info.framerate,
+            audio_device_index: recording.info.audio_device_index,
+            started_at: recording.info.started_at,
+            max_size_bytes: recording.info.max_size_bytes,
+            preview_path: recording.info.preview_path.clone(),
+            text_file: recording.info.text_file.clone(),
+        };
+        if let Err(e) = update_detached_recordings(|entries| entries.push(entry)) {
+            log::warn!("Failed to add detached recording {} to the recovery file: {}", process_id, e);
+        }
+    }
+
+    if let Ok(mut started) = STARTED_RECORDINGS.lock() {
+        started.push(std::path::PathBuf::from(&recording.output_path));
+    }
+    lock_recording_processes().insert(process_id, recording);
+}
+
+fn detached_recordings_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(DETACHED_RECORDINGS_FILE_NAME)
+}
+
+/// Load, change and save the recovery file; a missing or unreadable file counts as empty
+fn update_detached_recordings(update: impl FnOnce(&mut Vec<DetachedRecording>)) -> Result<(), ClipForgeError> {
+    let _guard = DETACHED_RECORDINGS_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = detached_recordings_path();
+
+    let mut entries: Vec<DetachedRecording> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+    update(&mut entries);
+
+    if entries.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return Ok(());
+    }
+    let contents = serde_json::to_string_pretty(&entries)
+        .map_err(|e| ClipForgeError::internal(format!("Failed to serialize detached recordings: {}", e)))?;
+    std::fs::write(&path, contents)
+        .map_err(|e| ClipForgeError::io(format!("Failed to write {}: {}", path.display(), e), &e))
+}
+
+fn forget_detached_recording(process_id: u64) {
+    if let Err(e) = update_detached_recordings(|entries| entries.retain(|entry| entry.process_id != process_id)) {
+        log::warn!("Failed to remove detached recording {} from the recovery file: {}", process_id, e);
+    }
+}
+
+/// Whether `pid` is still the FFmpeg writing `output_path`, rather than a new process that reused the PID
+fn is_detached_ffmpeg(pid: u32, output_path: &str) -> bool {
+    if !recording_child::is_running(pid) {
+        return false;
+    }
+    Command::new("ps")
+        .arg("-o")
+        .arg("command=")
+        .arg("-p")
+        .arg(pid.to_string())
+        .output()
+        .map(|output| output.status.success() && String::from_utf8_lossy(&output.stdout).contains(output_path))
+        .unwrap_or(false)
+}
+
+/// Track the detached recordings left running by a previous app session again, e.g. after a crash,
+/// so they can be stopped as usual. Call once on startup
+/// Recordings keep their process IDs where possible; entries whose FFmpeg has since exited are dropped
+#[tauri::command]
+fn reattach_recordings() -> Result<Vec<RecordingResult>, ClipForgeError> {
+    let mut reattached = Vec::new();
+
+    update_detached_recordings(|entries| {
+        let mut processes = lock_recording_processes();
+        entries.retain_mut(|entry| {
+            if processes.values().any(|recording| recording.child.id() == entry.pid) {
+                return true;
+            }
+            if !is_detached_ffmpeg(entry.pid, &entry.output_path) {
+                log::info!("Detached recording {} ({}) is no longer running", entry.process_id, entry.output_path);
+                return false;
+            }
+
+            if processes.contains_key(&entry.process_id) {
+                entry.process_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
+            }
+            NEXT_RECORDING_ID.fetch_max(entry.process_id + 1, Ordering::SeqCst);
+
+            let capture_type = match entry.capture_type.as_str() {
+                "webcam" => "webcam",
+                "screen_webcam" => "screen_webcam",
+                "dual_screen" => "dual_screen",
+                _ => "screen",
+            };
+            let info = RecordingInfo {
+                capture_type,
+                framerate: entry.framerate,
+                audio_device_index: entry.audio_device_index,
+                started_at: entry.started_at,
+                max_size_bytes: entry.max_size_bytes,
+                preview_path: entry.preview_path.clone(),
+                text_file: entry.text_file.clone(),
+                internal: false,
+            };
+            processes.insert(entry.process_id, RecordingProcess::reattached(entry.pid, entry.output_path.clone(), info));
+            log::info!("Reattached detached recording {} (pid {}): {}", entry.process_id, entry.pid, entry.output_path);
+
+            reattached.push(RecordingResult {
+                process_id: entry.process_id,
+                output_path: entry.output_path.clone(),
+                screen_device_index: None,
+                command: None,
+                framerate: entry.framerate,
+                resolution: None,
+                audio_enabled: entry.audio_device_index.is_some(),
+            });
+            true
+        });
+    })?;
+
+    Ok(reattached)
+}
+
+/// Poll until `child` exits or `timeout` passes; returns whether it exited
+fn wait_for_exit(child: &mut RecordingChild, timeout: std::time::Duration) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => return true,
+            Ok(None) if std::time::Instant::now() < deadline => {
+                std::thread::sleep(std::time::Duration::from_millis(STOP_POLL_MS));
+            }
+            Ok(None) => return false,
+            Err(e) => {
+                log::error!("Error checking process status: {}", e);
+                return false;
+            }
+        }
+    }
+}
+
+/// Poll until the file at `path` exists with a non-zero size, for up to OUTPUT_FILE_WAIT_MS
+/// Returns as soon as it does, so finished recordings aren't held up; false means it never did
+fn wait_for_nonempty_file(path: &str) -> bool {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(OUTPUT_FILE_WAIT_MS);
+    loop {
+        if std::fs::metadata(path).map(|metadata| metadata.len() > 0).unwrap_or(false) {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(OUTPUT_FILE_POLL_MS));
+    }
+}
+
+/// Folder unsaved recordings are written to when no output_path is given
+/// Default names are "clipforge-<kind>-<unix timestamp>.<ext>", e.g. clipforge-recording-1700000000.mp4
+#[tauri::command]
+fn get_recordings_dir() -> String {
+    recordings_dir().to_string_lossy().to_string()
+}
+
+/// Write default-named recordings to `path` instead of the OS temp dir, which may be cleared on reboot
+/// The folder must already exist and be writable; returns it as an absolute path. delete_recording
+/// still only removes ClipForge's own recordings in it, never other files the folder holds
+#[tauri::command]
+fn set_default_output_dir(path: String) -> Result<String, ClipForgeError> {
+    let dir = std::path::Path::new(&path);
+    if !dir.is_dir() {
+        return Err(ClipForgeError::not_found(format!("Output folder not found: {}", path)));
+    }
+    let dir = dir.canonicalize()
+        .map_err(|e| ClipForgeError::io(format!("Failed to resolve output folder {}: {}", path, e), &e))?;
+    ensure_output_dir_writable(&dir.join("clipforge-output-dir-check").to_string_lossy())?;
+
+    let mut default_dir = DEFAULT_OUTPUT_DIR.lock()
+        .map_err(|e| ClipForgeError::internal(format!("Failed to lock default output folder: {}", e)))?;
+    log::info!("Default output folder set to {}", dir.display());
+    *default_dir = Some(dir.clone());
+
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// Folder set with set_default_output_dir; None while recordings still go to the OS temp dir
+#[tauri::command]
+fn get_default_output_dir() -> Option<String> {
+    DEFAULT_OUTPUT_DIR.lock().ok()
+        .and_then(|dir| dir.clone())
+        .map(|dir| dir.to_string_lossy().to_string())
+}
+
+/// Whether a resolved path is named like a default recording ("clipforge-*.mp4" etc.), which may be
+/// left from an earlier session
+fn is_default_named_recording(file: &std::path::Path) -> bool {
+    file.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with(DEFAULT_RECORDING_PREFIX))
+        && file.extension().and_then(|ext| ext.to_str())
+            .is_some_and(|ext| RECORDING_FILE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Whether a resolved path is the output of a recording started this session, in whatever folder
+fn started_this_session(file: &std::path::Path) -> bool {
+    STARTED_RECORDINGS.lock().is_ok_and(|started| {
+        started.iter().any(|path| path.canonicalize().is_ok_and(|started| started == file))
+    })
+}
+
+/// Default-named recordings in the recordings folder that no running recording owns,
+/// e.g. left behind by a crash, so the UI can offer to recover or delete them
+/// Recordings named after the active window don't carry the prefix and aren't listed
+#[tauri::command]
+fn list_orphaned_recordings() -> Result<Vec<VideoFile>, ClipForgeError> {
+    let dir = recordings_dir();
+    let entries = std::fs::read_dir(&dir)
+        .map_err(|e| ClipForgeError::io(format!("Failed to read recordings folder {}: {}", dir.display(), e), &e))?;
+
+    let active: Vec<std::path::PathBuf> = lock_recording_processes()
+        .values()
+        .map(|recording| std::path::PathBuf::from(&recording.output_path))
+        .collect();
+
+    let mut orphans: Vec<VideoFile> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?.to_string();
+            let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+            if !name.starts_with(DEFAULT_RECORDING_PREFIX)
+                || !RECORDING_FILE_EXTENSIONS.contains(&extension.as_str())
+                || active.contains(&path)
+            {
+                return None;
+            }
+
+            let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+            Some(VideoFile {
+                path: path.to_string_lossy().to_string(),
+                name,
+                size: metadata.len(),
+            })
+        })
+        .collect();
+
+    orphans.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(orphans)
+}
+
+/// Delete a recording (and its .json metadata sidecar, if any)
+/// Only ClipForge's own recordings are accepted, so the frontend can't be used to delete arbitrary
+/// files: default-named ones ("clipforge-*.mp4" etc.) in the recordings folder, and any started this
+/// session, including ones written to an earlier default output folder or an explicit output_path
+#[tauri::command]
+fn delete_recording(path: String) -> Result<ExportResult, ClipForgeError> {
+    let file = std::path::Path::new(&path);
+    if !file.exists() {
+        return Err(ClipForgeError::not_found(format!("Recording not found: {}", path)));
     }
-    
-    cmd.arg("-r")
-        .arg("30")  // Output framerate
-        .arg("-c:v")
-        .arg("libx264")  // Video codec
-        .arg("-preset")
-        .arg("fast")  // Encoding speed
-        .arg("-crf")
-        .arg("23")  // Quality (lower = better, 18-28 is reasonable range)
-        .arg("-pix_fmt")
-        .arg("yuv420p")  // Pixel format for compatibility
-        .arg("-y")  // Overwrite output file
-        .arg(&output)
-        // Capture stderr to log errors for debugging
-        .stderr(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::null());
 
-    // Spawn the FFmpeg process
-    let mut child = cmd.spawn()
-        .map_err(|e| format!("Failed to start FFmpeg process: {}. Make sure FFmpeg is installed and available in PATH.", e))?;
+    // Compare resolved paths so "..", symlinks and /tmp vs /private/tmp can't slip through
+    let file = file.canonicalize()
+        .map_err(|e| ClipForgeError::io(format!("Failed to resolve {}: {}", path, e), &e))?;
+    let dir = recordings_dir().canonicalize()
+        .map_err(|e| ClipForgeError::io(format!("Failed to resolve recordings folder: {}", e), &e))?;
+    let default_named_here = file.parent() == Some(dir.as_path()) && is_default_named_recording(&file);
+    if !file.is_file() || !(default_named_here || started_this_session(&file)) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Only recordings made by ClipForge can be deleted (default-named ones in {} or ones started this session), got {}",
+            dir.display(),
+            path
+        )));
+    }
 
-    // Give FFmpeg a moment to initialize and check if it's still running
-    std::thread::sleep(std::time::Duration::from_millis(200));
-    
-    // Check if process immediately crashed
-    match child.try_wait() {
-        Ok(Some(status)) => {
-            // Process already exited - try to read stderr for error info
-            let error_msg = if let Some(mut stderr) = child.stderr.take() {
-                use std::io::Read;
-                let mut error_output = String::new();
-                let _ = stderr.read_to_string(&mut error_output);
-                if !error_output.is_empty() {
-                    format!("FFmpeg exited immediately with status {:?}. Error output: {}", status, error_output)
-                } else {
-                    format!("FFmpeg exited immediately with status {:?}", status)
-                }
-            } else {
-                format!("FFmpeg exited immediately with status {:?}", status)
-            };
-            return Err(error_msg);
-        }
-        Ok(None) => {
-            // Process is still running, good!
+    let in_use = lock_recording_processes()
+        .values()
+        .any(|recording| std::path::Path::new(&recording.output_path).canonicalize().is_ok_and(|active| active == file));
+    if in_use {
+        return Err(ClipForgeError::invalid_argument(format!("Recording is still in progress: {}", path)));
+    }
+
+    std::fs::remove_file(&file)
+        .map_err(|e| ClipForgeError::io(format!("Failed to delete {}: {}", path, e), &e))?;
+
+    let sidecar = file.with_extension("json");
+    let removed_sidecar = sidecar.is_file();
+    if removed_sidecar {
+        std::fs::remove_file(&sidecar)
+            .map_err(|e| ClipForgeError::io(format!("Deleted the recording but not its metadata sidecar {}: {}", sidecar.display(), e), &e))?;
+    }
+
+    let mut settings = HashMap::new();
+    settings.insert("mode".to_string(), "delete".to_string());
+    settings.insert("removed_sidecar".to_string(), removed_sidecar.to_string());
+
+    Ok(ExportResult {
+        success: true,
+        message: if removed_sidecar {
+            "Recording and metadata sidecar deleted".to_string()
+        } else {
+            "Recording deleted".to_string()
+        },
+        source_path: Some(path),
+        settings,
+        timestamp: Some(unix_timestamp()),
+        ..Default::default()
+    })
+}
+
+/// List the recordings that are currently running
+/// Lets the UI recover its state after a reload and offer to stop recordings it lost track of
+#[tauri::command]
+fn list_active_recordings() -> Result<Vec<ActiveRecording>, ClipForgeError> {
+    let processes = lock_recording_processes();
+
+    let mut recordings: Vec<ActiveRecording> = processes
+        .iter()
+        .filter(|(_, recording)| !recording.info.internal)
+        .map(|(process_id, recording)| ActiveRecording {
+            process_id: *process_id,
+            output_path: recording.output_path.clone(),
+            elapsed_seconds: recording.started_at.elapsed().as_secs_f64(),
+            size_limit_reached: recording.size_limit_reached,
+        })
+        .collect();
+
+    // Oldest recording first
+    recordings.sort_by(|a, b| b.elapsed_seconds.total_cmp(&a.elapsed_seconds));
+    Ok(recordings)
+}
+
+/// Latest frame of a running recording as JPEG bytes, for a live thumbnail in the UI
+/// Only available for recordings started with `preview`; FFmpeg refreshes it about once a second
+#[tauri::command]
+fn get_preview_frame(process_id: u64) -> Result<Vec<u8>, ClipForgeError> {
+    let preview_path = lock_recording_processes()
+        .get(&process_id)
+        .ok_or_else(|| ClipForgeError::not_found(format!("Recording with ID {} not found", process_id)))?
+        .info
+        .preview_path
+        .clone()
+        .ok_or_else(|| ClipForgeError::invalid_argument(format!("Recording {} was not started with preview", process_id)))?;
+
+    // FFmpeg overwrites the file in place, so a read can catch it half written; a complete JPEG ends with FFD9
+    for attempt in 1..=PREVIEW_READ_ATTEMPTS {
+        match std::fs::read(&preview_path) {
+            Ok(frame) if frame.ends_with(&[0xFF, 0xD9]) => return Ok(frame),
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ClipForgeError::not_found(format!("Recording {} has no preview frame yet", process_id)));
+            }
+            Err(e) => {
+                return Err(ClipForgeError::io(format!("Failed to read preview frame {}: {}", preview_path.display(), e), &e));
+            }
         }
-        Err(e) => {
-            return Err(format!("Failed to check FFmpeg process status: {}", e));
+        if attempt < PREVIEW_READ_ATTEMPTS {
+            std::thread::sleep(std::time::Duration::from_millis(PREVIEW_RETRY_MS));
         }
     }
 
-    // Generate a unique process ID
-    let process_id = child.id();
+    Err(ClipForgeError::process_failed(format!("Preview frame for recording {} is still being written", process_id)))
+}
 
-    // Store the process handle and output path
-    let mut processes = RECORDING_PROCESSES.lock()
-        .map_err(|e| format!("Failed to lock recording processes: {}", e))?;
-    
-    processes.insert(process_id, (child, output.clone()));
+/// List on-screen application windows that can be passed to start_screen_recording as `window_id`
+/// Titles are only available once Screen Recording permission is granted; owner_name always is
+#[tauri::command]
+fn list_windows() -> Result<Vec<WindowInfo>, ClipForgeError> {
+    let windows = active_window::list_windows();
+    if windows.is_empty() && !cfg!(target_os = "macos") {
+        return Err(ClipForgeError::invalid_argument("Window listing is only supported on macOS"));
+    }
+    Ok(windows)
+}
 
-    Ok(RecordingResult {
-        process_id,
-        output_path: output,
+/// Recent FFmpeg output of a running recording (newest last), for diagnosing it while it runs
+/// FFmpeg's in-place progress line is kept only in its latest form
+#[tauri::command]
+fn get_recording_log(process_id: u64) -> Result<String, ClipForgeError> {
+    lock_recording_processes()
+        .get(&process_id)
+        .map(|recording| recording.stderr_log.contents())
+        .ok_or_else(|| ClipForgeError::not_found(format!("Recording with ID {} not found", process_id)))
+}
+
+/// Current fps, encoding speed and dropped-frame count of a running recording
+#[tauri::command]
+fn get_encoding_stats(process_id: u64) -> Result<EncodingStats, ClipForgeError> {
+    let progress = lock_recording_processes()
+        .get(&process_id)
+        .map(|recording| recording.stderr_log.latest_progress())
+        .ok_or_else(|| ClipForgeError::not_found(format!("Recording with ID {} not found", process_id)))?
+        .unwrap_or_default();
+
+    Ok(EncodingStats {
+        fps: progress_value(&progress, "fps").and_then(|value| value.parse().ok()),
+        speed: progress_value(&progress, "speed").and_then(|value| value.trim_end_matches('x').parse().ok()),
+        dropped_frames: progress_value(&progress, "drop").and_then(|value| value.parse().ok()),
+        frame: progress_value(&progress, "frame").and_then(|value| value.parse().ok()),
     })
 }
 
-/// Stop a screen recording process
-/// Returns the path to the saved recording file
+/// Value of `key` in an FFmpeg progress line such as "frame=  120 fps= 30 ... drop=2 speed=1.01x"
+/// FFmpeg pads values after the '=', so whitespace there is skipped
+fn progress_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("{}=", key);
+    let mut search_from = 0;
+    while let Some(offset) = line[search_from..].find(&pattern) {
+        let start = search_from + offset;
+        // Only match whole keys, not the tail of a longer one
+        if start == 0 || line[..start].ends_with(char::is_whitespace) {
+            let rest = line[start + pattern.len()..].trim_start();
+            return rest.split_whitespace().next().filter(|value| *value != "N/A");
+        }
+        search_from = start + pattern.len();
+    }
+    None
+}
+
+/// Probe an avfoundation screen for the capture framerates it supports
+/// `screen_index` is the screen number (0 = first screen), looked up as the avfoundation
+/// "Capture screen N" device, or guessed as device 4 + N without a listing. The device is opened with a
+/// deliberately invalid framerate so avfoundation prints its supported modes; screens that accept any
+/// rate fall back to common values
 #[tauri::command]
-fn stop_screen_recording(process_id: u32) -> Result<StopRecordingResult, String> {
-    let mut processes = RECORDING_PROCESSES.lock()
-        .map_err(|e| format!("Failed to lock recording processes: {}", e))?;
+fn get_display_framerates(screen_index: u32) -> Result<DisplayFramerates, ClipForgeError> {
+    let device_index = screen_capture_device(screen_index).unwrap_or(SCREEN_CAPTURE_DEVICE_OFFSET + screen_index);
 
-    // Find and remove the process
-    let (mut child, output_path) = processes.remove(&process_id)
-        .ok_or_else(|| format!("Recording process with ID {} not found", process_id))?;
+    let output = Command::new(ffmpeg_binary())
+        .arg("-f")
+        .arg("avfoundation")
+        .arg("-framerate")
+        .arg(PROBE_INVALID_FRAMERATE)
+        .arg("-i")
+        .arg(format!("{}:", device_index))
+        .arg("-frames:v")
+        .arg("1")
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
 
-    // Get the actual child process ID (might be different from stored process_id)
-    let child_pid = child.id();
-    
-    // Try to gracefully stop FFmpeg first
-    #[cfg(unix)]
-    {
-        // On Unix, try to send SIGINT for graceful shutdown using the actual child PID
-        let pid = nix::unistd::Pid::from_raw(child_pid as i32);
-        if nix::sys::signal::kill(pid, nix::sys::signal::Signal::SIGINT).is_ok() {
-            // Give FFmpeg a moment to flush buffers
-            std::thread::sleep(std::time::Duration::from_millis(500));
-            
-            // Check if process already exited gracefully
-            match child.try_wait() {
-                Ok(Some(_)) => {
-                    // Process already exited, check file
-                    if std::path::Path::new(&output_path).exists() {
-                        return Ok(StopRecordingResult {
-                            success: true,
-                            file_path: output_path,
-                            message: "Recording saved successfully".to_string(),
-                        });
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if is_device_open_error(&stderr) {
+        return Err(ClipForgeError::not_found(format!(
+            "Screen {} (avfoundation device {}) could not be opened: {}",
+            screen_index, device_index, stderr.trim()
+        )));
+    }
+
+    // Device rejected the probe rate and listed its modes
+    if let Some((_, supported_modes)) = parse_unsupported_mode_error(&stderr) {
+        if !supported_modes.is_empty() {
+            let mut framerates: Vec<f64> = Vec::new();
+            for mode in &supported_modes {
+                for rate in COMMON_FRAMERATES
+                    .iter()
+                    .copied()
+                    .filter(|rate| *rate >= mode.min_framerate && *rate <= mode.max_framerate)
+                    .chain(std::iter::once(mode.max_framerate.round()))
+                {
+                    if !framerates.contains(&rate) {
+                        framerates.push(rate);
                     }
                 }
-                Ok(None) => {
-                    // Process still running, continue to kill
-                }
-                Err(e) => {
-                    eprintln!("Error checking process status: {}", e);
-                }
             }
+            framerates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+            return Ok(DisplayFramerates {
+                screen_index,
+                framerates,
+                reported_by_device: true,
+            });
         }
     }
 
-    // Kill the process if it's still running
-    let _ = child.kill();
+    // Screen capture devices usually accept any rate, so offer the common ones
+    Ok(DisplayFramerates {
+        screen_index,
+        framerates: COMMON_FRAMERATES.to_vec(),
+        reported_by_device: false,
+    })
+}
 
-    // Wait for the process to finish
-    let wait_result = child.wait();
-    
-    // Try to read stderr for error messages
-    let mut stderr_output = String::new();
-    if let Some(mut stderr) = child.stderr.take() {
-        use std::io::Read;
-        let _ = stderr.read_to_string(&mut stderr_output);
+/// Capture a short sample from an audio device and report its RMS and peak level,
+/// so the UI can check that a microphone picks up sound before recording
+/// Async, since it blocks on FFmpeg for the whole sample
+#[tauri::command(async)]
+fn measure_audio_level(device_index: u32, duration_ms: u64) -> Result<AudioLevel, ClipForgeError> {
+    if !(MIN_AUDIO_LEVEL_SAMPLE_MS..=MAX_AUDIO_LEVEL_SAMPLE_MS).contains(&duration_ms) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "duration_ms must be between {} and {}, got {}",
+            MIN_AUDIO_LEVEL_SAMPLE_MS, MAX_AUDIO_LEVEL_SAMPLE_MS, duration_ms
+        )));
     }
 
-    // Give more time for file system to sync (FFmpeg might still be flushing)
-    std::thread::sleep(std::time::Duration::from_millis(1000));
+    let output = Command::new(ffmpeg_binary())
+        .arg("-f")
+        .arg("avfoundation")
+        .arg("-i")
+        .arg(format!(":{}", device_index))
+        .arg("-t")
+        .arg(format!("{:.3}", duration_ms as f64 / 1000.0))
+        .arg("-af")
+        .arg("astats")
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
 
-    // Check if the output file exists
-    if std::path::Path::new(&output_path).exists() {
-        // Verify file is not empty
-        if let Ok(metadata) = std::fs::metadata(&output_path) {
-            if metadata.len() > 0 {
-                return Ok(StopRecordingResult {
-                    success: true,
-                    file_path: output_path,
-                    message: "Recording saved successfully".to_string(),
-                });
-            } else {
-                return Err(format!(
-                    "Recording file exists but is empty (0 bytes). FFmpeg may have failed to record. Stderr: {}",
-                    if stderr_output.is_empty() { "No error output".to_string() } else { stderr_output }
-                ));
-            }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if is_device_open_error(&stderr) {
+        return Err(ClipForgeError::not_found(format!(
+            "Audio device {} could not be opened: {}",
+            device_index, stderr.trim()
+        )));
+    }
+    if !output.status.success() {
+        return Err(ClipForgeError::process_failed(format!("FFmpeg failed to sample audio device {}: {}", device_index, stderr.trim())));
+    }
+
+    // astats prints per-channel sections, then an "Overall" one covering all channels
+    let overall = stderr
+        .rfind("Overall")
+        .map(|start| &stderr[start..])
+        .ok_or_else(|| ClipForgeError::process_failed(format!("FFmpeg did not report levels for audio device {}", device_index)))?;
+    let rms_db = astats_level(overall, "RMS level dB:");
+    let peak_db = astats_level(overall, "Peak level dB:");
+
+    Ok(AudioLevel {
+        device_index,
+        rms_db,
+        peak_db,
+        silent: peak_db.is_none_or(|peak| peak < SILENCE_PEAK_DB),
+    })
+}
+
+/// First astats value after `key`, e.g. "Peak level dB: -12.5"; None for "-inf" or a missing key
+fn astats_level(stats: &str, key: &str) -> Option<f64> {
+    let value = stats.lines().find_map(|line| line.split_once(key).map(|(_, value)| value.trim()))?;
+    value.parse::<f64>().ok().filter(|level| level.is_finite())
+}
+
+/// List the resolutions and framerates a camera supports, so the UI only offers valid video_size values
+/// The device is opened with a deliberately invalid size so avfoundation prints its supported modes
+#[tauri::command]
+fn list_camera_capabilities(device_index: u32) -> Result<Vec<CameraMode>, ClipForgeError> {
+    let output = Command::new(ffmpeg_binary())
+        .arg("-f")
+        .arg("avfoundation")
+        .arg("-video_size")
+        .arg(PROBE_INVALID_VIDEO_SIZE)
+        .arg("-i")
+        .arg(format!("{}:", device_index))
+        .arg("-frames:v")
+        .arg("1")
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .logged_output()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to run FFmpeg: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if is_device_open_error(&stderr) {
+        return Err(ClipForgeError::not_found(format!(
+            "Camera {} could not be opened: {}",
+            device_index, stderr.trim()
+        )));
+    }
+
+    let supported_modes = parse_unsupported_mode_error(&stderr)
+        .map(|(_, supported_modes)| supported_modes)
+        .filter(|supported_modes| !supported_modes.is_empty())
+        .ok_or_else(|| {
+            ClipForgeError::not_found(format!("Device {} did not report its capture modes; it may not be a camera", device_index))
+        })?;
+
+    // avfoundation lists a mode per pixel format, so the same size and rate can appear several times
+    let mut modes: Vec<CameraMode> = Vec::new();
+    for mode in supported_modes {
+        let mode = CameraMode { width: mode.width, height: mode.height, fps: mode.max_framerate };
+        if !modes.contains(&mode) {
+            modes.push(mode);
         }
     }
-    
-    // File doesn't exist - provide detailed error information
-    let error_details = match wait_result {
-        Ok(status) => {
-            let status_str = format!("{:?}", status);
-            let mut details = format!("Process exited with status: {}", status_str);
-            if !stderr_output.is_empty() {
-                details.push_str(&format!("\nFFmpeg stderr output:\n{}", stderr_output));
-            }
-            details
-        },
-        Err(e) => format!("Failed to wait for process: {}", e),
-    };
-    
-    Err(format!(
-        "Recording file not found at '{}'.\n{}",
-        output_path, error_details
-    ))
+    modes.sort_by(|a, b| {
+        (b.width * b.height)
+            .cmp(&(a.width * a.height))
+            .then(b.fps.partial_cmp(&a.fps).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    Ok(modes)
 }
 
 /// Check screen recording permission status on macOS
-/// Note: Direct permission checking requires Objective-C/Swift interop, so this is a placeholder
+/// Uses CGPreflightScreenCaptureAccess, which reads the current grant without prompting the user;
+/// other platforms have no such permission and always report it as granted
 #[tauri::command]
-fn check_screen_recording_permission() -> Result<PermissionStatus, String> {
+fn check_screen_recording_permission() -> Result<PermissionStatus, ClipForgeError> {
     #[cfg(target_os = "macos")]
     {
-        // On macOS, we can't directly check permissions from Rust without FFI
-        // The actual permission check will happen when getDisplayMedia() is called
-        // For now, return a placeholder that indicates permission should be checked by the frontend
-        Ok(PermissionStatus {
-            has_permission: false, // Assume false, frontend will verify via getDisplayMedia()
-            message: "Permission status cannot be determined from backend. Frontend will check via getDisplayMedia() API.".to_string(),
-        })
+        // CGPreflightScreenCaptureAccess reports the current grant without showing a prompt
+        if active_window::screen_capture_allowed() {
+            Ok(PermissionStatus {
+                has_permission: true,
+                message: "Screen recording permission granted".to_string(),
+            })
+        } else {
+            Ok(PermissionStatus {
+                has_permission: false,
+                message: SCREEN_RECORDING_PERMISSION_MESSAGE.to_string(),
+            })
+        }
     }
 
     #[cfg(not(target_os = "macos"))]
@@ -471,90 +6706,369 @@ fn check_screen_recording_permission() -> Result<PermissionStatus, String> {
     }
 }
 
+/// Run every setup check a first recording depends on, for an onboarding/readiness panel
+/// Each check is reported separately; one failing doesn't stop the others from running
+#[tauri::command]
+fn run_healthcheck() -> HealthReport {
+    fn check(name: &str, status: HealthStatus, message: impl Into<String>) -> HealthCheck {
+        HealthCheck { name: name.to_string(), status, message: message.into() }
+    }
+
+    let mut checks = Vec::new();
+
+    let ffmpeg_ok = match validate_ffmpeg() {
+        Ok(info) => {
+            checks.push(check("ffmpeg", HealthStatus::Ok, format!("FFmpeg {} at {}", info.version, info.path)));
+            true
+        }
+        Err(e) => {
+            checks.push(check("ffmpeg", HealthStatus::Failed, e.message()));
+            false
+        }
+    };
+
+    // Device listing needs FFmpeg, so don't report the same failure twice
+    if ffmpeg_ok {
+        checks.push(match list_audio_devices() {
+            Ok(list) if list.devices.is_empty() => {
+                check("devices", HealthStatus::Warning, "Device listing works but no microphones were found")
+            }
+            Ok(list) => check("devices", HealthStatus::Ok, format!("{} audio device(s) found", list.devices.len())),
+            Err(e) => check("devices", HealthStatus::Failed, e.message()),
+        });
+    }
+
+    checks.push(match check_screen_recording_permission() {
+        Ok(permission) if permission.has_permission => check("screen_permission", HealthStatus::Ok, permission.message),
+        Ok(permission) => check("screen_permission", HealthStatus::Failed, permission.message),
+        Err(e) => check("screen_permission", HealthStatus::Failed, e.message()),
+    });
+
+    checks.push(match check_microphone_permission() {
+        Ok(permission) if permission.has_permission => check("microphone_permission", HealthStatus::Ok, permission.message),
+        // Only the frontend can query this, so it isn't a hard failure
+        Ok(permission) => check("microphone_permission", HealthStatus::Warning, permission.message),
+        Err(e) => check("microphone_permission", HealthStatus::Warning, e.message()),
+    });
+
+    let probe_path = recordings_dir().join("clipforge-healthcheck");
+    checks.push(match ensure_output_dir_writable(&probe_path.to_string_lossy()) {
+        Ok(()) => check("output_dir", HealthStatus::Ok, format!("{} is writable", recordings_dir().display())),
+        Err(e) => check("output_dir", HealthStatus::Failed, e.message()),
+    });
+
+    HealthReport {
+        ready: checks.iter().all(|check| check.status != HealthStatus::Failed),
+        checks,
+    }
+}
+
+/// Record a few seconds with the given settings, check the file has the expected streams and a
+/// nonzero duration, then delete it; lets new users confirm their settings work before a real take
+/// Records the webcam when `webcam_device_index` is set, the screen at `screen_index` otherwise
+/// The test take isn't listed by list_active_recordings, stopped by the recording hotkey, or reused by it
+/// Failures to start or stop the recording are reported as a failed test rather than an error
+#[tauri::command(async)]
+fn test_recording(app: tauri::AppHandle, settings: RecordingSettings) -> Result<TestResult, ClipForgeError> {
+    let extension = settings.container.as_deref().unwrap_or("mp4").trim_start_matches('.').to_ascii_lowercase();
+    let output_path = std::env::temp_dir()
+        .join(format!("clipforge-test-{}.{}", unix_timestamp(), extension))
+        .to_string_lossy()
+        .to_string();
+
+    let started = match settings.webcam_device_index {
+        Some(device_index) => start_webcam_recording_with(app, WebcamRecordingOptions {
+            output_path: Some(output_path.clone()),
+            device_index: Some(device_index),
+            audio_device_index: settings.audio_device_index,
+            quality: settings.quality.clone(),
+            crf: settings.crf,
+            framerate: settings.framerate,
+            container: settings.container.clone(),
+            bitrate: settings.bitrate.clone(),
+            video_size: settings.video_size.clone(),
+            internal: true,
+            ..Default::default()
+        }),
+        None => start_screen_recording_with(app, ScreenRecordingOptions {
+            output_path: Some(output_path.clone()),
+            audio_device_index: settings.audio_device_index,
+            quality: settings.quality.clone(),
+            crf: settings.crf,
+            framerate: settings.framerate,
+            container: settings.container.clone(),
+            bitrate: settings.bitrate.clone(),
+            screen_index: settings.screen_index,
+            internal: true,
+            ..Default::default()
+        }),
+    };
+    let process_id = match started {
+        Ok(recording) => recording.process_id,
+        Err(e) => return Ok(failed_test(format!("Recording could not start: {}", e))),
+    };
+
+    std::thread::sleep(std::time::Duration::from_secs(TEST_RECORDING_SECONDS));
+
+    let stopped = stop_recording_process(process_id, std::time::Duration::from_millis(DEFAULT_FLUSH_TIMEOUT_MS), false);
+    let result = match stopped {
+        Ok(stopped) if !stopped.clean_shutdown => failed_test("FFmpeg did not stop cleanly, so recordings may come out corrupt"),
+        Ok(_) => check_test_recording(&output_path, settings.audio_device_index.is_some()),
+        Err(e) => failed_test(format!("Recording could not be stopped: {}", e)),
+    };
+
+    let _ = std::fs::remove_file(&output_path);
+    Ok(result)
+}
+
+fn failed_test(message: impl Into<String>) -> TestResult {
+    TestResult {
+        passed: false,
+        message: message.into(),
+        duration_seconds: None,
+        video_codec: None,
+        audio_codec: None,
+    }
+}
+
+/// Probe a test recording for a video stream, an audio stream if one was requested, and a duration
+fn check_test_recording(path: &str, expect_audio: bool) -> TestResult {
+    let streams = match probe_stream_codecs(path) {
+        Ok(streams) => streams,
+        Err(e) => return failed_test(format!("The recording could not be read: {}", e)),
+    };
+    let codec = |codec_type: &str| streams.iter().find(|(kind, _)| kind == codec_type).map(|(_, codec)| codec.clone());
+    let video_codec = codec("video");
+    let audio_codec = codec("audio");
+    let duration_seconds = probe_duration_seconds(path).filter(|duration| *duration > 0.0);
+
+    let problem = if video_codec.is_none() {
+        Some("The recording has no video stream".to_string())
+    } else if expect_audio && audio_codec.is_none() {
+        Some("The recording has no audio stream; check the microphone and its permission".to_string())
+    } else if duration_seconds.is_none() {
+        Some("The recording has no playable duration".to_string())
+    } else {
+        None
+    };
+
+    TestResult {
+        passed: problem.is_none(),
+        message: problem.unwrap_or_else(|| match &audio_codec {
+            Some(audio_codec) => format!("Recorded {} video with {} audio", video_codec.as_deref().unwrap_or_default(), audio_codec),
+            None => format!("Recorded {} video", video_codec.as_deref().unwrap_or_default()),
+        }),
+        duration_seconds,
+        video_codec,
+        audio_codec,
+    }
+}
+
+/// Whether an audio device name matches a known virtual/loopback driver
+/// These carry desktop audio rather than a microphone
+fn is_virtual_audio_device(name: &str) -> bool {
+    let name = name.to_lowercase();
+    VIRTUAL_AUDIO_DEVICE_PATTERNS.iter().any(|pattern| name.contains(pattern))
+}
+
 /// List available audio devices (microphones) using FFmpeg
 #[tauri::command]
-fn list_audio_devices() -> Result<AudioDeviceList, String> {
+fn list_audio_devices() -> Result<AudioDeviceList, ClipForgeError> {
     // Check if FFmpeg is available
-    let ffmpeg_check = Command::new("ffmpeg")
+    let ffmpeg_check = Command::new(ffmpeg_binary())
         .arg("-version")
-        .output();
+        .logged_output();
     
     match ffmpeg_check {
         Ok(_) => {},
-        Err(_) => return Err("FFmpeg is not installed or not found in PATH. Please install FFmpeg to list audio devices.".to_string()),
+        Err(_) => return Err(ClipForgeError::ffmpeg_not_found("FFmpeg is not installed or not found in PATH. Please install FFmpeg to list audio devices.")),
     }
 
-    // Run FFmpeg to list devices
-    // FFmpeg outputs device list to stderr (not stdout)
-    let output = Command::new("ffmpeg")
-        .arg("-f")
-        .arg("avfoundation")
-        .arg("-list_devices")
-        .arg("true")
-        .arg("-i")
-        .arg("")
-        .output()
-        .map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+    let backend = device_backend::current();
+    let devices = backend
+        .list_devices()?
+        .audio
+        .into_iter()
+        .map(|device| AudioDevice {
+            index: device.index,
+            is_virtual: is_virtual_audio_device(&device.name),
+            name: device.name,
+        })
+        .collect::<Vec<_>>();
+    log::debug!("Found {} audio devices via {}", devices.len(), backend.name());
 
-    // Parse stderr for audio devices
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let mut devices = Vec::new();
-    
-    // FFmpeg output format for avfoundation:
-    // [AVFoundation indev @ ...] AVFoundation audio devices:
-    // [AVFoundation indev @ ...] [0] MacBook Air Microphone
-    // [AVFoundation indev @ ...] [1] External Microphone
-    // etc.
-    // We look for lines that contain "[AVFoundation indev" and have an index followed by audio device names
-    let mut in_audio_section = false;
-    
-    for line in stderr.lines() {
-        // Check if we're entering the audio devices section
-        if line.contains("AVFoundation audio devices:") {
-            in_audio_section = true;
-            continue;
+    Ok(AudioDeviceList { devices })
+}
+
+/// Indices of the video capture devices (on macOS, cameras and "Capture screen N" entries)
+fn list_video_device_indices() -> Result<Vec<u32>, ClipForgeError> {
+    Ok(device_backend::current().list_devices()?.video.iter().map(|device| device.index).collect())
+}
+
+/// avfoundation device named "Capture screen `screen_index`" in a fresh device listing
+/// None when the listing fails or has no such entry (e.g. on other platforms)
+fn screen_capture_device(screen_index: u32) -> Option<u32> {
+    let name = format!("Capture screen {}", screen_index);
+    let devices = device_backend::current()
+        .list_devices()
+        .map_err(|e| log::warn!("Failed to list devices to find {}: {}", name, e))
+        .ok()?;
+    devices.video.iter().find(|device| device.name == name).map(|device| device.index)
+}
+
+/// Devices to try, in order, for capturing screen `screen_index`: the listed device when there is one;
+/// otherwise screen 0 is usually device 4 but machines with fewer cameras list it earlier, so its
+/// neighbors are retried, and other screens are guessed from the offset
+fn screen_device_candidates(listed_screen_device: Option<u32>, screen_index: u32) -> Vec<u32> {
+    match listed_screen_device {
+        Some(device) => vec![device],
+        None if screen_index == 0 => SCREEN_DEVICE_CANDIDATES.to_vec(),
+        None => vec![SCREEN_CAPTURE_DEVICE_OFFSET + screen_index],
+    }
+}
+
+/// The webcam to record when none is requested: the first video device that isn't a
+/// "Capture screen N" entry, so a machine without cameras fails here instead of recording a screen
+fn default_webcam_device_index() -> Result<u32, ClipForgeError> {
+    device_backend::current()
+        .list_devices()?
+        .video
+        .iter()
+        .find(|device| !device.name.starts_with("Capture screen"))
+        .map(|device| device.index)
+        .ok_or_else(|| ClipForgeError::not_found("No webcam available"))
+}
+
+/// Check requested capture devices against a fresh device listing before spawning FFmpeg
+/// A missing device otherwise only surfaces as an opaque FFmpeg input error; an index that belongs
+/// to the other device type (e.g. a camera passed as the microphone) gets its own error
+fn validate_capture_devices(video_indices: &[u32], audio_indices: &[u32]) -> Result<(), ClipForgeError> {
+    let list_audio_indices = || -> Result<Vec<u32>, ClipForgeError> {
+        Ok(list_audio_devices()?.devices.iter().map(|device| device.index).collect())
+    };
+    if !video_indices.is_empty() {
+        check_device_indices(DeviceKind::Video, video_indices, &list_video_device_indices()?, list_audio_indices)?;
+    }
+    if !audio_indices.is_empty() {
+        check_device_indices(DeviceKind::Audio, audio_indices, &list_audio_indices()?, list_video_device_indices)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DeviceKind {
+    Video,
+    Audio,
+}
+
+impl DeviceKind {
+    fn name(self) -> &'static str {
+        match self {
+            DeviceKind::Video => "video",
+            DeviceKind::Audio => "audio",
         }
-        
-        // Check if we're leaving the audio section (entering video section or other section)
-        if line.contains("AVFoundation video devices:") || (line.contains("[AVFoundation indev") && !in_audio_section && devices.len() > 0) {
-            in_audio_section = false;
-            // Don't break - there might be more sections after video
-        }
-        
-        // Parse audio device lines
-        // Format: [AVFoundation indev @ ...] [INDEX] DEVICE_NAME
-        if in_audio_section && line.contains("[AVFoundation indev") && line.contains('[') {
-            // Find the last bracket pair which contains the index
-            // The format is: [AVFoundation indev @ ...] [INDEX] NAME
-            if let Some(last_open_bracket) = line.rfind('[') {
-                // Check if there's a closing bracket after the last open bracket
-                if let Some(closing_bracket) = line[last_open_bracket..].find(']') {
-                    let index_str = &line[last_open_bracket + 1..last_open_bracket + closing_bracket];
-                    if let Ok(index) = index_str.parse::<u32>() {
-                        // Extract device name (everything after the closing bracket, trimmed)
-                        let name_start = last_open_bracket + closing_bracket + 1;
-                        if name_start < line.len() {
-                            let name = line[name_start..].trim().to_string();
-                            if !name.is_empty() {
-                                devices.push(AudioDevice {
-                                    index,
-                                    name,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+    }
+
+    fn with_article(self) -> &'static str {
+        match self {
+            DeviceKind::Video => "a video",
+            DeviceKind::Audio => "an audio",
         }
     }
-    
-    Ok(AudioDeviceList { devices })
+
+    fn other(self) -> DeviceKind {
+        match self {
+            DeviceKind::Video => DeviceKind::Audio,
+            DeviceKind::Audio => DeviceKind::Video,
+        }
+    }
+
+    /// Parse a `kind` argument; cameras and screens are video devices, microphones audio ones
+    fn parse(kind: &str) -> Result<DeviceKind, ClipForgeError> {
+        match kind.to_ascii_lowercase().as_str() {
+            "video" | "camera" | "webcam" | "screen" => Ok(DeviceKind::Video),
+            "audio" | "microphone" | "mic" => Ok(DeviceKind::Audio),
+            other => Err(ClipForgeError::invalid_argument(format!(
+                "Unknown device kind '{}'. Expected \"video\" or \"audio\"",
+                other
+            ))),
+        }
+    }
+}
+
+/// Current index of the capture device called `name`, so saved settings can refer to devices by name
+/// and survive indices shifting when USB devices are plugged in or removed
+/// An exact name match wins; otherwise a single case-insensitive match is accepted
+#[tauri::command]
+fn resolve_device_index(name: String, kind: String) -> Result<u32, ClipForgeError> {
+    let kind = DeviceKind::parse(&kind)?;
+    let listing = device_backend::current().list_devices()?;
+    let devices = match kind {
+        DeviceKind::Video => listing.video,
+        DeviceKind::Audio => listing.audio,
+    };
+
+    if let Some(device) = devices.iter().find(|device| device.name == name) {
+        return Ok(device.index);
+    }
+    let matches: Vec<_> = devices.iter().filter(|device| device.name.eq_ignore_ascii_case(name.trim())).collect();
+    match matches.as_slice() {
+        [device] => return Ok(device.index),
+        [] => {}
+        _ => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Several {} devices match \"{}\" ignoring case; use the exact name",
+                kind.name(),
+                name
+            )));
+        }
+    }
+
+    let available = devices.iter().map(|device| format!("\"{}\"", device.name)).collect::<Vec<_>>();
+    Err(ClipForgeError::not_found(format!(
+        "No {} device named \"{}\" is connected. Available: {}",
+        kind.name(),
+        name,
+        if available.is_empty() { "none".to_string() } else { available.join(", ") }
+    )))
+}
+
+/// `list_other_kind` is only run for an index missing from `available`, to tell a wrong device
+/// type apart from an index that doesn't exist at all
+fn check_device_indices(
+    kind: DeviceKind,
+    requested: &[u32],
+    available: &[u32],
+    list_other_kind: impl FnOnce() -> Result<Vec<u32>, ClipForgeError>,
+) -> Result<(), ClipForgeError> {
+    let Some(&index) = requested.iter().find(|index| !available.contains(index)) else {
+        return Ok(());
+    };
+
+    // A failed second listing shouldn't hide the original problem
+    if list_other_kind().is_ok_and(|other| other.contains(&index)) {
+        return Err(ClipForgeError::invalid_argument(format!(
+            "Index {} is {} device, not {} device",
+            index,
+            kind.other().with_article(),
+            kind.with_article()
+        )));
+    }
+    if available.is_empty() {
+        return Err(ClipForgeError::not_found(format!("No {} devices available", kind.name())));
+    }
+    Err(ClipForgeError::not_found(format!(
+        "{} device index {} out of range ({} available)",
+        if kind == DeviceKind::Audio { "Audio" } else { "Video" },
+        index,
+        available.len()
+    )))
 }
 
 /// Check microphone permission status on macOS
 /// Note: Direct permission checking requires Objective-C/Swift interop, so this is a placeholder
 #[tauri::command]
-fn check_microphone_permission() -> Result<PermissionStatus, String> {
+fn check_microphone_permission() -> Result<PermissionStatus, ClipForgeError> {
     #[cfg(target_os = "macos")]
     {
         // On macOS, we can't directly check permissions from Rust without FFI
@@ -574,130 +7088,327 @@ fn check_microphone_permission() -> Result<PermissionStatus, String> {
         })
     }
 }
-
-/// Start simultaneous screen + webcam recording with picture-in-picture overlay
-/// Returns a process ID that can be used to stop the recording
-#[tauri::command]
-fn start_screen_webcam_recording(
+
+// Settings of start_screen_webcam_recording, taken by the command as one `options` object and built with
+// `..Default::default()` by the hotkey starter and internal callers
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ScreenWebcamRecordingOptions {
     output_path: Option<String>,
     webcam_device_index: Option<u32>,
     pip_position: Option<String>, // "bottom-right", "bottom-left", "top-right", "top-left"
-    _pip_size: Option<String>,      // e.g., "320:240" or "25%"
+    pip_size: Option<String>, // e.g., "320:240" or "25%"
+    pip_shape: Option<String>, // "rectangle" (default) or "circle"
     audio_device_index: Option<u32>,
-) -> Result<RecordingResult, String> {
+    quality: Option<String>, // "low", "medium", "high", "lossless"
+    crf: Option<u32>, // Explicit CRF (0-51), overrides the quality preset's CRF
+    framerate: Option<u32>, // Capture and output framerate (1-120), defaults to 30
+    auto_name_from_active_window: Option<bool>, // Name the file after the foreground window when output_path is not set
+    delay_seconds: Option<u64>, // Count down this many seconds (emitting recording-countdown) before starting
+    audio_gain_db: Option<f32>, // Boost or cut captured audio (-30 to +30 dB); ignored without an audio device
+    denoise: Option<bool>, // Suppress steady background noise (fans, hum) on captured audio
+    mirror: Option<bool>, // Flip the webcam horizontally
+    container: Option<String>, // "mp4" (default), "webm" (VP9 + Opus), or "mkv"
+    bitrate: Option<String>, // Target video bitrate such as "5M" (capped VBR); mutually exclusive with crf
+    watermark_text: Option<String>, // Text burned into the bottom-left corner
+    show_timestamp: Option<bool>, // Burn the wall-clock time into the bottom-right corner
+    audio_bitrate: Option<String>, // e.g. "64k"; defaults to 192k
+    audio_sample_rate: Option<u32>, // Hz; defaults to 48000
+    audio_channels: Option<u8>, // 1 (mono) or 2 (stereo); defaults to 2
+    dry_run: Option<bool>, // Return the FFmpeg command in `command` without starting anything
+    highlight_cursor: Option<bool>, // Draw a ring around the pointer whenever the mouse is clicked
+    show_cursor: Option<bool>, // Include the mouse pointer in the capture; defaults to true
+    video_codec: Option<String>, // "h264" (default), "hevc" (libx265; slow in software), or "hevc_videotoolbox" (hardware)
+    trim_start_ms: Option<u64>, // Drop this much from the start to hide garbled first frames; see DEFAULT_TRIM_START_MS, 0 disables
+    pixel_format: Option<String>, // Output pix_fmt, e.g. "yuv444p" for sharper small text; defaults to yuv420p
+    color_range: Option<String>, // "tv" (limited, default) or "pc" (full)
+    filename_template: Option<String>, // Name for generated paths, e.g. "{type} {date} {time}"; see expand_filename_template
+    detached: Option<bool>, // Keep FFmpeg running if the app quits; reattach_recordings picks it up again (macOS/Linux)
+    auto_framerate: Option<bool>, // Record at the display's refresh rate, capped at `framerate` (or 60 when not set)
+    low_priority: Option<bool>, // Run FFmpeg at a lowered CPU priority (nice 10 / BELOW_NORMAL) to keep the system responsive; may drop frames under load
+    keyframe_interval_seconds: Option<f64>, // Force a keyframe this often so later trims can cut close to the requested time
+    audio_device_indices: Option<Vec<u32>>, // More microphones, mixed with audio_device_index into one track (e.g. two interview mics)
+    overlay_image_path: Option<String>, // Logo composited over the video, e.g. a transparent PNG
+    overlay_position: Option<String>, // Corner for overlay_image_path: "top-right" (default), "top-left", "bottom-right", "bottom-left"
+    max_size_bytes: Option<u64>, // Stop automatically once the file reaches this size (FFmpeg -fs), e.g. for an upload cap
+    preview: Option<bool>, // Keep a small JPEG of the latest frame of the first screen only, not the composed frame, for get_preview_frame
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+    audio_highpass_hz: Option<u32>, // Cut the microphone below this frequency, e.g. 80 to remove rumble from voice
+    audio_lowpass_hz: Option<u32>, // Cut the microphone above this frequency, e.g. 12000 to tame hiss
+}
+
+/// Start simultaneous screen + webcam recording with picture-in-picture overlay
+/// Returns a process ID that can be used to stop the recording
+/// `pip_shape: "circle"` masks the webcam per pixel and costs more CPU than the default rectangle
+/// `highlight_cursor` rings the pointer only while the mouse is clicked (see start_screen_recording)
+/// Settings are passed as one `options` object keyed by the ScreenWebcamRecordingOptions field names, all optional
+#[tauri::command(async)]
+fn start_screen_webcam_recording(app: tauri::AppHandle, options: ScreenWebcamRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    start_screen_webcam_recording_with(app, options)
+}
+
+/// start_screen_webcam_recording with its settings already gathered into options
+fn start_screen_webcam_recording_with(app: tauri::AppHandle, options: ScreenWebcamRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    // Same settings with a fresh file name, for the recording hotkey
+    let starter: RecordingStarter = {
+        let options = ScreenWebcamRecordingOptions { output_path: None, dry_run: None, ..options.clone() };
+        std::sync::Arc::new(move |app| start_screen_webcam_recording_with(app, options.clone()))
+    };
+    let ScreenWebcamRecordingOptions {
+        output_path,
+        webcam_device_index,
+        pip_position,
+        pip_size: _pip_size,
+        pip_shape,
+        audio_device_index,
+        quality,
+        crf,
+        framerate,
+        auto_name_from_active_window,
+        delay_seconds,
+        audio_gain_db,
+        denoise,
+        mirror,
+        container,
+        bitrate,
+        watermark_text,
+        show_timestamp,
+        audio_bitrate,
+        audio_sample_rate,
+        audio_channels,
+        dry_run,
+        highlight_cursor,
+        show_cursor,
+        video_codec,
+        trim_start_ms,
+        pixel_format,
+        color_range,
+        filename_template,
+        detached,
+        auto_framerate,
+        low_priority,
+        keyframe_interval_seconds,
+        audio_device_indices,
+        overlay_image_path,
+        overlay_position,
+        max_size_bytes,
+        preview,
+        extra_args,
+        audio_highpass_hz,
+        audio_lowpass_hz,
+    } = options;
+
+    let (audio_device_index, extra_microphones) = resolve_microphones(audio_device_index, audio_device_indices)?;
+    let video_quality = resolve_video_quality(quality.as_deref(), crf, bitrate.as_deref())?
+        .with_pixel_format(pixel_format.as_deref(), color_range.as_deref())?;
+    let framerate = if auto_framerate.unwrap_or(false) {
+        resolve_auto_framerate(framerate, 0)?
+    } else {
+        resolve_framerate(framerate)?
+    };
+    let keyframe_args = keyframe_interval_args(keyframe_interval_seconds, framerate)?;
+    let size_limit_args = size_limit_args(max_size_bytes)?;
+    let (preview_path, preview_args) = preview_output(preview.unwrap_or(false));
+    let extra_args = resolve_extra_args(extra_args)?;
+    let image_overlay = image_overlay_filter(overlay_image_path.as_deref(), overlay_position.as_deref())?;
+    let trim_start_ms = resolve_trim_start_ms(trim_start_ms, true)?;
+    let audio_filters = build_audio_filters(audio_gain_db, denoise.unwrap_or(false), audio_highpass_hz, audio_lowpass_hz)?;
+    let container = resolve_container(container.as_deref(), video_codec.as_deref())?;
+    container.check_pixel_format(&video_quality)?;
+    let audio_encoding = resolve_audio_encoding(container.audio_codec, audio_bitrate.as_deref(), audio_sample_rate, audio_channels)?;
+    let (text_file, text_filters) = build_text_overlay_filters(&app, watermark_text.as_deref(), show_timestamp.unwrap_or(false), dry_run.unwrap_or(false))?;
+    let circle = match pip_shape.as_deref().unwrap_or("rectangle") {
+        "rectangle" => false,
+        "circle" => true,
+        other => {
+            return Err(ClipForgeError::invalid_argument(format!(
+                "Unknown pip_shape '{}'. Expected one of: rectangle, circle",
+                other
+            )));
+        }
+    };
+
+    // Calculate overlay position based on desired corner
+    let overlay_pos = corner_overlay_position("pip_position", pip_position.as_deref().unwrap_or("bottom-right"))?;
+
+    let dry_run = dry_run.unwrap_or(false);
+    let detached = detached.unwrap_or(false);
+    // A dry run skips the device listing, so it shows device 0 when none is requested
+    let webcam_idx = match webcam_device_index {
+        Some(index) => index,
+        None if dry_run => 0,
+        None => default_webcam_device_index()?,
+    };
+    if !dry_run {
+        validate_capture_devices(&[webcam_idx], &microphones_and(&[audio_device_index], &extra_microphones))?;
+    }
+
+    // Count down before picking the file name so the default timestamp reflects the actual start
+    if let (Some(delay), false) = (delay_seconds, dry_run) {
+        run_countdown(&app, delay)?;
+    }
+
     // Generate output path if not provided
     let output = if let Some(path) = output_path {
         path
     } else {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let temp_dir = std::env::temp_dir();
-        temp_dir
-            .join(format!("clipforge-pip-{}.mp4", timestamp))
-            .to_str()
-            .ok_or("Failed to create temp file path")?
-            .to_string()
+        default_recording_path("clipforge-pip", "screen_webcam", container.extension, auto_name_from_active_window.unwrap_or(false), filename_template.as_deref())?
     };
 
+    // Fail early on a missing or read-only folder instead of after FFmpeg starts
+    if !dry_run {
+        ensure_output_dir_writable(&output)?;
+    }
+
     // Check if FFmpeg is available
-    let ffmpeg_check = Command::new("ffmpeg")
+    let ffmpeg_check = Command::new(ffmpeg_binary())
         .arg("-version")
-        .output();
+        .logged_output();
     
     match ffmpeg_check {
         Ok(_) => {},
-        Err(_) => return Err("FFmpeg is not installed or not found in PATH. Please install FFmpeg to use screen recording.".to_string()),
+        Err(_) => return Err(ClipForgeError::ffmpeg_not_found("FFmpeg is not installed or not found in PATH. Please install FFmpeg to use screen recording.")),
     }
 
-    // Use device index 0 by default for webcam, or user-specified
-    let webcam_idx = webcam_device_index.unwrap_or(0);
-    
     // Build input device strings with optional audio
-    // Screen capture device: "4:audio_index" or "4:" if no audio
+    // Screen capture device "Capture screen 0" (usually 4), as "4:audio_index" or "4:" if no audio
+    let screen_device_index = if dry_run {
+        SCREEN_CAPTURE_DEVICE_OFFSET
+    } else {
+        screen_capture_device(0).unwrap_or(SCREEN_CAPTURE_DEVICE_OFFSET)
+    };
     let screen_device = if let Some(audio_idx) = audio_device_index {
-        format!("4:{}", audio_idx)
+        format!("{}:{}", screen_device_index, audio_idx)
     } else {
-        "4:".to_string()
+        format!("{}:", screen_device_index)
     };
     let webcam_device = format!("{}:", webcam_idx);
 
     // Default PiP settings
     let pip_width = "320";
     let pip_height = "240";
-    let position = pip_position.as_deref().unwrap_or("bottom-right");
-    
-    // Calculate overlay position based on desired corner
-    let overlay_pos = match position {
-        "bottom-right" => "W-w-10:H-h-10",  // 10px from right and bottom
-        "bottom-left" => "10:H-h-10",       // 10px from left and bottom
-        "top-right" => "W-w-10:10",         // 10px from right and top
-        "top-left" => "10:10",              // 10px from left and top
-        _ => "W-w-10:H-h-10",               // Default to bottom-right
-    };
+
+    // Webcam chain: flip before scaling so the overlay comes out mirrored as a whole
+    let mut webcam_filters: Vec<String> = Vec::new();
+    if mirror.unwrap_or(false) {
+        webcam_filters.push("hflip".to_string());
+    }
+    if circle {
+        // Center-crop to a square (portrait cameras too), then clear the alpha outside the inscribed circle
+        // geq evaluates an expression per pixel, so the circle costs noticeably more CPU than a rectangle
+        webcam_filters.push("crop='min(iw,ih)':'min(iw,ih)'".to_string());
+        webcam_filters.push(format!("scale={}:{}", pip_height, pip_height));
+        webcam_filters.push("format=rgba".to_string());
+        webcam_filters.push(
+            "geq=r='r(X,Y)':g='g(X,Y)':b='b(X,Y)':a='if(lte(hypot(X-W/2,Y-H/2),W/2),255,0)'".to_string(),
+        );
+    } else {
+        webcam_filters.push(format!("scale={}:{}", pip_width, pip_height));
+    }
 
     // Construct FFmpeg command with filter_complex for PiP overlay
     // Input 0: Screen capture (device 4)
     // Input 1: Webcam (device 0 or specified)
     // Filter: Scale webcam and overlay on screen
-    let mut cmd = Command::new("ffmpeg");
+    let mut cmd = Command::new(ffmpeg_binary());
     cmd.arg("-f")
         .arg("avfoundation")
         .arg("-capture_cursor")
-        .arg("1")  // Capture cursor on screen
+        .arg(if show_cursor.unwrap_or(true) { "1" } else { "0" })  // Capture cursor on screen
+        .arg("-capture_mouse_clicks")
+        .arg(if highlight_cursor.unwrap_or(false) { "1" } else { "0" })  // Ring around the pointer on clicks
         .arg("-framerate")
-        .arg("30")
+        .arg(framerate.to_string())
         .arg("-i")
         .arg(screen_device)  // Input 0: Screen
         .arg("-f")
         .arg("avfoundation")
         .arg("-framerate")
-        .arg("30")
+        .arg(framerate.to_string())
         .arg("-video_size")
         .arg("1280x720")  // Webcam resolution (will be scaled down)
         .arg("-i")
-        .arg(&webcam_device)  // Input 1: Webcam
-        .arg("-filter_complex")
-        .arg(format!(
-            "[1:v]scale={}:{}[webcam];[0:v][webcam]overlay={}[v]",
-            pip_width, pip_height, overlay_pos
-        ))
+        .arg(&webcam_device);  // Input 1: Webcam
+    add_microphone_inputs(&mut cmd, &extra_microphones);  // Inputs 2..: extra microphones
+
+    let mut filter_graph = format!(
+        "[1:v]{}[webcam];[0:v][webcam]overlay={}{}[v]",
+        webcam_filters.join(","),
+        overlay_pos,
+        // Text and logo go on the composited frame so they sit above the webcam bubble
+        text_filters.iter().chain(&image_overlay).map(|filter| format!(",{}", filter)).collect::<String>()
+    );
+    if !extra_microphones.is_empty() {
+        filter_graph.push(';');
+        filter_graph.push_str(&microphone_mix_filter(0, 2, extra_microphones.len(), &audio_filters));
+    }
+    cmd.arg("-filter_complex")
+        .arg(&filter_graph)
         .arg("-map")
         .arg("[v]");  // Map the filtered video output
     
     // Map audio from input 0 (screen input with audio) if audio device is provided
     if audio_device_index.is_some() {
         cmd.arg("-map")
-            .arg("0:a")  // Map audio from input 0
+            .arg(if extra_microphones.is_empty() { "0:a" } else { "[mic]" })  // Audio from input 0, or the mixed microphones
             .arg("-c:a")
-            .arg("aac")  // Audio codec
+            .arg(container.audio_codec)  // Audio codec
             .arg("-b:a")
-            .arg("192k")  // Audio bitrate (192 kbps)
+            .arg(&audio_encoding.bitrate)  // Audio bitrate (192k by default)
             .arg("-ar")
-            .arg("48000")  // Sample rate (48 kHz)
+            .arg(audio_encoding.sample_rate.to_string())  // Sample rate (48 kHz by default)
             .arg("-ac")
-            .arg("2");  // Stereo (2 channels)
+            .arg(audio_encoding.channels.to_string());  // Channels (stereo by default)
+
+        if !audio_filters.is_empty() && extra_microphones.is_empty() {
+            cmd.arg("-af").arg(audio_filters.join(","));
+        }
     }
-    
+
+    cmd.args(trim_start_args(trim_start_ms));
+
     cmd.arg("-r")
-        .arg("30")  // Output framerate
+        .arg(framerate.to_string())  // Output framerate
         .arg("-c:v")
-        .arg("libx264")  // Video codec
-        .arg("-preset")
-        .arg("fast")  // Encoding speed
-        .arg("-crf")
-        .arg("23")  // Quality
-        .arg("-pix_fmt")
-        .arg("yuv420p")  // Pixel format for compatibility
+        .arg(container.video_codec)  // Video codec
+        .args(container.tag_args())  // hvc1 tag for HEVC in mp4
+        .args(container.speed_args(&video_quality))  // Encoding speed
+        .args(container.rate_control_args(&video_quality))  // Quality: CRF or capped bitrate
+        .args(video_quality.pixel_format_args())  // Pixel format and color range
+        .args(&keyframe_args)  // GOP length
+        .args(&size_limit_args)  // Stop at max_size_bytes
+        .args(container.recording_mux_args())  // Fragmented mp4 survives a killed FFmpeg
+        .args(&extra_args)  // Caller's own flags, applied to the main output
         .arg("-y")  // Overwrite output file
         .arg(&output)
+        .args(&preview_args)  // Second output refreshing the preview JPEG
+        // Piped for the startup check; once tracked, StderrLog drains it so FFmpeg never blocks on a full pipe
         .stderr(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::null());
+        .stdout(std::process::Stdio::null())
+        .stdin(std::process::Stdio::piped());  // Stop sends FFmpeg's "q" command here
+
+    if detached {
+        detach_from_app(&mut cmd)?;
+    }
+
+    if low_priority.unwrap_or(false) {
+        lower_priority(&mut cmd);
+    }
+
+    if dry_run {
+        return Ok(RecordingResult {
+            process_id: 0,
+            output_path: output,
+            screen_device_index: Some(screen_device_index),
+            command: Some(command_argv(&cmd)),
+            framerate,
+            resolution: display_resolution(0),
+            audio_enabled: audio_device_index.is_some(),
+        });
+    }
 
     // Spawn the FFmpeg process
-    let mut child = cmd.spawn()
-        .map_err(|e| format!("Failed to start FFmpeg process: {}. Make sure FFmpeg is installed and available in PATH.", e))?;
+    let mut child = cmd.logged_spawn()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to start FFmpeg process: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
 
     // Give FFmpeg a moment to initialize
     std::thread::sleep(std::time::Duration::from_millis(200));
@@ -709,13 +7420,9 @@ fn start_screen_webcam_recording(
                 use std::io::Read;
                 let mut error_output = String::new();
                 let _ = stderr.read_to_string(&mut error_output);
-                if !error_output.is_empty() {
-                    format!("FFmpeg exited immediately with status {:?}. Error output: {}", status, error_output)
-                } else {
-                    format!("FFmpeg exited immediately with status {:?}", status)
-                }
+                immediate_exit_error(status, &error_output, true)
             } else {
-                format!("FFmpeg exited immediately with status {:?}", status)
+                ClipForgeError::process_failed(format!("FFmpeg exited immediately with status {:?}", status))
             };
             return Err(error_msg);
         }
@@ -723,42 +7430,794 @@ fn start_screen_webcam_recording(
             // Process is still running, good!
         }
         Err(e) => {
-            return Err(format!("Failed to check FFmpeg process status: {}", e));
+            return Err(ClipForgeError::internal(format!("Failed to check FFmpeg process status: {}", e)));
         }
     }
 
-    // Generate a unique process ID
-    let process_id = child.id();
+    // Key the recording by a ClipForge ID rather than the PID, which the OS can reuse
+    let process_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
 
     // Store the process handle and output path
-    let mut processes = RECORDING_PROCESSES.lock()
-        .map_err(|e| format!("Failed to lock recording processes: {}", e))?;
-    
-    processes.insert(process_id, (child, output.clone()));
+    track_recording(
+        process_id,
+        RecordingProcess::new(child, output.clone(), RecordingInfo::new("screen_webcam", framerate, audio_device_index, max_size_bytes).with_preview(preview_path).with_text_file(text_file)),
+        detached,
+    );
+
+    remember_recording_starter(starter);
+
+    Ok(RecordingResult {
+        process_id,
+        output_path: output,
+        screen_device_index: Some(screen_device_index),
+        command: None,
+        framerate,
+        resolution: display_resolution(0),
+        audio_enabled: audio_device_index.is_some(),
+    })
+}
+
+// Settings of start_dual_screen_recording, taken by the command as one `options` object and built with
+// `..Default::default()` by the hotkey starter and internal callers
+#[derive(Debug, Clone, Default, Deserialize)]
+struct DualScreenRecordingOptions {
+    left_index: u32,
+    right_index: u32,
+    output_path: Option<String>,
+    audio_device_index: Option<u32>,
+    quality: Option<String>, // "low", "medium", "high", "lossless"
+    crf: Option<u32>, // Explicit CRF (0-51), overrides the quality preset's CRF
+    framerate: Option<u32>, // Capture and output framerate (1-120), defaults to 30
+    output_height: Option<u32>, // Common height of both screens in the output, defaults to 1080
+    delay_seconds: Option<u64>, // Count down this many seconds (emitting recording-countdown) before starting
+    audio_gain_db: Option<f32>, // Boost or cut captured audio (-30 to +30 dB); ignored without an audio device
+    denoise: Option<bool>, // Suppress steady background noise (fans, hum) on captured audio
+    container: Option<String>, // "mp4" (default), "webm" (VP9 + Opus), or "mkv"
+    bitrate: Option<String>, // Target video bitrate such as "5M" (capped VBR); mutually exclusive with crf
+    video_codec: Option<String>, // "h264" (default), "hevc" (libx265; slow in software), or "hevc_videotoolbox" (hardware)
+    show_cursor: Option<bool>, // Include the mouse pointer in the capture; defaults to true
+    dry_run: Option<bool>, // Return the FFmpeg command in `command` without starting anything
+    trim_start_ms: Option<u64>, // Drop this much from the start to hide garbled first frames; see DEFAULT_TRIM_START_MS, 0 disables
+    pixel_format: Option<String>, // Output pix_fmt, e.g. "yuv444p" for sharper small text; defaults to yuv420p
+    color_range: Option<String>, // "tv" (limited, default) or "pc" (full)
+    filename_template: Option<String>, // Name for generated paths, e.g. "{type} {date} {time}"; see expand_filename_template
+    detached: Option<bool>, // Keep FFmpeg running if the app quits; reattach_recordings picks it up again (macOS/Linux)
+    low_priority: Option<bool>, // Run FFmpeg at a lowered CPU priority (nice 10 / BELOW_NORMAL) to keep the system responsive; may drop frames under load
+    keyframe_interval_seconds: Option<f64>, // Force a keyframe this often so later trims can cut close to the requested time
+    audio_device_indices: Option<Vec<u32>>, // More microphones, mixed with audio_device_index into one track (e.g. two interview mics)
+    overlay_image_path: Option<String>, // Logo composited over the video, e.g. a transparent PNG
+    overlay_position: Option<String>, // Corner for overlay_image_path: "top-right" (default), "top-left", "bottom-right", "bottom-left"
+    max_size_bytes: Option<u64>, // Stop automatically once the file reaches this size (FFmpeg -fs), e.g. for an upload cap
+    preview: Option<bool>, // Keep a small JPEG of the latest frame of the first screen only, not the composed frame, for get_preview_frame
+    extra_args: Option<Vec<String>>, // Extra FFmpeg arguments inserted verbatim before the output path; unchecked, so a wrong flag can break the command
+    audio_highpass_hz: Option<u32>, // Cut the microphone below this frequency, e.g. 80 to remove rumble from voice
+    audio_lowpass_hz: Option<u32>, // Cut the microphone above this frequency, e.g. 12000 to tame hiss
+}
+
+/// Start recording two screens side by side in one video
+/// `left_index`/`right_index` are screen numbers (0 = first screen), like get_display_framerates;
+/// both are scaled to a common height and joined with hstack. Audio is taken with the left screen
+/// Returns a process ID that can be used to stop the recording
+/// Settings are passed as one `options` object keyed by the DualScreenRecordingOptions field names, all optional except left_index and right_index
+#[tauri::command(async)]
+fn start_dual_screen_recording(app: tauri::AppHandle, options: DualScreenRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    start_dual_screen_recording_with(app, options)
+}
+
+/// start_dual_screen_recording with its settings already gathered into options
+fn start_dual_screen_recording_with(app: tauri::AppHandle, options: DualScreenRecordingOptions) -> Result<RecordingResult, ClipForgeError> {
+    // Same settings with a fresh file name, for the recording hotkey
+    let starter: RecordingStarter = {
+        let options = DualScreenRecordingOptions { output_path: None, dry_run: None, ..options.clone() };
+        std::sync::Arc::new(move |app| start_dual_screen_recording_with(app, options.clone()))
+    };
+    let DualScreenRecordingOptions {
+        left_index,
+        right_index,
+        output_path,
+        audio_device_index,
+        quality,
+        crf,
+        framerate,
+        output_height,
+        delay_seconds,
+        audio_gain_db,
+        denoise,
+        container,
+        bitrate,
+        video_codec,
+        show_cursor,
+        dry_run,
+        trim_start_ms,
+        pixel_format,
+        color_range,
+        filename_template,
+        detached,
+        low_priority,
+        keyframe_interval_seconds,
+        audio_device_indices,
+        overlay_image_path,
+        overlay_position,
+        max_size_bytes,
+        preview,
+        extra_args,
+        audio_highpass_hz,
+        audio_lowpass_hz,
+    } = options;
+
+    if left_index == right_index {
+        return Err(ClipForgeError::invalid_argument("left_index and right_index must be different screens"));
+    }
+    let output_height = output_height.unwrap_or(DEFAULT_DUAL_SCREEN_HEIGHT);
+    if output_height < 2 || !output_height.is_multiple_of(2) {
+        return Err(ClipForgeError::invalid_argument(format!("output_height must be an even number, got {}", output_height)));
+    }
+
+    let (audio_device_index, extra_microphones) = resolve_microphones(audio_device_index, audio_device_indices)?;
+    let video_quality = resolve_video_quality(quality.as_deref(), crf, bitrate.as_deref())?
+        .with_pixel_format(pixel_format.as_deref(), color_range.as_deref())?;
+    let framerate = resolve_framerate(framerate)?;
+    let keyframe_args = keyframe_interval_args(keyframe_interval_seconds, framerate)?;
+    let size_limit_args = size_limit_args(max_size_bytes)?;
+    let (preview_path, preview_args) = preview_output(preview.unwrap_or(false));
+    let extra_args = resolve_extra_args(extra_args)?;
+    let image_overlay = image_overlay_filter(overlay_image_path.as_deref(), overlay_position.as_deref())?;
+    let trim_start_ms = resolve_trim_start_ms(trim_start_ms, true)?;
+    let audio_filters = build_audio_filters(audio_gain_db, denoise.unwrap_or(false), audio_highpass_hz, audio_lowpass_hz)?;
+    let container = resolve_container(container.as_deref(), video_codec.as_deref())?;
+    container.check_pixel_format(&video_quality)?;
+    let audio_encoding = AudioEncoding::default();
+
+    let dry_run = dry_run.unwrap_or(false);
+    let detached = detached.unwrap_or(false);
+
+    // Open the devices the listing names "Capture screen N"; only guess from the offset without a listing
+    let screen_device_index = |screen_index: u32| {
+        let listed = if dry_run { None } else { screen_capture_device(screen_index) };
+        listed.unwrap_or(SCREEN_CAPTURE_DEVICE_OFFSET + screen_index)
+    };
+    let left_device_index = screen_device_index(left_index);
+    let right_device_index = screen_device_index(right_index);
+    if !dry_run {
+        validate_capture_devices(&[left_device_index, right_device_index], &microphones_and(&[audio_device_index], &extra_microphones))?;
+    }
+
+    // Count down before picking the file name so the default timestamp reflects the actual start
+    if let (Some(delay), false) = (delay_seconds, dry_run) {
+        run_countdown(&app, delay)?;
+    }
+
+    // Generate output path if not provided
+    let output = if let Some(path) = output_path {
+        path
+    } else {
+        default_recording_path("clipforge-dual-screen", "dual_screen", container.extension, false, filename_template.as_deref())?
+    };
+
+    // Fail early on a missing or read-only folder instead of after FFmpeg starts
+    if !dry_run {
+        ensure_output_dir_writable(&output)?;
+    }
+
+    // Check if FFmpeg is available
+    let ffmpeg_check = Command::new(ffmpeg_binary())
+        .arg("-version")
+        .logged_output();
+
+    match ffmpeg_check {
+        Ok(_) => {},
+        Err(_) => return Err(ClipForgeError::ffmpeg_not_found("FFmpeg is not installed or not found in PATH. Please install FFmpeg to use screen recording.")),
+    }
+
+    let left_device = match audio_device_index {
+        Some(audio_idx) => format!("{}:{}", left_device_index, audio_idx),
+        None => format!("{}:", left_device_index),
+    };
+    let capture_cursor = if show_cursor.unwrap_or(true) { "1" } else { "0" };
+
+    // Input 0: left screen (with optional audio), input 1: right screen
+    // Both are scaled to the same height (-2 keeps widths even) before stacking horizontally
+    let mut cmd = Command::new(ffmpeg_binary());
+    cmd.arg("-f")
+        .arg("avfoundation")
+        .arg("-capture_cursor")
+        .arg(capture_cursor)
+        .arg("-framerate")
+        .arg(framerate.to_string())
+        .arg("-i")
+        .arg(&left_device)  // Input 0: Left screen
+        .arg("-f")
+        .arg("avfoundation")
+        .arg("-capture_cursor")
+        .arg(capture_cursor)
+        .arg("-framerate")
+        .arg(framerate.to_string())
+        .arg("-i")
+        .arg(format!("{}:", right_device_index));  // Input 1: Right screen
+    add_microphone_inputs(&mut cmd, &extra_microphones);  // Inputs 2..: extra microphones
+
+    let mut filter_graph = format!(
+        "[0:v]scale=-2:{height}[left];[1:v]scale=-2:{height}[right];[left][right]hstack=inputs=2{logo}[v]",
+        height = output_height,
+        logo = image_overlay.map(|filter| format!(",{}", filter)).unwrap_or_default()
+    );
+    if !extra_microphones.is_empty() {
+        filter_graph.push(';');
+        filter_graph.push_str(&microphone_mix_filter(0, 2, extra_microphones.len(), &audio_filters));
+    }
+    cmd.arg("-filter_complex")
+        .arg(&filter_graph)
+        .arg("-map")
+        .arg("[v]");
+
+    if audio_device_index.is_some() {
+        cmd.arg("-map")
+            .arg(if extra_microphones.is_empty() { "0:a" } else { "[mic]" })  // Audio comes in with the left screen, or the mixed microphones
+            .arg("-c:a")
+            .arg(container.audio_codec)
+            .arg("-b:a")
+            .arg(&audio_encoding.bitrate)
+            .arg("-ar")
+            .arg(audio_encoding.sample_rate.to_string())
+            .arg("-ac")
+            .arg(audio_encoding.channels.to_string());
+
+        if !audio_filters.is_empty() && extra_microphones.is_empty() {
+            cmd.arg("-af").arg(audio_filters.join(","));
+        }
+    }
+
+    cmd.args(trim_start_args(trim_start_ms));
+
+    cmd.arg("-r")
+        .arg(framerate.to_string())  // Output framerate
+        .arg("-c:v")
+        .arg(container.video_codec)  // Video codec
+        .args(container.tag_args())  // hvc1 tag for HEVC in mp4
+        .args(container.speed_args(&video_quality))  // Encoding speed
+        .args(container.rate_control_args(&video_quality))  // Quality: CRF or capped bitrate
+        .args(video_quality.pixel_format_args())  // Pixel format and color range
+        .args(&keyframe_args)  // GOP length
+        .args(&size_limit_args)  // Stop at max_size_bytes
+        .args(container.recording_mux_args())  // Fragmented mp4 survives a killed FFmpeg
+        .args(&extra_args)  // Caller's own flags, applied to the main output
+        .arg("-y")  // Overwrite output file
+        .arg(&output)
+        .args(&preview_args)  // Second output refreshing the preview JPEG
+        // Piped for the startup check; once tracked, StderrLog drains it so FFmpeg never blocks on a full pipe
+        .stderr(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stdin(std::process::Stdio::piped());  // Stop sends FFmpeg's "q" command here
+
+    if detached {
+        detach_from_app(&mut cmd)?;
+    }
+
+    if low_priority.unwrap_or(false) {
+        lower_priority(&mut cmd);
+    }
+
+    if dry_run {
+        return Ok(RecordingResult {
+            process_id: 0,
+            output_path: output,
+            screen_device_index: Some(left_device_index),
+            command: Some(command_argv(&cmd)),
+            framerate,
+            // The width depends on both screens' aspect ratios, which aren't known up front
+            resolution: None,
+            audio_enabled: audio_device_index.is_some(),
+        });
+    }
+
+    // Spawn the FFmpeg process
+    let mut child = cmd.logged_spawn()
+        .map_err(|e| ClipForgeError::spawn(format!("Failed to start FFmpeg process: {}. Make sure FFmpeg is installed and available in PATH.", e), &e))?;
+
+    // Give FFmpeg a moment to initialize
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    // Check if process immediately crashed, e.g. because one of the screens doesn't exist
+    match child.try_wait() {
+        Ok(Some(status)) => {
+            let error_msg = if let Some(mut stderr) = child.stderr.take() {
+                use std::io::Read;
+                let mut error_output = String::new();
+                let _ = stderr.read_to_string(&mut error_output);
+                immediate_exit_error(status, &error_output, true)
+            } else {
+                ClipForgeError::process_failed(format!("FFmpeg exited immediately with status {:?}", status))
+            };
+            return Err(error_msg);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return Err(ClipForgeError::internal(format!("Failed to check FFmpeg process status: {}", e)));
+        }
+    }
+
+    // Key the recording by a ClipForge ID rather than the PID, which the OS can reuse
+    let process_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
+
+    track_recording(
+        process_id,
+        RecordingProcess::new(child, output.clone(), RecordingInfo::new("dual_screen", framerate, audio_device_index, max_size_bytes).with_preview(preview_path)),
+        detached,
+    );
+
+    remember_recording_starter(starter);
 
     Ok(RecordingResult {
         process_id,
         output_path: output,
+        screen_device_index: Some(left_device_index),
+        command: None,
+        framerate,
+        resolution: None,
+        audio_enabled: audio_device_index.is_some(),
     })
 }
 
+/// Payload of the `recording-countdown` event, emitted once per second before a delayed recording starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingCountdownEvent {
+    pub seconds_remaining: u64,
+}
+
+/// Payload of the `recording-hotkey-stopped` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHotkeyStoppedEvent {
+    pub process_id: u64,
+    pub file_path: String,
+}
+
+/// Payload of the `recording-auto-stopped-idle` event, emitted when a recording is stopped
+/// because the screen stayed unchanged for the configured inactivity period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingAutoStoppedEvent {
+    pub process_id: u64,
+    pub file_path: String,
+    pub idle_seconds: u64,
+}
+
+/// Stop every tracked recording so FFmpeg can finalize its files before the app quits
+/// All children are asked to stop at once and share one flush deadline; survivors are killed
+fn stop_all_recordings() {
+    // Detached recordings are meant to outlive the app; dropping the handle leaves FFmpeg running
+    let mut recordings: Vec<(u64, RecordingProcess)> = lock_recording_processes()
+        .drain()
+        .filter(|(_, recording)| !recording.detached)
+        .collect();
+
+    // Drop the pre-roll capture too so it doesn't outlive the app
+    if let Ok(mut buffer) = PREROLL_BUFFER.lock() {
+        if let Some(buffer) = buffer.take() {
+            let segment_dir = buffer.segment_dir.clone();
+            finish_preroll_capture(buffer);
+            let _ = std::fs::remove_dir_all(segment_dir);
+        }
+    }
+
+    if recordings.is_empty() {
+        return;
+    }
+
+    for (_, recording) in &mut recordings {
+        let _ = recording.child.request_stop();
+    }
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(EXIT_FLUSH_TIMEOUT_MS);
+    for (process_id, mut recording) in recordings {
+        while let Ok(None) = recording.child.try_wait() {
+            if std::time::Instant::now() >= deadline {
+                log::warn!("Recording process {} did not exit in time, killing it", process_id);
+                let _ = recording.child.kill();
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        let _ = recording.child.wait();
+        log::info!("Stopped recording {} on exit: {}", process_id, recording.output_path);
+    }
+}
+
+/// Periodically check every tracked recording process and reap the ones that have exited
+/// Runs forever on a background thread started from `run()`
+fn watch_recording_processes(app: tauri::AppHandle) {
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(RECORDING_WATCH_INTERVAL_MS));
+
+        // Only hold the lock long enough to pull exited processes out of the map,
+        // so stderr reads and event emission never block the recording commands
+        let (exited, size_limited) = {
+            let mut processes = lock_recording_processes();
+
+            let exited_ids: Vec<(u64, String)> = processes
+                .iter_mut()
+                .filter(|(_, recording)| !recording.size_limit_reached)
+                .filter_map(|(process_id, recording)| match recording.child.try_wait() {
+                    Ok(Some(status)) => Some((*process_id, status)),
+                    _ => None,
+                })
+                .collect();
+
+            // Recordings that stopped at max_size_bytes stay tracked so stopping them returns the file
+            let mut size_limited = Vec::new();
+            let mut crashed_ids = Vec::new();
+            for (process_id, status) in exited_ids {
+                let Some(recording) = processes.get_mut(&process_id) else {
+                    continue;
+                };
+                if reached_size_limit(&recording.info, &recording.output_path, recording.child.exit_success()) {
+                    recording.size_limit_reached = true;
+                    size_limited.push(RecordingSizeLimitEvent {
+                        process_id,
+                        output_path: recording.output_path.clone(),
+                        max_size_bytes: recording.info.max_size_bytes.unwrap_or_default(),
+                    });
+                } else {
+                    crashed_ids.push((process_id, status));
+                }
+            }
+
+            let exited = crashed_ids
+                .into_iter()
+                .filter_map(|(process_id, status)| {
+                    processes.remove(&process_id).map(|recording| {
+                        recording.info.remove_temp_files();
+                        (process_id, status, recording.stderr_log, recording.output_path, recording.detached)
+                    })
+                })
+                .collect::<Vec<_>>();
+            (exited, size_limited)
+        };
+
+        for event in size_limited {
+            log::info!("Recording {} reached its size limit of {} bytes", event.process_id, event.max_size_bytes);
+            if let Err(e) = app.emit("recording-size-limit-reached", event) {
+                log::error!("Failed to emit recording-size-limit-reached event: {}", e);
+            }
+        }
+
+        for (process_id, status, mut stderr_log, output_path, detached) in exited {
+            if detached {
+                forget_detached_recording(process_id);
+            }
+            // Dropping the claimed pre-roll removes its segment directory
+            if let Ok(mut preroll) = PREROLL_SEGMENTS.lock() {
+                preroll.remove(&process_id);
+            }
+
+            // The process has exited, so the stderr reader finishes right away
+            let stderr_output = stderr_log.finish();
+
+            log::error!("Recording process {} exited unexpectedly with status {}", process_id, status);
+
+            let event = RecordingCrashedEvent {
+                process_id,
+                output_path,
+                exit_status: status,
+                stderr: stderr_output,
+            };
+            if let Err(e) = app.emit("recording-crashed", event) {
+                log::error!("Failed to emit recording-crashed event: {}", e);
+            }
+        }
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .targets([
+                    Target::new(TargetKind::Stdout),
+                    Target::new(TargetKind::LogDir { file_name: Some(LOG_FILE_NAME.to_string()) }),
+                ])
+                .level(log::LevelFilter::Info)
+                .max_file_size(LOG_MAX_FILE_BYTES)
+                .rotation_strategy(RotationStrategy::KeepSome(LOG_ROTATED_FILES_KEPT))
+                .build(),
+        )
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .setup(|app| {
+            // Prefer an FFmpeg shipped inside the app bundle over the system one
+            if let Some(path) = detect_bundled_ffmpeg(app.handle()) {
+                if let Ok(mut bundled) = BUNDLED_FFMPEG_PATH.lock() {
+                    *bundled = Some(path);
+                }
+            }
+
+            // Reap recordings whose FFmpeg process dies after the startup check
+            let handle = app.handle().clone();
+            std::thread::spawn(move || watch_recording_processes(handle));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet, 
             export_video,
+            move_recording,
+            write_export_manifest,
+            mobile_split,
+            transcode_video,
+            export_gif,
+            strip_metadata,
+            extract_audio,
+            generate_chapter_thumbnails,
+            capture_screenshot,
+            segment_video,
+            set_ffmpeg_path,
+            get_ffmpeg_source,
+            get_log_path,
+            save_settings,
+            load_settings,
+            validate_ffmpeg,
             start_screen_recording,
             start_webcam_recording,
             start_screen_webcam_recording,
+            start_dual_screen_recording,
             stop_screen_recording,
+            list_active_recordings,
+            get_recordings_dir,
+            list_orphaned_recordings,
+            delete_recording,
+            get_recording_log,
+            recover_segments,
+            start_preroll_buffer,
+            stop_preroll_buffer,
+            cancel_recording_countdown,
+            register_recording_hotkey,
+            unregister_recording_hotkey,
+            get_display_framerates,
+            list_windows,
             check_screen_recording_permission,
             list_audio_devices,
-            check_microphone_permission
+            check_microphone_permission,
+            run_healthcheck,
+            append_recording,
+            get_encoding_stats,
+            remux_video,
+            list_camera_capabilities,
+            reattach_recordings,
+            queue_export,
+            list_export_jobs,
+            cancel_export_job,
+            set_export_concurrency,
+            wrap_with_intro_outro,
+            repair_recording,
+            measure_audio_level,
+            test_recording,
+            export_for_web,
+            rotate_video,
+            burn_subtitles,
+            set_default_output_dir,
+            get_default_output_dir,
+            get_preview_frame,
+            resolve_device_index,
+            export_videos
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            // Finalize in-progress recordings instead of orphaning FFmpeg on quit
+            if let tauri::RunEvent::Exit = event {
+                stop_all_recordings();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Stdio;
+
+    // Tests that use the global recording map run one at a time, since stop_all_recordings drains it
+    static RECORDING_MAP: Mutex<()> = Mutex::new(());
+
+    fn serialize_recording_map_tests() -> MutexGuard<'static, ()> {
+        RECORDING_MAP.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Stand-in for FFmpeg that exits as soon as it reads a line (the "q" command) on stdin
+    fn spawn_stoppable_child() -> std::process::Child {
+        Command::new("sh")
+            .arg("-c")
+            .arg("read line")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to spawn sh")
+    }
+
+    /// Stand-in for a detached FFmpeg that ignores stdin, so dropping its handle doesn't end it
+    fn spawn_long_running_child() -> std::process::Child {
+        Command::new("sleep")
+            .arg("30")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to spawn sleep")
+    }
+
+    /// Whether `pid` is still running; unlike recording_child::is_running, a killed process that
+    /// hasn't been reaped (a zombie) doesn't count
+    #[cfg(unix)]
+    fn is_alive(pid: u32) -> bool {
+        Command::new("ps")
+            .args(["-o", "stat=", "-p", &pid.to_string()])
+            .output()
+            .map(|output| {
+                let stat = String::from_utf8_lossy(&output.stdout);
+                let stat = stat.trim();
+                !stat.is_empty() && !stat.starts_with('Z')
+            })
+            .unwrap_or(false)
+    }
+
+    fn test_recording_info() -> RecordingInfo {
+        RecordingInfo::new("screen", 30, None, None)
+    }
+
+    #[test]
+    fn misspelled_pip_position_names_the_valid_corners() {
+        let error = corner_overlay_position("pip_position", "botom-right").unwrap_err();
+        let ClipForgeError::InvalidArgument { message } = error else {
+            panic!("expected InvalidArgument, got {:?}", error);
+        };
+        assert!(message.contains("pip_position 'botom-right'"), "{}", message);
+        for corner in ["bottom-right", "bottom-left", "top-right", "top-left"] {
+            assert!(message.contains(corner), "{} is missing from: {}", corner, message);
+        }
+    }
+
+    #[test]
+    fn filename_template_without_counter_does_not_reuse_an_existing_name() {
+        let dir = unique_temp_path("template-test");
+        let _dir = TempPathGuard::create_dir(dir.clone()).unwrap();
+
+        let first = expand_filename_template("demo {type}", "screen", "mp4", &dir).unwrap();
+        assert_eq!(first, dir.join("demo screen.mp4"));
+        std::fs::write(&first, b"").unwrap();
+
+        let second = expand_filename_template("demo {type}", "screen", "mp4", &dir).unwrap();
+        assert_eq!(second, dir.join("demo screen-2.mp4"));
+    }
+
+    #[test]
+    fn failed_exit_is_not_mistaken_for_the_size_limit() {
+        let file = TempPathGuard::write_file(unique_temp_path("size-limit-test"), &"x".repeat(2048)).unwrap();
+        let path = file.path().to_string_lossy().to_string();
+        let info = RecordingInfo::new("screen", 30, None, Some(2048));
+
+        assert!(reached_size_limit(&info, &path, Some(true)));
+        assert!(reached_size_limit(&info, &path, None));
+        assert!(!reached_size_limit(&info, &path, Some(false)));
+    }
+
+    #[test]
+    fn lock_recording_processes_recovers_from_poisoning() {
+        let _serial = serialize_recording_map_tests();
+        let _ = std::thread::spawn(|| {
+            let _processes = RECORDING_PROCESSES.lock().unwrap();
+            panic!("poison the recording map");
+        })
+        .join();
+        assert!(RECORDING_PROCESSES.is_poisoned());
+
+        let process_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
+        lock_recording_processes().insert(process_id, RecordingProcess::new(spawn_stoppable_child(), "poisoned.mp4".to_string(), test_recording_info()));
+        assert!(!RECORDING_PROCESSES.is_poisoned());
+
+        let mut recording = lock_recording_processes().remove(&process_id).expect("recording should still be tracked");
+        assert_eq!(recording.output_path, "poisoned.mp4");
+        let _ = recording.child.kill();
+        let _ = recording.child.wait();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stop_all_recordings_stops_attached_and_leaves_detached_running() {
+        let _serial = serialize_recording_map_tests();
+        let attached_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
+        let attached = spawn_stoppable_child();
+        let attached_pid = attached.id();
+        lock_recording_processes().insert(attached_id, RecordingProcess::new(attached, "attached.mp4".to_string(), test_recording_info()));
+
+        let detached_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::SeqCst);
+        let detached = spawn_long_running_child();
+        let detached_pid = detached.id();
+        let mut detached_recording = RecordingProcess::new(detached, "detached.mp4".to_string(), test_recording_info());
+        detached_recording.detached = true;
+        lock_recording_processes().insert(detached_id, detached_recording);
+
+        stop_all_recordings();
+
+        let processes = lock_recording_processes();
+        assert!(!processes.contains_key(&attached_id));
+        assert!(!processes.contains_key(&detached_id));
+        drop(processes);
+        assert!(!recording_child::is_running(attached_pid), "attached recording should have been stopped");
+        assert!(is_alive(detached_pid), "detached recording should be left running");
+
+        let _ = nix::sys::signal::kill(nix::unistd::Pid::from_raw(detached_pid as i32), nix::sys::signal::Signal::SIGKILL);
+    }
+
+    // astats output of a short stereo sample: per-channel sections, then the "Overall" one
+    const ASTATS_STDERR: &str = "\
+[Parsed_astats_0 @ 0x600001d2c000] Channel: 1
+[Parsed_astats_0 @ 0x600001d2c000] DC offset: 0.000012
+[Parsed_astats_0 @ 0x600001d2c000] Peak level dB: -18.204120
+[Parsed_astats_0 @ 0x600001d2c000] RMS level dB: -41.532904
+[Parsed_astats_0 @ 0x600001d2c000] RMS peak dB: -30.127374
+[Parsed_astats_0 @ 0x600001d2c000] RMS trough dB: -inf
+[Parsed_astats_0 @ 0x600001d2c000] Channel: 2
+[Parsed_astats_0 @ 0x600001d2c000] DC offset: 0.000009
+[Parsed_astats_0 @ 0x600001d2c000] Peak level dB: -inf
+[Parsed_astats_0 @ 0x600001d2c000] RMS level dB: -inf
+[Parsed_astats_0 @ 0x600001d2c000] RMS peak dB: -inf
+[Parsed_astats_0 @ 0x600001d2c000] RMS trough dB: -inf
+[Parsed_astats_0 @ 0x600001d2c000] Overall
+[Parsed_astats_0 @ 0x600001d2c000] DC offset: 0.000010
+[Parsed_astats_0 @ 0x600001d2c000] Peak level dB: -18.204120
+[Parsed_astats_0 @ 0x600001d2c000] RMS level dB: -44.543204
+[Parsed_astats_0 @ 0x600001d2c000] RMS peak dB: -30.127374
+[Parsed_astats_0 @ 0x600001d2c000] RMS trough dB: -inf
+[Parsed_astats_0 @ 0x600001d2c000] Number of samples: 24000
+";
+
+    #[test]
+    fn astats_level_reads_the_overall_section() {
+        let overall = &ASTATS_STDERR[ASTATS_STDERR.rfind("Overall").unwrap()..];
+        assert_eq!(astats_level(overall, "Peak level dB:"), Some(-18.20412));
+        // "RMS peak dB" comes later and must not be mistaken for the RMS level
+        assert_eq!(astats_level(overall, "RMS level dB:"), Some(-44.543204));
+        assert_eq!(astats_level(overall, "Flat factor:"), None);
+    }
+
+    #[test]
+    fn astats_level_treats_minus_infinity_as_no_level() {
+        let channel_2 = &ASTATS_STDERR[ASTATS_STDERR.find("Channel: 2").unwrap()..];
+        assert_eq!(astats_level(channel_2, "Peak level dB:"), None);
+        assert_eq!(astats_level(channel_2, "RMS level dB:"), None);
+    }
+
+    const PROGRESS_LINE: &str = "frame=  240 fps= 29.8 q=23.0 size=    1024kB time=00:00:08.00 bitrate=1048.6kbits/s dup=3 drop=12 speed=0.993x    ";
+
+    #[test]
+    fn progress_value_reads_padded_values() {
+        assert_eq!(progress_value(PROGRESS_LINE, "frame"), Some("240"));
+        assert_eq!(progress_value(PROGRESS_LINE, "fps"), Some("29.8"));
+        assert_eq!(progress_value(PROGRESS_LINE, "speed"), Some("0.993x"));
+    }
+
+    #[test]
+    fn progress_value_matches_whole_keys_only() {
+        assert_eq!(progress_value(PROGRESS_LINE, "drop"), Some("12"));
+        assert_eq!(progress_value(PROGRESS_LINE, "dup"), Some("3"));
+        // "rate=" appears only as the tail of "bitrate="
+        assert_eq!(progress_value(PROGRESS_LINE, "rate"), None);
+    }
+
+    #[test]
+    fn progress_value_treats_n_a_as_missing() {
+        let line = "frame=    0 fps=0.0 q=0.0 size=       0kB time=N/A bitrate=N/A speed=N/A    ";
+        assert_eq!(progress_value(line, "speed"), None);
+        assert_eq!(progress_value(line, "time"), None);
+        assert_eq!(progress_value(line, "frame"), Some("0"));
+        assert_eq!(progress_value(line, "drop"), None);
+    }
+
+    #[test]
+    fn segment_number_reads_the_trailing_digits() {
+        assert_eq!(segment_number(std::path::Path::new("/tmp/recording_1700000000_003.mp4")), Some(3));
+        assert_eq!(segment_number(std::path::Path::new("segment12.mkv")), Some(12));
+        assert_eq!(segment_number(std::path::Path::new("0042")), Some(42));
+        assert_eq!(segment_number(std::path::Path::new("recording_final.mp4")), None);
+    }
 }
@@ -0,0 +1,110 @@
+use std::io;
+use std::process::Child;
+
+// How often `wait` checks whether a reattached process has exited
+const REATTACHED_POLL_MS: u64 = 50;
+
+/// The FFmpeg process behind a recording
+/// Usually spawned by this instance of the app; a detached recording picked up again after a restart
+/// is only known by its PID, since it is no longer our child
+pub enum RecordingChild {
+    Spawned(Child),
+    Reattached(u32),
+}
+
+impl RecordingChild {
+    /// OS process ID
+    pub fn id(&self) -> u32 {
+        match self {
+            RecordingChild::Spawned(child) => child.id(),
+            RecordingChild::Reattached(pid) => *pid,
+        }
+    }
+
+    /// Like `Child::try_wait`, with the exit status formatted for logs and events
+    /// A reattached process isn't our child, so only the fact that it exited is known
+    pub fn try_wait(&mut self) -> io::Result<Option<String>> {
+        match self {
+            RecordingChild::Spawned(child) => Ok(child.try_wait()?.map(|status| format!("{:?}", status))),
+            RecordingChild::Reattached(pid) => Ok((!is_running(*pid)).then(|| "unknown (reattached process)".to_string())),
+        }
+    }
+
+    /// Whether the process exited successfully; None while it runs, and always for a reattached process,
+    /// whose exit status can't be read
+    pub fn exit_success(&mut self) -> Option<bool> {
+        match self {
+            RecordingChild::Spawned(child) => child.try_wait().ok().flatten().map(|status| status.success()),
+            RecordingChild::Reattached(_) => None,
+        }
+    }
+
+    /// Ask FFmpeg to finish writing the file and exit by sending its "q" command on stdin, which works on
+    /// every platform; reattached processes have no stdin pipe, so on Unix they get SIGINT instead
+    pub fn request_stop(&mut self) -> io::Result<()> {
+        match self {
+            RecordingChild::Spawned(child) => {
+                use std::io::Write;
+                let stdin = child.stdin.as_mut()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "FFmpeg's stdin is not connected"))?;
+                stdin.write_all(b"q\n")?;
+                stdin.flush()
+            }
+            RecordingChild::Reattached(pid) => interrupt(*pid),
+        }
+    }
+
+    /// Forcefully terminate the process
+    pub fn kill(&mut self) -> io::Result<()> {
+        match self {
+            RecordingChild::Spawned(child) => child.kill(),
+            RecordingChild::Reattached(pid) => kill(*pid),
+        }
+    }
+
+    /// Block until the process exits; see `try_wait` for the status
+    pub fn wait(&mut self) -> io::Result<String> {
+        match self {
+            RecordingChild::Spawned(child) => Ok(format!("{:?}", child.wait()?)),
+            RecordingChild::Reattached(_) => loop {
+                if let Some(status) = self.try_wait()? {
+                    return Ok(status);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(REATTACHED_POLL_MS));
+            },
+        }
+    }
+}
+
+/// Whether a process with this PID exists (signal 0 checks without delivering anything)
+#[cfg(unix)]
+pub fn is_running(pid: u32) -> bool {
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), None).is_ok()
+}
+
+#[cfg(not(unix))]
+pub fn is_running(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn interrupt(pid: u32) -> io::Result<()> {
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), nix::sys::signal::Signal::SIGINT)
+        .map_err(io::Error::from)
+}
+
+#[cfg(not(unix))]
+fn interrupt(_pid: u32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "reattached processes can only be killed on this platform"))
+}
+
+#[cfg(unix)]
+fn kill(pid: u32) -> io::Result<()> {
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), nix::sys::signal::Signal::SIGKILL)
+        .map_err(io::Error::from)
+}
+
+#[cfg(not(unix))]
+fn kill(_pid: u32) -> io::Result<()> {
+    Ok(())
+}
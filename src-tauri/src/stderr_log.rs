@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::io::Read;
+use std::process::ChildStderr;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+
+// Lines of FFmpeg output kept per recording; the oldest are dropped first
+const MAX_LINES: usize = 1000;
+
+/// FFmpeg stderr of a running recording, drained continuously on a background thread
+/// Reading as output arrives keeps FFmpeg from blocking on a full pipe during long recordings,
+/// and the most recent lines stay available for live diagnostics
+pub struct StderrLog {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl StderrLog {
+    /// Start draining `stderr`; each line is also sent to `forward` for as long as its receiver is alive
+    pub fn start(stderr: Option<ChildStderr>, forward: Option<Sender<String>>) -> Self {
+        let lines = Arc::new(Mutex::new(VecDeque::new()));
+        let reader = stderr.map(|stderr| {
+            let lines = Arc::clone(&lines);
+            std::thread::spawn(move || drain(stderr, &lines, forward))
+        });
+        StderrLog { lines, reader }
+    }
+
+    /// The lines captured so far, newline separated
+    pub fn contents(&self) -> String {
+        lock(&self.lines).iter().map(String::as_str).collect::<Vec<_>>().join("\n")
+    }
+
+    /// FFmpeg's most recent progress line, if it has printed one yet
+    pub fn latest_progress(&self) -> Option<String> {
+        lock(&self.lines).iter().rev().find(|line| is_progress_line(line)).cloned()
+    }
+
+    /// Wait for FFmpeg to close stderr, then return the captured lines
+    /// Only call once the process has exited, otherwise this blocks until it does
+    pub fn finish(&mut self) -> String {
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+        self.contents()
+    }
+}
+
+fn lock(lines: &Mutex<VecDeque<String>>) -> MutexGuard<'_, VecDeque<String>> {
+    lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Read until EOF, splitting on '\r' as well as '\n' since FFmpeg redraws its progress line in place
+fn drain(mut stderr: ChildStderr, lines: &Mutex<VecDeque<String>>, mut forward: Option<Sender<String>>) {
+    let mut buffer = [0u8; 4096];
+    let mut current: Vec<u8> = Vec::new();
+
+    loop {
+        let read = match stderr.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+
+        for &byte in &buffer[..read] {
+            if byte == b'\n' || byte == b'\r' {
+                if !current.is_empty() {
+                    push_line(lines, &mut forward, String::from_utf8_lossy(&current).into_owned());
+                    current.clear();
+                }
+            } else {
+                current.push(byte);
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        push_line(lines, &mut forward, String::from_utf8_lossy(&current).into_owned());
+    }
+}
+
+fn push_line(lines: &Mutex<VecDeque<String>>, forward: &mut Option<Sender<String>>, line: String) {
+    // Stop forwarding once the receiver is gone
+    if let Some(sender) = forward {
+        if sender.send(line.clone()).is_err() {
+            *forward = None;
+        }
+    }
+
+    let mut lines = lock(lines);
+
+    // Keep only the latest progress update so it doesn't push everything else out
+    if is_progress_line(&line) && lines.back().is_some_and(|last| is_progress_line(last)) {
+        lines.pop_back();
+    }
+    if lines.len() == MAX_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// FFmpeg's periodic "frame=... fps=... time=..." (or "size=..." for audio-only) status line
+fn is_progress_line(line: &str) -> bool {
+    line.starts_with("frame=") || line.starts_with("size=")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_only_the_latest_lines() {
+        // 3000 lines of about 40 bytes each, well over 64 KB
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("i=0; while [ $i -lt 3000 ]; do echo \"line $i padded out to forty bytes......\" >&2; i=$((i+1)); done")
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to spawn sh");
+        let mut log = StderrLog::start(child.stderr.take(), None);
+        child.wait().expect("sh did not exit");
+
+        let contents = log.finish();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), MAX_LINES);
+        assert!(lines[0].starts_with("line 2000 "), "oldest kept line was {:?}", lines[0]);
+        assert!(lines[MAX_LINES - 1].starts_with("line 2999 "), "newest line was {:?}", lines[MAX_LINES - 1]);
+    }
+}